@@ -9,9 +9,12 @@
 # GNU Radio version: 3.10.12.0
 
 from gnuradio import blocks
+from gnuradio import fft
 from gnuradio import gr
 from gnuradio.filter import firdes
 from gnuradio.fft import window
+import math
+from gnuradio import network
 import sys
 import signal
 from argparse import ArgumentParser
@@ -32,11 +35,18 @@ class untitled(gr.top_block):
         # Variables
         ##################################################
         self.samp_rate = samp_rate = 32000
+        self.fft_len = fft_len = 256
 
         ##################################################
         # Blocks
         ##################################################
 
+        self.network_udp_sink_0 = network.udp_sink(gr.sizeof_float, fft_len, '127.0.0.1', 17006, 0, (fft_len*4), False)
+        self.fft_vxx_0 = fft.fft_vcc(fft_len, True, window.blackmanharris(fft_len), True, 1)
+        self.blocks_stream_to_vector_0 = blocks.stream_to_vector(gr.sizeof_gr_complex*1, fft_len)
+        self.blocks_nlog10_ff_0 = blocks.nlog10_ff(10, fft_len, (-20*math.log10(fft_len)))
+        self.blocks_keep_one_in_n_0 = blocks.keep_one_in_n(gr.sizeof_float*fft_len, 8)
+        self.blocks_complex_to_mag_squared_0 = blocks.complex_to_mag_squared(fft_len)
         self.blocks_throttle2_0 = blocks.throttle( gr.sizeof_gr_complex*1, samp_rate, True, 0 if "auto" == "auto" else max( int(float(0.1) * samp_rate) if "auto" == "time" else int(0.1), 1) )
         self.blocks_null_source_0 = blocks.null_source(gr.sizeof_gr_complex*1)
         self.blocks_null_sink_0 = blocks.null_sink(gr.sizeof_gr_complex*1)
@@ -46,7 +56,13 @@ class untitled(gr.top_block):
         # Connections
         ##################################################
         self.connect((self.blocks_null_source_0, 0), (self.blocks_throttle2_0, 0))
+        self.connect((self.blocks_complex_to_mag_squared_0, 0), (self.blocks_keep_one_in_n_0, 0))
+        self.connect((self.blocks_keep_one_in_n_0, 0), (self.blocks_nlog10_ff_0, 0))
+        self.connect((self.blocks_nlog10_ff_0, 0), (self.network_udp_sink_0, 0))
+        self.connect((self.blocks_stream_to_vector_0, 0), (self.fft_vxx_0, 0))
         self.connect((self.blocks_throttle2_0, 0), (self.blocks_null_sink_0, 0))
+        self.connect((self.blocks_throttle2_0, 0), (self.blocks_stream_to_vector_0, 0))
+        self.connect((self.fft_vxx_0, 0), (self.blocks_complex_to_mag_squared_0, 0))
 
 
     def get_samp_rate(self):
@@ -56,6 +72,12 @@ class untitled(gr.top_block):
         self.samp_rate = samp_rate
         self.blocks_throttle2_0.set_sample_rate(self.samp_rate)
 
+    def get_fft_len(self):
+        return self.fft_len
+
+    def set_fft_len(self, fft_len):
+        self.fft_len = fft_len
+
 
 
 
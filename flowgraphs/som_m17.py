@@ -21,6 +21,7 @@ from gnuradio import filter
 from gnuradio.filter import firdes
 from gnuradio import gr
 from gnuradio.fft import window
+import os
 import sys
 import signal
 from PyQt5 import Qt
@@ -33,6 +34,7 @@ from gnuradio import vocoder
 from gnuradio.vocoder import codec2
 import sip
 import threading
+import time
 
 
 
@@ -76,12 +78,29 @@ class receiverRTLSDR(gr.top_block, Qt.QWidget):
         self.samp_rate = samp_rate = 48000
         self.rf_gain = rf_gain = 15
         self.ppm = ppm = 1.2
-        self.freq = freq = 433001750
+        self.freq = freq = int(os.environ.get("LINHT_RX_FREQ", 433001750))
+        self.rssi_offset = rssi_offset = -60
+        self.rssi = rssi = -120
 
         ##################################################
         # Blocks
         ##################################################
 
+        self.blocks_probe_signal_x_0 = blocks.probe_signal_f()
+        def _rssi_probe():
+          self.flowgraph_started.wait()
+          while True:
+
+            val = self.blocks_probe_signal_x_0.level()
+            try:
+              self.set_rssi(val)
+            except AttributeError:
+              pass
+            time.sleep(1.0 / (2))
+        _rssi_thread = threading.Thread(target=_rssi_probe)
+        _rssi_thread.daemon = True
+        _rssi_thread.start()
+
         _variable_qtgui_check_box_0_check_box = Qt.QCheckBox("AFC")
         self._variable_qtgui_check_box_0_choices = {True: 1.0, False: 0.0}
         self._variable_qtgui_check_box_0_choices_inv = dict((v,k) for k,v in self._variable_qtgui_check_box_0_choices.items())
@@ -307,6 +326,9 @@ class receiverRTLSDR(gr.top_block, Qt.QWidget):
         self.blocks_sub_xx_0 = blocks.sub_ff(1)
         self.blocks_stream_to_vector_0 = blocks.stream_to_vector(gr.sizeof_char*1, 64)
         self.blocks_short_to_float_0 = blocks.short_to_float(1, 32768.0)
+        self.blocks_nlog10_ff_0 = blocks.nlog10_ff(10, 1, rssi_offset)
+        self.blocks_moving_average_xx_1 = blocks.moving_average_ff((int(0.1*samp_rate)), (1/int(0.1*samp_rate)), 4000, 1)
+        self.blocks_complex_to_mag_squared_0 = blocks.complex_to_mag_squared(1)
         self.blocks_packed_to_unpacked_xx_0 = blocks.packed_to_unpacked_bb(1, gr.GR_MSB_FIRST)
         self.blocks_multiply_const_vxx_0 = blocks.multiply_const_ff(variable_qtgui_check_box_0)
         self.blocks_moving_average_xx_0 = blocks.moving_average_ff((int(0.1*samp_rate)), (1/int(0.1*samp_rate)), 4000, 1)
@@ -317,6 +339,10 @@ class receiverRTLSDR(gr.top_block, Qt.QWidget):
         ##################################################
         # Connections
         ##################################################
+        self.connect((self.blocks_complex_to_mag_squared_0, 0), (self.blocks_moving_average_xx_1, 0))
+        self.connect((self.blocks_moving_average_xx_1, 0), (self.blocks_nlog10_ff_0, 0))
+        self.connect((self.blocks_nlog10_ff_0, 0), (self.blocks_probe_signal_x_0, 0))
+        self.connect((self.rational_resampler_xxx_0_0, 0), (self.blocks_complex_to_mag_squared_0, 0))
         self.connect((self.analog_quadrature_demod_cf_0, 0), (self.blocks_moving_average_xx_0, 0))
         self.connect((self.analog_quadrature_demod_cf_0, 0), (self.blocks_sub_xx_0, 0))
         self.connect((self.blocks_moving_average_xx_0, 0), (self.blocks_multiply_const_vxx_0, 0))
@@ -353,6 +379,19 @@ class receiverRTLSDR(gr.top_block, Qt.QWidget):
         self._variable_qtgui_check_box_0_callback(self.variable_qtgui_check_box_0)
         self.blocks_multiply_const_vxx_0.set_k(self.variable_qtgui_check_box_0)
 
+    def get_rssi_offset(self):
+        return self.rssi_offset
+
+    def set_rssi_offset(self, rssi_offset):
+        self.rssi_offset = rssi_offset
+
+    def get_rssi(self):
+        return self.rssi
+
+    def set_rssi(self, rssi):
+        self.rssi = rssi
+        print(f"RSSI: {self.rssi:.1f}", flush=True)
+
     def get_samp_rate(self):
         return self.samp_rate
 
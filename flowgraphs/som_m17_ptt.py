@@ -30,7 +30,9 @@ from gnuradio import vocoder
 from gnuradio.vocoder import codec2
 from gnuradio import zeromq
 import numpy as np
+import os
 import threading
+import time
 
 
 
@@ -46,13 +48,32 @@ class som_m17_ptt(gr.top_block):
         ##################################################
         self.samp_rate = samp_rate = int(500e3)
         self.zmq_len = zmq_len = 2048
+        self.rssi_offset = rssi_offset = -60
+        self.rssi = rssi = -120
         self.ptt = ptt = 0
+        self.volume = volume = int(os.environ.get("LINHT_VOLUME", 100)) / 100.0
+        self.callsign = callsign = os.environ.get("LINHT_CALLSIGN", "OE3ANC")
         self.ch_flt = ch_flt = firdes.low_pass(1.0, samp_rate, 6.25e3, 6.25e3, window.WIN_HAMMING, 6.76)
 
         ##################################################
         # Blocks
         ##################################################
 
+        self.blocks_probe_signal_x_0 = blocks.probe_signal_f()
+        def _rssi_probe():
+          self.flowgraph_started.wait()
+          while True:
+
+            val = self.blocks_probe_signal_x_0.level()
+            try:
+              self.set_rssi(val)
+            except AttributeError:
+              pass
+            time.sleep(1.0 / (2))
+        _rssi_thread = threading.Thread(target=_rssi_probe)
+        _rssi_thread.daemon = True
+        _rssi_thread.start()
+
         self.zeromq_sub_msg_source_0 = zeromq.sub_msg_source('tcp://10.17.17.1:17001', 100, False)
         self.zeromq_pub_sink_2 = zeromq.pub_sink(gr.sizeof_short, zmq_len, 'tcp://*:17005', 100, False, (-1), '', True, True)
         self.vocoder_codec2_decode_ps_0 = vocoder.codec2_decode_ps(codec2.MODE_3200)
@@ -83,7 +104,7 @@ class som_m17_ptt(gr.top_block):
                 taps=[],
                 fractional_bw=0)
         self.m17_m17_decoder_0 = m17.m17_decoder(True,False,2.0,True,False,0,'','')
-        self.m17_m17_coder_0 = m17.m17_coder('OE3ANC','SP5WWP',1,1,0,0,0,0,"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",'','',False,False,'')
+        self.m17_m17_coder_0 = m17.m17_coder(callsign,'SP5WWP',1,1,0,0,0,0,"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",'','',False,False,'')
         self.freq_xlating_fir_filter_xxx_0 = filter.freq_xlating_fir_filter_ccc(1, ch_flt, 100e3, samp_rate)
         self.fir_filter_xxx_0 = filter.fir_filter_ccf(1, [0.004562185464, -0.003315159603, 0.004494660764, -0.005933215824, 0.007662642297, -0.009730151262, 0.012163462902, -0.015021624093, 0.018344736003, -0.022179571087, 0.026587935938, -0.031624350959, 0.037349001966, -0.043825719260, 0.051125585868, -0.059326237377, 0.068504390315, -0.078746186598, 0.090147725138, -0.102813489566, 0.116858526852, -0.132408962019, 0.149609118181, -0.168623734209, 0.189641904748, -0.212886091306, 0.238618491973, -0.267152027409, 0.298864858677, -0.334221364400, 0.373800820471, -0.418331953197, 0.468744199982, -0.526240588097, 0.592399581386, -0.669319857641, 0.759823522591, -0.867734659884, 0.998215908414, -1.158013367129, 1.354923496534, -1.593658456679, 1.856602265650, -2.021519762741, 1.526746272983, 1.526746272983, -2.021519762741, 1.856602265650, -1.593658456679, 1.354923496534, -1.158013367129, 0.998215908414, -0.867734659884, 0.759823522591, -0.669319857641, 0.592399581386, -0.526240588097, 0.468744199982, -0.418331953197, 0.373800820471, -0.334221364400, 0.298864858677, -0.267152027409, 0.238618491973, -0.212886091306, 0.189641904748, -0.168623734209, 0.149609118181, -0.132408962019, 0.116858526852, -0.102813489566, 0.090147725138, -0.078746186598, 0.068504390315, -0.059326237377, 0.051125585868, -0.043825719260, 0.037349001966, -0.031624350959, 0.026587935938, -0.022179571087, 0.018344736003, -0.015021624093, 0.012163462902, -0.009730151262, 0.007662642297, -0.005933215824, 0.004494660764, -0.003315159603, 0.004562185464])
         self.fir_filter_xxx_0.declare_sample_delay(0)
@@ -100,12 +121,18 @@ class som_m17_ptt(gr.top_block):
             128,
             [])
         self.blocks_vector_source_x_0 = blocks.vector_source_b((0xDE, 0xAD, 0xBE, 0xEF, 0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00), True, 1, [])
+        self.blocks_short_to_float_0 = blocks.short_to_float(1, 1)
+        self.blocks_float_to_short_0 = blocks.float_to_short(1, 1)
         self.blocks_stream_to_vector_3 = blocks.stream_to_vector(gr.sizeof_short*1, zmq_len)
         self.blocks_stream_to_vector_2 = blocks.stream_to_vector(gr.sizeof_char*1, 64)
         self.blocks_selector_0 = blocks.selector(gr.sizeof_gr_complex*1,ptt,ptt)
         self.blocks_selector_0.set_enabled(True)
+        self.blocks_nlog10_ff_0 = blocks.nlog10_ff(10, 1, rssi_offset)
+        self.blocks_moving_average_xx_1 = blocks.moving_average_ff((int(0.1*samp_rate)), (1/int(0.1*samp_rate)), 4000, 1)
+        self.blocks_complex_to_mag_squared_0 = blocks.complex_to_mag_squared(1)
         self.blocks_packed_to_unpacked_xx_0 = blocks.packed_to_unpacked_bb(1, gr.GR_MSB_FIRST)
         self.blocks_null_sink_0 = blocks.null_sink(gr.sizeof_char*1)
+        self.blocks_multiply_const_vxx_1 = blocks.multiply_const_ff(volume)
         self.blocks_multiply_const_vxx_0 = blocks.multiply_const_cc((0.5 + 0j))
         self.blocks_msgpair_to_var_0 = blocks.msg_pair_to_var(self.set_ptt)
         self.blocks_message_strobe_0 = blocks.message_strobe(pmt.intern("TEST"), 10000)
@@ -123,6 +150,10 @@ class som_m17_ptt(gr.top_block):
         ##################################################
         self.msg_connect((self.blocks_message_strobe_0, 'strobe'), (self.m17_m17_coder_0, 'end_of_transmission'))
         self.msg_connect((self.zeromq_sub_msg_source_0, 'out'), (self.blocks_msgpair_to_var_0, 'inpair'))
+        self.connect((self.blocks_complex_to_mag_squared_0, 0), (self.blocks_moving_average_xx_1, 0))
+        self.connect((self.blocks_moving_average_xx_1, 0), (self.blocks_nlog10_ff_0, 0))
+        self.connect((self.blocks_nlog10_ff_0, 0), (self.blocks_probe_signal_x_0, 0))
+        self.connect((self.freq_xlating_fir_filter_xxx_0, 0), (self.blocks_complex_to_mag_squared_0, 0))
         self.connect((self.analog_frequency_modulator_fc_0, 0), (self.rational_resampler_xxx_1, 0))
         self.connect((self.analog_quadrature_demod_cf_0, 0), (self.root_raised_cosine_filter_0, 0))
         self.connect((self.audio_source_0, 1), (self.blocks_float_to_complex_0, 1))
@@ -131,10 +162,13 @@ class som_m17_ptt(gr.top_block):
         self.connect((self.blocks_complex_to_float_0, 0), (self.audio_sink_0, 0))
         self.connect((self.blocks_correctiq_0, 0), (self.freq_xlating_fir_filter_xxx_0, 0))
         self.connect((self.blocks_float_to_complex_0, 0), (self.blocks_selector_0, 0))
+        self.connect((self.blocks_float_to_short_0, 0), (self.blocks_stream_to_vector_3, 0))
         self.connect((self.blocks_multiply_const_vxx_0, 0), (self.blocks_selector_0, 1))
+        self.connect((self.blocks_multiply_const_vxx_1, 0), (self.blocks_float_to_short_0, 0))
         self.connect((self.blocks_packed_to_unpacked_xx_0, 0), (self.blocks_stream_to_vector_2, 0))
         self.connect((self.blocks_selector_0, 1), (self.blocks_complex_to_float_0, 0))
         self.connect((self.blocks_selector_0, 0), (self.fir_filter_xxx_0, 0))
+        self.connect((self.blocks_short_to_float_0, 0), (self.blocks_multiply_const_vxx_1, 0))
         self.connect((self.blocks_stream_to_vector_2, 0), (self.vocoder_codec2_decode_ps_0, 0))
         self.connect((self.blocks_stream_to_vector_3, 0), (self.zeromq_pub_sink_2, 0))
         self.connect((self.blocks_vector_source_x_0, 0), (self.m17_m17_coder_0, 0))
@@ -148,8 +182,21 @@ class som_m17_ptt(gr.top_block):
         self.connect((self.rational_resampler_xxx_1, 0), (self.blocks_multiply_const_vxx_0, 0))
         self.connect((self.root_raised_cosine_filter_0, 0), (self.digital_symbol_sync_xx_0, 0))
         self.connect((self.root_raised_cosine_filter_1, 0), (self.analog_frequency_modulator_fc_0, 0))
-        self.connect((self.vocoder_codec2_decode_ps_0, 0), (self.blocks_stream_to_vector_3, 0))
+        self.connect((self.vocoder_codec2_decode_ps_0, 0), (self.blocks_short_to_float_0, 0))
+
+
+    def get_rssi_offset(self):
+        return self.rssi_offset
+
+    def set_rssi_offset(self, rssi_offset):
+        self.rssi_offset = rssi_offset
+
+    def get_rssi(self):
+        return self.rssi
 
+    def set_rssi(self, rssi):
+        self.rssi = rssi
+        print(f"RSSI: {self.rssi:.1f}", flush=True)
 
     def get_samp_rate(self):
         return self.samp_rate
@@ -171,6 +218,20 @@ class som_m17_ptt(gr.top_block):
         self.ptt = ptt
         self.blocks_selector_0.set_input_index(self.ptt)
         self.blocks_selector_0.set_output_index(self.ptt)
+        print(f"PTT: {int(self.ptt)}", flush=True)
+
+    def get_volume(self):
+        return self.volume
+
+    def set_volume(self, volume):
+        self.volume = volume
+        self.blocks_multiply_const_vxx_1.set_k(self.volume)
+
+    def get_callsign(self):
+        return self.callsign
+
+    def set_callsign(self, callsign):
+        self.callsign = callsign
 
     def get_ch_flt(self):
         return self.ch_flt
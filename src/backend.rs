@@ -0,0 +1,358 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use memmap2::{MmapMut, MmapOptions};
+use crate::framebuffer::{Bitfield, DeviceInfo, FramebufferError, PixelFormat, Rect};
+
+mod socket;
+mod spi;
+
+pub use socket::{SocketBackend, DEFAULT_SOCKET_PATH};
+pub use spi::{LcdCommand, SpiLcdBackend, SpiLcdConfig, ST7735_INIT};
+
+/// Output device the framebuffer canvas is flushed to
+///
+/// `Framebuffer` converts the canvas into the device pixel format and
+/// orientation, writes it into `buffer_mut` and then calls `present` with the
+/// device-space regions that changed.
+pub trait Backend: Send {
+    /// Geometry and pixel format of the device
+    fn device_info(&self) -> &DeviceInfo;
+    
+    /// Device pixel memory, at least `line_length * height` bytes
+    fn buffer_mut(&mut self) -> &mut [u8];
+    
+    /// Makes the updated regions of the buffer visible
+    fn present(&mut self, _regions: &[Rect]) -> Result<(), FramebufferError> {
+        Ok(())
+    }
+    
+    /// Number of pages the device cycles through, 2 when page flipping
+    ///
+    /// `buffer_mut` then returns a page that was last written `page_count`
+    /// flushes ago, so `Framebuffer` redraws the regions of the flushes in between.
+    fn page_count(&self) -> usize {
+        1
+    }
+    
+    /// Powers the panel down (`true`) or back up, keeping the buffer contents
+    fn set_blank(&mut self, _blank: bool) -> Result<(), FramebufferError> {
+        Ok(())
+    }
+    
+    /// Handles device events between frames, called regularly even when nothing is drawn
+    fn poll(&mut self) -> Result<(), FramebufferError> {
+        Ok(())
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FbBitfield {
+    offset: u32,
+    length: u32,
+    msb_right: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FbVarScreeninfo {
+    xres: u32,
+    yres: u32,
+    xres_virtual: u32,
+    yres_virtual: u32,
+    xoffset: u32,
+    yoffset: u32,
+    bits_per_pixel: u32,
+    grayscale: u32,
+    red: FbBitfield,
+    green: FbBitfield,
+    blue: FbBitfield,
+    transp: FbBitfield,
+    padding: [u8; 80],
+}
+
+#[repr(C)]
+struct FbFixScreeninfo {
+    id: [u8; 16],
+    smem_start: usize,
+    smem_len: u32,
+    type_: u32,
+    type_aux: u32,
+    visual: u32,
+    xpanstep: u16,
+    ypanstep: u16,
+    ywrapstep: u16,
+    line_length: u32,
+    padding: [u8; 32],
+}
+
+const FBIOGET_VSCREENINFO: libc::c_ulong = 0x4600;
+const FBIOGET_FSCREENINFO: libc::c_ulong = 0x4602;
+const FBIOPAN_DISPLAY: libc::c_ulong = 0x4606;
+const FBIOBLANK: libc::c_ulong = 0x4611;
+const FBIO_WAITFORVSYNC: libc::c_ulong = 0x4004_4620;
+
+/// Linux fbdev device, memory mapped so writes go straight to the screen
+///
+/// If the virtual resolution holds two screens and the driver can pan, frames
+/// are drawn into the hidden page and shown with FBIOPAN_DISPLAY, waiting for
+/// vertical sync where FBIO_WAITFORVSYNC is supported. Otherwise the visible
+/// page is written directly.
+pub struct FbdevBackend {
+    file: File,
+    mmap: MmapMut,
+    device_info: DeviceInfo,
+    var_info: FbVarScreeninfo,
+    /// Bytes from the start of one page to the next
+    page_size: usize,
+    /// Page currently being scanned out
+    front_page: usize,
+    page_flipping: bool,
+    vsync: bool,
+}
+
+impl FbdevBackend {
+    /// Opens and maps the framebuffer device at the given path
+    pub fn open(device_path: &str) -> Result<Self, FramebufferError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_SYNC)
+            .open(device_path)?;
+
+        let (var_info, fix_info) = Self::query_screen_info(&file)?;
+        let device_info = Self::device_info_from(&var_info, &fix_info)?;
+        
+        device_info.validate()?;
+
+        let mmap = unsafe {
+            MmapOptions::new()
+                .len(device_info.buffer_size)
+                .map_mut(&file)
+                .map_err(|e| FramebufferError::DeviceError(format!("Failed to mmap framebuffer: {}", e)))?
+        };
+
+        let page_size = (device_info.line_length as usize) * (device_info.height as usize);
+        let shown_page = (var_info.yoffset / var_info.yres) as usize;
+        
+        let mut backend = Self {
+            file,
+            mmap,
+            device_info,
+            var_info,
+            page_size,
+            front_page: 0,
+            page_flipping: false,
+            vsync: true,
+        };
+        
+        if (shown_page + 1) * page_size <= backend.mmap.len() {
+            backend.front_page = shown_page;
+        }
+        if var_info.yres_virtual >= 2 * var_info.yres
+            && backend.mmap.len() >= 2 * page_size
+            && fix_info.ypanstep != 0
+        {
+            backend.enable_page_flipping();
+        }
+
+        Ok(backend)
+    }
+    
+    fn enable_page_flipping(&mut self) {
+        self.front_page = self.front_page.min(1);
+        
+        // Panning to the page already on screen proves the driver supports it
+        if let Err(e) = self.pan_to(self.front_page) {
+            println!("[Framebuffer] Page flipping unavailable, copying instead: {}", e);
+            return;
+        }
+        
+        let front = self.page_range(self.front_page);
+        let back = self.page_range(1 - self.front_page);
+        self.mmap.copy_within(front, back.start);
+        self.page_flipping = true;
+        println!("[Framebuffer] Page flipping enabled");
+    }
+    
+    /// Whether frames are shown by panning between two pages
+    pub fn is_page_flipping(&self) -> bool {
+        self.page_flipping
+    }
+    
+    fn back_page(&self) -> usize {
+        if self.page_flipping { 1 - self.front_page } else { self.front_page }
+    }
+    
+    fn page_range(&self, page: usize) -> std::ops::Range<usize> {
+        page * self.page_size..(page + 1) * self.page_size
+    }
+    
+    fn pan_to(&mut self, page: usize) -> Result<(), FramebufferError> {
+        self.var_info.xoffset = 0;
+        self.var_info.yoffset = (page as u32) * self.var_info.yres;
+        
+        let result = unsafe {
+            libc::ioctl(self.file.as_raw_fd(), FBIOPAN_DISPLAY, &mut self.var_info as *mut _)
+        };
+        
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(FramebufferError::DeviceError(
+                format!("Failed to pan display: {}", io::Error::last_os_error())
+            ))
+        }
+    }
+    
+    /// Blocks until the next vertical blank, if the driver can report it
+    fn wait_for_vsync(&mut self) {
+        if !self.vsync {
+            return;
+        }
+        
+        let mut screen: u32 = 0;
+        let result = unsafe {
+            libc::ioctl(self.file.as_raw_fd(), FBIO_WAITFORVSYNC, &mut screen as *mut _)
+        };
+        if result != 0 {
+            println!("[Framebuffer] FBIO_WAITFORVSYNC unsupported: {}", io::Error::last_os_error());
+            self.vsync = false;
+        }
+    }
+    
+    fn query_screen_info(file: &File) -> Result<(FbVarScreeninfo, FbFixScreeninfo), FramebufferError> {
+        use std::mem;
+        
+        let fd = file.as_raw_fd();
+        
+        let mut var_info: FbVarScreeninfo = unsafe { mem::zeroed() };
+        let mut fix_info: FbFixScreeninfo = unsafe { mem::zeroed() };
+        
+        let var_result = unsafe {
+            libc::ioctl(fd, FBIOGET_VSCREENINFO, &mut var_info as *mut _)
+        };
+        
+        let fix_result = unsafe {
+            libc::ioctl(fd, FBIOGET_FSCREENINFO, &mut fix_info as *mut _)
+        };
+        
+        if var_result == 0 && fix_result == 0 {
+            Ok((var_info, fix_info))
+        } else {
+            Err(FramebufferError::DeviceError(
+                format!("Failed to query screen info: {}", io::Error::last_os_error())
+            ))
+        }
+    }
+    
+    fn device_info_from(var_info: &FbVarScreeninfo, fix_info: &FbFixScreeninfo) -> Result<DeviceInfo, FramebufferError> {
+        for field in [var_info.red, var_info.green, var_info.blue, var_info.transp] {
+            if field.msb_right != 0 {
+                return Err(FramebufferError::InvalidDeviceCapabilities(
+                    "MSB-right bitfields are not supported".to_string()
+                ));
+            }
+        }
+        
+        let bitfield = |field: FbBitfield| Bitfield::new(field.offset, field.length);
+        
+        Ok(DeviceInfo {
+            width: var_info.xres as u16,
+            height: var_info.yres as u16,
+            pixel_format: PixelFormat::new(
+                var_info.bits_per_pixel,
+                bitfield(var_info.red),
+                bitfield(var_info.green),
+                bitfield(var_info.blue),
+                bitfield(var_info.transp),
+            ),
+            line_length: fix_info.line_length,
+            buffer_size: fix_info.smem_len as usize,
+        })
+    }
+}
+
+impl Backend for FbdevBackend {
+    fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
+    
+    fn buffer_mut(&mut self) -> &mut [u8] {
+        let range = self.page_range(self.back_page());
+        &mut self.mmap[range]
+    }
+    
+    fn present(&mut self, _regions: &[Rect]) -> Result<(), FramebufferError> {
+        if !self.page_flipping {
+            return Ok(());
+        }
+        
+        let back = self.back_page();
+        self.pan_to(back)?;
+        self.wait_for_vsync();
+        self.front_page = back;
+        Ok(())
+    }
+    
+    fn page_count(&self) -> usize {
+        if self.page_flipping { 2 } else { 1 }
+    }
+    
+    fn set_blank(&mut self, blank: bool) -> Result<(), FramebufferError> {
+        const FB_BLANK_UNBLANK: libc::c_ulong = 0;
+        const FB_BLANK_POWERDOWN: libc::c_ulong = 4;
+        
+        let mode = if blank { FB_BLANK_POWERDOWN } else { FB_BLANK_UNBLANK };
+        let result = unsafe { libc::ioctl(self.file.as_raw_fd(), FBIOBLANK, mode) };
+        
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(FramebufferError::DeviceError(
+                format!("Failed to set blanking: {}", io::Error::last_os_error())
+            ))
+        }
+    }
+}
+
+/// Plain memory buffer standing in for a display, used for tests and screenshots
+pub struct MemoryBackend {
+    data: Vec<u8>,
+    device_info: DeviceInfo,
+}
+
+impl MemoryBackend {
+    pub fn new(width: u16, height: u16, pixel_format: PixelFormat) -> Self {
+        let line_length = (width as usize) * pixel_format.bytes_per_pixel();
+        let buffer_size = line_length * (height as usize);
+        
+        Self {
+            data: vec![0; buffer_size],
+            device_info: DeviceInfo {
+                width,
+                height,
+                pixel_format,
+                line_length: line_length as u32,
+                buffer_size,
+            },
+        }
+    }
+    
+    /// Raw device memory in the configured pixel format
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
+    
+    fn buffer_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
@@ -0,0 +1,207 @@
+// Stand-in display that streams frames to a desktop viewer over a Unix socket
+//
+// Every `present` sends one message to each connected viewer:
+//
+//   b"LHTF"  magic
+//   u16      screen width
+//   u16      screen height
+//   u16      number of regions, followed by that many regions:
+//     u16 x, u16 y, u16 width, u16 height
+//     width * height RGBA pixels, row by row
+//
+// All integers are little endian. A viewer that connects receives the whole
+// screen as a single region first, on the next `present` or `poll`. `tools/linht_viewer.py` shows the stream.
+
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::Backend;
+use crate::framebuffer::{DeviceInfo, FramebufferError, PixelFormat, Rect, BYTES_PER_PIXEL};
+
+/// Socket path used when none is configured
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/linht-display.sock";
+
+/// Viewers that cannot take a frame within this time are disconnected
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// In-memory RGBA screen mirrored to any number of socket viewers
+pub struct SocketBackend {
+    listener: UnixListener,
+    path: PathBuf,
+    viewers: Vec<UnixStream>,
+    data: Vec<u8>,
+    device_info: DeviceInfo,
+    /// Reused buffer the next message is assembled in
+    message: Vec<u8>,
+}
+
+impl SocketBackend {
+    /// Listens on `path` for viewers of a `width` x `height` screen
+    ///
+    /// A stale socket file left behind by a previous run is replaced.
+    pub fn bind<P: AsRef<Path>>(path: P, width: u16, height: u16) -> Result<Self, FramebufferError> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() && UnixStream::connect(&path).is_err() {
+            fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+
+        let pixel_format = PixelFormat::RGBA8888;
+        let line_length = (width as usize) * pixel_format.bytes_per_pixel();
+        let buffer_size = line_length * (height as usize);
+
+        println!("[Framebuffer] Streaming {}x{} frames on {}", width, height, path.display());
+        Ok(Self {
+            listener,
+            path,
+            viewers: Vec::new(),
+            data: vec![0; buffer_size],
+            device_info: DeviceInfo {
+                width,
+                height,
+                pixel_format,
+                line_length: line_length as u32,
+                buffer_size,
+            },
+            message: Vec::new(),
+        })
+    }
+
+    /// Number of viewers currently connected
+    pub fn viewer_count(&self) -> usize {
+        self.viewers.len()
+    }
+
+    fn accept_viewers(&mut self) -> io::Result<()> {
+        loop {
+            match self.listener.accept() {
+                Ok((mut stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+                    let screen = Rect::new(0, 0, self.device_info.width, self.device_info.height);
+                    self.encode(&[screen]);
+                    if stream.write_all(&self.message).is_ok() {
+                        println!("[Framebuffer] Viewer connected");
+                        self.viewers.push(stream);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn encode(&mut self, regions: &[Rect]) {
+        let line_length = self.device_info.line_length as usize;
+        let message = &mut self.message;
+        message.clear();
+
+        message.extend_from_slice(b"LHTF");
+        message.extend_from_slice(&self.device_info.width.to_le_bytes());
+        message.extend_from_slice(&self.device_info.height.to_le_bytes());
+        message.extend_from_slice(&(regions.len() as u16).to_le_bytes());
+
+        for rect in regions {
+            for value in [rect.x, rect.y, rect.width, rect.height] {
+                message.extend_from_slice(&value.to_le_bytes());
+            }
+            for y in rect.y..rect.bottom() as u16 {
+                let start = (y as usize) * line_length + (rect.x as usize) * BYTES_PER_PIXEL;
+                message.extend_from_slice(&self.data[start..start + (rect.width as usize) * BYTES_PER_PIXEL]);
+            }
+        }
+    }
+}
+
+impl Backend for SocketBackend {
+    fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
+
+    fn buffer_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    fn present(&mut self, regions: &[Rect]) -> Result<(), FramebufferError> {
+        if !self.viewers.is_empty() {
+            self.encode(regions);
+
+            let message = &self.message;
+            self.viewers.retain_mut(|viewer| match viewer.write_all(message) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("[Framebuffer] Viewer disconnected: {}", e);
+                    false
+                }
+            });
+        }
+
+        // Viewers joining now get the full screen, which already includes `regions`
+        self.accept_viewers()?;
+        Ok(())
+    }
+
+    fn poll(&mut self) -> Result<(), FramebufferError> {
+        self.accept_viewers()?;
+        Ok(())
+    }
+}
+
+impl Drop for SocketBackend {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn backend(dir: &tempfile::TempDir) -> SocketBackend {
+        let mut backend = SocketBackend::bind(dir.path().join("display.sock"), 4, 3).unwrap();
+        for (index, byte) in backend.buffer_mut().iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        backend
+    }
+
+    #[test]
+    fn encodes_regions_row_by_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = backend(&dir);
+        backend.encode(&[Rect::new(1, 1, 2, 2)]);
+
+        let mut expected = b"LHTF".to_vec();
+        for value in [4u16, 3, 1, 1, 1, 2, 2] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        // Rows start 16 bytes apart, the region one pixel in
+        expected.extend(20..28);
+        expected.extend(36..44);
+        assert_eq!(backend.message, expected);
+    }
+
+    #[test]
+    fn viewers_get_the_screen_without_a_redraw() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = backend(&dir);
+        let mut viewer = UnixStream::connect(dir.path().join("display.sock")).unwrap();
+        viewer.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+
+        backend.poll().unwrap();
+        assert_eq!(backend.viewer_count(), 1);
+
+        let mut message = vec![0; 18 + 4 * 3 * BYTES_PER_PIXEL];
+        viewer.read_exact(&mut message).unwrap();
+        assert_eq!(&message[..14], b"LHTF\x04\0\x03\0\x01\0\0\0\0\0");
+        assert_eq!(&message[14..18], &[4, 0, 3, 0]);
+        assert_eq!(message[18..], backend.data[..]);
+    }
+}
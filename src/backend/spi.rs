@@ -0,0 +1,357 @@
+// Raw SPI LCD panels driven through spidev, such as ST7735 modules
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use super::Backend;
+use crate::framebuffer::{DeviceInfo, FramebufferError, PixelFormat, Rect};
+
+/// One controller command with its parameters and the time to wait afterwards
+#[derive(Debug, Clone, Copy)]
+pub struct LcdCommand {
+    pub command: u8,
+    pub data: &'static [u8],
+    pub delay_ms: u16,
+}
+
+const fn cmd(command: u8, data: &'static [u8], delay_ms: u16) -> LcdCommand {
+    LcdCommand { command, data, delay_ms }
+}
+
+const SWRESET: u8 = 0x01;
+const SLPOUT: u8 = 0x11;
+const NORON: u8 = 0x13;
+const INVOFF: u8 = 0x20;
+const DISPOFF: u8 = 0x28;
+const DISPON: u8 = 0x29;
+const CASET: u8 = 0x2A;
+const RASET: u8 = 0x2B;
+const RAMWR: u8 = 0x2C;
+const MADCTL: u8 = 0x36;
+const COLMOD: u8 = 0x3A;
+
+/// Power-up sequence for ST7735R/S controllers in 16-bit color mode
+pub const ST7735_INIT: &[LcdCommand] = &[
+    cmd(SWRESET, &[], 150),
+    cmd(SLPOUT, &[], 120),
+    cmd(0xB1, &[0x01, 0x2C, 0x2D], 0),
+    cmd(0xB2, &[0x01, 0x2C, 0x2D], 0),
+    cmd(0xB3, &[0x01, 0x2C, 0x2D, 0x01, 0x2C, 0x2D], 0),
+    cmd(0xB4, &[0x07], 0),
+    cmd(0xC0, &[0xA2, 0x02, 0x84], 0),
+    cmd(0xC1, &[0xC5], 0),
+    cmd(0xC2, &[0x0A, 0x00], 0),
+    cmd(0xC3, &[0x8A, 0x2A], 0),
+    cmd(0xC4, &[0x8A, 0xEE], 0),
+    cmd(0xC5, &[0x0E], 0),
+    cmd(INVOFF, &[], 0),
+    cmd(COLMOD, &[0x05], 0),
+    cmd(0xE0, &[0x02, 0x1C, 0x07, 0x12, 0x37, 0x32, 0x29, 0x2D, 0x29, 0x25, 0x2B, 0x39, 0x00, 0x01, 0x03, 0x10], 0),
+    cmd(0xE1, &[0x03, 0x1D, 0x07, 0x06, 0x2E, 0x2C, 0x29, 0x2D, 0x2E, 0x2E, 0x37, 0x3F, 0x00, 0x00, 0x02, 0x10], 0),
+    cmd(NORON, &[], 10),
+    cmd(DISPON, &[], 100),
+];
+
+/// Largest single write; the spidev default `bufsiz` is 4096 bytes
+const MAX_TRANSFER: usize = 4096;
+
+/// Wiring and geometry of an SPI LCD panel
+#[derive(Debug, Clone)]
+pub struct SpiLcdConfig {
+    /// spidev node, e.g. `/dev/spidev0.0`
+    pub device: PathBuf,
+    pub width: u16,
+    pub height: u16,
+    pub speed_hz: u32,
+    /// GPIO number of the data/command select line
+    pub dc_gpio: u32,
+    pub reset_gpio: Option<u32>,
+    /// Offset of the visible area in controller memory, non-zero on some panel variants
+    pub x_offset: u16,
+    pub y_offset: u16,
+    /// Memory access control value selecting scan direction and RGB/BGR order
+    pub madctl: u8,
+    pub init: &'static [LcdCommand],
+}
+
+impl SpiLcdConfig {
+    /// A 160x128 ST7735 panel in landscape orientation with BGR color order
+    pub fn st7735<P: AsRef<Path>>(device: P, dc_gpio: u32) -> Self {
+        Self {
+            device: device.as_ref().to_path_buf(),
+            width: 160,
+            height: 128,
+            speed_hz: 16_000_000,
+            dc_gpio,
+            reset_gpio: None,
+            x_offset: 0,
+            y_offset: 0,
+            madctl: 0xA8,
+            init: ST7735_INIT,
+        }
+    }
+
+    /// Checks that the screen, moved by the offsets, lies within the controller's 16-bit addresses
+    pub fn validate(&self) -> Result<(), FramebufferError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(FramebufferError::InvalidDeviceCapabilities(
+                format!("SPI LCD size {}x{} is empty", self.width, self.height)
+            ));
+        }
+
+        let fits = |offset: u16, size: u16| offset.checked_add(size - 1).is_some();
+        if !fits(self.x_offset, self.width) || !fits(self.y_offset, self.height) {
+            return Err(FramebufferError::InvalidDeviceCapabilities(format!(
+                "SPI LCD offset {},{} moves the {}x{} screen past the last controller address",
+                self.x_offset, self.y_offset, self.width, self.height
+            )));
+        }
+        Ok(())
+    }
+
+    /// Column (CASET) and row (RASET) parameters selecting `rect`, inclusive end coordinates
+    ///
+    /// `rect` must be a non-empty area of the screen of a validated configuration.
+    fn window(&self, rect: &Rect) -> ([u8; 4], [u8; 4]) {
+        let x0 = rect.x + self.x_offset;
+        let y0 = rect.y + self.y_offset;
+        let x1 = x0 + (rect.width - 1);
+        let y1 = y0 + (rect.height - 1);
+
+        let [x0h, x0l] = x0.to_be_bytes();
+        let [x1h, x1l] = x1.to_be_bytes();
+        let [y0h, y0l] = y0.to_be_bytes();
+        let [y1h, y1l] = y1.to_be_bytes();
+        ([x0h, x0l, x1h, x1l], [y0h, y0l, y1h, y1l])
+    }
+}
+
+/// LCD controller on an SPI bus, updated region by region on `present`
+///
+/// Frames are kept in RGB565 in memory and sent to the controller's own
+/// display RAM, so only the changed regions travel over the bus.
+pub struct SpiLcdBackend {
+    spi: File,
+    dc: GpioLine,
+    config: SpiLcdConfig,
+    data: Vec<u8>,
+    device_info: DeviceInfo,
+    /// Reused buffer for byte-swapped pixel data
+    transfer: Vec<u8>,
+}
+
+impl SpiLcdBackend {
+    /// Opens the SPI device and GPIO lines and runs the panel's init sequence
+    pub fn open(config: SpiLcdConfig) -> Result<Self, FramebufferError> {
+        config.validate()?;
+        let spi = OpenOptions::new().read(true).write(true).open(&config.device)?;
+        configure_spi(&spi, config.speed_hz)?;
+
+        let dc = GpioLine::output(config.dc_gpio, false)?;
+        if let Some(pin) = config.reset_gpio {
+            let mut reset = GpioLine::output(pin, true)?;
+            reset.set(false)?;
+            thread::sleep(Duration::from_millis(10));
+            reset.set(true)?;
+            thread::sleep(Duration::from_millis(120));
+        }
+
+        let pixel_format = PixelFormat::RGB565;
+        let line_length = (config.width as usize) * pixel_format.bytes_per_pixel();
+        let buffer_size = line_length * (config.height as usize);
+        let device_info = DeviceInfo {
+            width: config.width,
+            height: config.height,
+            pixel_format,
+            line_length: line_length as u32,
+            buffer_size,
+        };
+
+        let mut backend = Self {
+            spi,
+            dc,
+            data: vec![0; buffer_size],
+            device_info,
+            transfer: Vec::new(),
+            config,
+        };
+
+        for step in backend.config.init {
+            backend.command(step.command, step.data)?;
+            if step.delay_ms > 0 {
+                thread::sleep(Duration::from_millis(step.delay_ms as u64));
+            }
+        }
+        let madctl = backend.config.madctl;
+        backend.command(MADCTL, &[madctl])?;
+
+        println!(
+            "[Framebuffer] SPI LCD {}x{} on {}",
+            backend.config.width,
+            backend.config.height,
+            backend.config.device.display()
+        );
+        Ok(backend)
+    }
+
+    fn command(&mut self, command: u8, data: &[u8]) -> io::Result<()> {
+        self.dc.set(false)?;
+        self.spi.write_all(&[command])?;
+
+        if !data.is_empty() {
+            self.dc.set(true)?;
+            for chunk in data.chunks(MAX_TRANSFER) {
+                self.spi.write_all(chunk)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the controller's write window to `rect`, inclusive end coordinates
+    fn set_window(&mut self, rect: &Rect) -> io::Result<()> {
+        let (columns, rows) = self.config.window(rect);
+        self.command(CASET, &columns)?;
+        self.command(RASET, &rows)
+    }
+
+    fn send_region(&mut self, rect: &Rect) -> io::Result<()> {
+        self.set_window(rect)?;
+        self.command(RAMWR, &[])?;
+        self.dc.set(true)?;
+
+        let line_length = self.device_info.line_length as usize;
+        let span = (rect.width as usize) * 2;
+        self.transfer.clear();
+
+        for y in rect.y..rect.bottom() as u16 {
+            let start = (y as usize) * line_length + (rect.x as usize) * 2;
+            // The controller expects each RGB565 pixel most significant byte first
+            for pixel in self.data[start..start + span].chunks_exact(2) {
+                let value = u16::from_ne_bytes([pixel[0], pixel[1]]);
+                self.transfer.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+
+        for chunk in self.transfer.chunks(MAX_TRANSFER) {
+            self.spi.write_all(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Backend for SpiLcdBackend {
+    fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
+
+    fn buffer_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    fn present(&mut self, regions: &[Rect]) -> Result<(), FramebufferError> {
+        for rect in regions.iter().filter(|rect| !rect.is_empty()) {
+            self.send_region(rect)?;
+        }
+        Ok(())
+    }
+
+    fn set_blank(&mut self, blank: bool) -> Result<(), FramebufferError> {
+        self.command(if blank { DISPOFF } else { DISPON }, &[])?;
+        Ok(())
+    }
+}
+
+/// Sets SPI mode 0, 8 bits per word and the clock rate
+fn configure_spi(spi: &File, speed_hz: u32) -> Result<(), FramebufferError> {
+    const SPI_IOC_WR_MODE: libc::c_ulong = 0x4001_6B01;
+    const SPI_IOC_WR_BITS_PER_WORD: libc::c_ulong = 0x4001_6B03;
+    const SPI_IOC_WR_MAX_SPEED_HZ: libc::c_ulong = 0x4004_6B04;
+
+    let fd = spi.as_raw_fd();
+    let mode: u8 = 0;
+    let bits: u8 = 8;
+
+    let results = unsafe {
+        [
+            libc::ioctl(fd, SPI_IOC_WR_MODE, &mode as *const u8),
+            libc::ioctl(fd, SPI_IOC_WR_BITS_PER_WORD, &bits as *const u8),
+            libc::ioctl(fd, SPI_IOC_WR_MAX_SPEED_HZ, &speed_hz as *const u32),
+        ]
+    };
+
+    if results.iter().all(|&result| result == 0) {
+        Ok(())
+    } else {
+        Err(FramebufferError::DeviceError(
+            format!("Failed to configure SPI: {}", io::Error::last_os_error())
+        ))
+    }
+}
+
+/// Output pin driven through the sysfs GPIO interface
+struct GpioLine {
+    value: File,
+    level: bool,
+}
+
+impl GpioLine {
+    fn output(number: u32, initial: bool) -> io::Result<Self> {
+        let base = PathBuf::from(format!("/sys/class/gpio/gpio{}", number));
+        if !base.exists() {
+            fs::write("/sys/class/gpio/export", number.to_string())?;
+        }
+
+        // "high"/"low" switch to output and set the level in one step
+        fs::write(base.join("direction"), if initial { "high" } else { "low" })?;
+        let value = OpenOptions::new().write(true).open(base.join("value"))?;
+
+        Ok(Self { value, level: initial })
+    }
+
+    fn set(&mut self, high: bool) -> io::Result<()> {
+        if high != self.level {
+            self.value.seek(SeekFrom::Start(0))?;
+            self.value.write_all(if high { b"1" } else { b"0" })?;
+            self.level = high;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_is_moved_by_the_offsets() {
+        let mut config = SpiLcdConfig::st7735("/dev/spidev0.0", 25);
+        assert_eq!(config.window(&Rect::new(0, 0, 160, 128)), ([0, 0, 0, 159], [0, 0, 0, 127]));
+
+        config.x_offset = 1;
+        config.y_offset = 300;
+        assert_eq!(config.window(&Rect::new(10, 20, 30, 1)), ([0, 11, 0, 40], [0x01, 0x40, 0x01, 0x40]));
+    }
+
+    #[test]
+    fn offsets_must_keep_the_screen_addressable() {
+        let mut config = SpiLcdConfig::st7735("/dev/spidev0.0", 25);
+        config.x_offset = u16::MAX - 159;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.window(&Rect::new(159, 0, 1, 1)).0, [0xFF, 0xFF, 0xFF, 0xFF]);
+
+        config.x_offset += 1;
+        assert!(config.validate().is_err());
+
+        config.x_offset = 0;
+        config.y_offset = u16::MAX;
+        assert!(config.validate().is_err());
+
+        config.y_offset = 0;
+        config.height = 0;
+        assert!(config.validate().is_err());
+    }
+}
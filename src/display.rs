@@ -1,37 +1,566 @@
-use crate::framebuffer::{Framebuffer, Color, Point, FontId};
+use crate::framebuffer::{Framebuffer, FramebufferError, Color, Point, Rect, FontId, LayerId, TextAlign, TextStyle, READOUT_GLYPHS};
+use crate::radio::{format_frequency, format_offset, RadioState};
+use crate::spectrum::SpectrumView;
+use crate::status::{Activity, FlowgraphState, StatusIndicators};
+use crate::theme::Theme;
+use crate::widgets::{Container, Divider, Insets, Label, Meter, Size, Ui, ValueReadout, Widget, WidgetId};
+use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+/// Screen size the layouts were designed for
+const REFERENCE_WIDTH: f32 = 160.0;
+const REFERENCE_HEIGHT: f32 = 128.0;
+
+/// Maps coordinates and text sizes of the reference layout onto the actual screen
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    width: u16,
+    height: u16,
+}
+
+impl Layout {
+    fn of(fb: &Framebuffer) -> Self {
+        Layout {
+            width: fb.width(),
+            height: fb.height(),
+        }
+    }
+    
+    fn x(&self, x: f32) -> u16 {
+        (x * self.width as f32 / REFERENCE_WIDTH).round() as u16
+    }
+    
+    fn y(&self, y: f32) -> u16 {
+        (y * self.height as f32 / REFERENCE_HEIGHT).round() as u16
+    }
+    
+    /// Scales a text size by the smaller axis so text never outgrows the screen
+    fn text_size(&self, size: f32) -> f32 {
+        let scale = (self.width as f32 / REFERENCE_WIDTH).min(self.height as f32 / REFERENCE_HEIGHT);
+        size * scale
+    }
+    
+    fn status_bar_height(&self) -> u16 {
+        self.y(16.0)
+    }
+    
+    /// The status bar including its separator line
+    fn status_area(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.status_bar_height() + 1)
+    }
+    
+    /// Everything below the status bar and its separator line
+    fn content_area(&self) -> Rect {
+        let top = self.status_bar_height() + 1;
+        Rect::new(0, top, self.width, self.height.saturating_sub(top))
+    }
+}
+
+/// Status text and indicators on a dark bar, above a separator line
+///
+/// From left to right: flowgraph state, text, RX/TX badge, signal meter,
+/// battery charge and clock. Indicators without a value take no space.
+struct StatusBar {
+    ui: Ui,
+    state: WidgetId,
+    status: WidgetId,
+    activity: WidgetId,
+    signal: WidgetId,
+    battery: WidgetId,
+    clock: WidgetId,
+    /// Width of each indicator when shown
+    widths: [(WidgetId, u16); 3],
+}
+
+impl StatusBar {
+    fn new(layout: &Layout, theme: &Theme) -> Self {
+        let mut ui = Ui::new(Container::vertical());
+        ui.set_background(theme.background);
+        let root = ui.root();
+        
+        let bar = ui.add(root, Container::horizontal()
+            .padding(Insets::symmetric(layout.x(4.0), 0))
+            .spacing(layout.x(3.0))
+            .background(theme.status_bar));
+        ui.set_fixed(bar, layout.status_bar_height());
+        
+        let state = ui.add(bar, StateIcon {
+            state: FlowgraphState::Stopped,
+            size: layout.y(7.0),
+            color: theme.status_text,
+        });
+        ui.set_fixed(state, layout.y(7.0));
+        
+        let style = TextStyle::new(layout.text_size(10.0), theme.status_text, FontId::Regular).wrap(false).ellipsis(true);
+        let status = ui.add(bar, Label::new("", style));
+        ui.set_flex(status, 1);
+        
+        let activity = ui.add(bar, ActivityBadge {
+            activity: Activity::Idle,
+            style: TextStyle::new(layout.text_size(8.0), theme.background, FontId::Bold).wrap(false).align(TextAlign::Center),
+            height: layout.y(11.0),
+            rx: theme.rx,
+            tx: theme.tx,
+        });
+        
+        let off = Color { a: 70, ..theme.status_text };
+        let signal = ui.add(bar, Meter::new(4)
+            .height(layout.y(7.0))
+            .colors(theme.status_text, theme.status_text, theme.status_text, off));
+        
+        let small = TextStyle::new(layout.text_size(9.0), theme.status_text, FontId::Regular).wrap(false).align(TextAlign::Right);
+        let battery = ui.add(bar, Label::new("", small));
+        let clock = ui.add(bar, Label::new("", small));
+        ui.set_fixed(clock, layout.x(25.0));
+        
+        ui.add(root, Divider::new(theme.border));
+        
+        let widths = [(activity, layout.x(15.0)), (signal, layout.x(12.0)), (battery, layout.x(22.0))];
+        for (id, _) in widths {
+            ui.set_fixed(id, 0);
+        }
+        StatusBar { ui, state, status, activity, signal, battery, clock, widths }
+    }
+    
+    /// Gives an indicator its width, or none to hide it
+    fn set_visible(&mut self, id: WidgetId, visible: bool) {
+        if let Some(&(_, width)) = self.widths.iter().find(|(indicator, _)| *indicator == id) {
+            self.ui.set_fixed(id, if visible { width } else { 0 });
+        }
+    }
+    
+    fn update(&mut self, indicators: &StatusIndicators) {
+        self.ui.update::<StateIcon>(self.state, |icon| icon.state = indicators.flowgraph);
+        
+        self.set_visible(self.activity, indicators.activity != Activity::Idle);
+        self.ui.update::<ActivityBadge>(self.activity, |badge| badge.activity = indicators.activity);
+        
+        self.set_visible(self.signal, indicators.signal.is_some());
+        self.ui.update::<Meter>(self.signal, |meter| meter.set_value(indicators.signal.unwrap_or(0.0)));
+        
+        self.set_visible(self.battery, indicators.battery.is_some());
+        let battery = indicators.battery.map_or(String::new(), |level| {
+            format!("{}{}%", if level.charging { "+" } else { "" }, level.percent)
+        });
+        self.ui.update::<Label>(self.battery, |label| label.set_text(battery));
+        
+        let clock = indicators.clock.map_or(String::new(), |time| time.to_string());
+        self.ui.update::<Label>(self.clock, |label| label.set_text(clock));
+    }
+}
+
+/// Square while the flowgraph is stopped, a play triangle while it runs and a cross if it failed
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StateIcon {
+    state: FlowgraphState,
+    size: u16,
+    color: Color,
+}
+
+impl Widget for StateIcon {
+    fn measure(&self, _fb: &Framebuffer, _available: Size) -> Size {
+        Size::new(self.size, self.size)
+    }
+    
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let size = self.size.min(bounds.width).min(bounds.height);
+        let x = bounds.x + (bounds.width - size) / 2;
+        let y = bounds.y + (bounds.height - size) / 2;
+        let last = size.saturating_sub(1);
+        
+        match self.state {
+            FlowgraphState::Stopped => fb.fill_rect(Rect::new(x, y, size, size), self.color),
+            FlowgraphState::Running => {
+                // Columns shrinking towards the tip on the right
+                for column in 0..size {
+                    let inset = column / 2;
+                    fb.draw_vline(Point::new(x + column, y + inset), size.saturating_sub(2 * inset), self.color);
+                }
+            }
+            FlowgraphState::Failed => {
+                for offset in 0..2 {
+                    fb.draw_line(Point::new(x + offset, y), Point::new(x + last, y + last - offset), self.color);
+                    fb.draw_line(Point::new(x + offset, y + last), Point::new(x + last, y + offset), self.color);
+                }
+            }
+        }
+    }
+}
+
+/// `RX` or `TX` on a badge in the receive or transmit color, empty while idle
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ActivityBadge {
+    activity: Activity,
+    style: TextStyle,
+    height: u16,
+    rx: Color,
+    tx: Color,
+}
+
+impl Widget for ActivityBadge {
+    fn measure(&self, _fb: &Framebuffer, available: Size) -> Size {
+        Size::new(available.width, self.height)
+    }
+    
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let (text, color) = match self.activity {
+            Activity::Idle => return,
+            Activity::Receiving => ("RX", self.rx),
+            Activity::Transmitting => ("TX", self.tx),
+        };
+        
+        let height = self.height.min(bounds.height);
+        let badge = Rect::new(bounds.x, bounds.y + (bounds.height - height) / 2, bounds.width, height);
+        fb.fill_rounded_rect(badge, height / 4, color);
+        
+        let text_height = fb.measure_text(text, self.style.size, self.style.font).map_or(0, |metrics| metrics.height());
+        let offset = height.saturating_sub(text_height) / 2;
+        let area = Rect::new(badge.x, badge.y + offset, badge.width, height - offset);
+        let _ = fb.write_text_box(text, area, &self.style);
+    }
+}
+
+/// Frequencies, channel and the selected flowgraph
+struct IdleScreen {
+    ui: Ui,
+    rx: WidgetId,
+    tx: WidgetId,
+    channel: WidgetId,
+    /// What up and down do, such as the tuning step
+    mode: WidgetId,
+    offset: WidgetId,
+    flowgraph: WidgetId,
+}
+
+impl IdleScreen {
+    fn new(layout: &Layout, theme: &Theme) -> Self {
+        let mut ui = Ui::new(Container::vertical().padding(Insets {
+            top: layout.y(8.0),
+            right: layout.x(8.0),
+            bottom: layout.y(6.0),
+            left: layout.x(8.0),
+        }).spacing(layout.y(4.0)));
+        ui.set_background(theme.background);
+        let root = ui.root();
+        
+        // Small caption and unit leave room for 6.25 kHz channels like 438.30625
+        let readout = TextStyle::new(layout.text_size(16.0), theme.rx, FontId::Mono);
+        let small = TextStyle::new(layout.text_size(10.0), theme.rx, FontId::Regular);
+        let rx = ui.add(root, ValueReadout::new("RX", "", "MHz", readout).caption_style(small).unit_style(small));
+        let readout = TextStyle { color: theme.tx, ..readout };
+        let small = TextStyle { color: theme.tx, ..small };
+        let tx = ui.add(root, ValueReadout::new("TX", "", "MHz", readout).caption_style(small).unit_style(small));
+        
+        let row = ui.add(root, Container::horizontal().spacing(layout.x(4.0)));
+        let style = TextStyle::new(layout.text_size(11.0), theme.foreground, FontId::Bold).wrap(false).ellipsis(true);
+        let channel = ui.add(row, Label::new("", style));
+        ui.set_flex(channel, 1);
+        let style = TextStyle::new(layout.text_size(10.0), theme.muted, FontId::Regular).wrap(false);
+        let mode = ui.add(row, Label::new("", TextStyle { color: theme.accent, ..style }));
+        let offset = ui.add(row, Label::new("", style));
+        
+        let spacer = ui.add(root, Container::vertical());
+        ui.set_flex(spacer, 1);
+        
+        let row = ui.add(root, Container::horizontal().spacing(layout.x(4.0)));
+        let caption = TextStyle::new(layout.text_size(10.0), theme.muted, FontId::Regular).wrap(false);
+        ui.add(row, Label::new("Flowgraph:", caption));
+        let name = TextStyle::new(layout.text_size(12.0), theme.foreground, FontId::Regular).wrap(false).ellipsis(true);
+        let flowgraph = ui.add(row, Label::new("", name));
+        ui.set_flex(flowgraph, 1);
+        IdleScreen { ui, rx, tx, channel, mode, offset, flowgraph }
+    }
+}
+
+/// A title above wrapped message text
+struct MessageScreen {
+    ui: Ui,
+    body: WidgetId,
+}
+
+impl MessageScreen {
+    fn new(layout: &Layout, theme: &Theme) -> Self {
+        let mut ui = Ui::new(Container::vertical().padding(Insets {
+            top: layout.y(4.0),
+            right: layout.x(10.0),
+            bottom: layout.y(4.0),
+            left: layout.x(10.0),
+        }).spacing(layout.y(4.0)));
+        ui.set_background(theme.background);
+        let root = ui.root();
+        
+        let title = TextStyle::new(layout.text_size(14.0), theme.accent, FontId::Bold).wrap(false);
+        ui.add(root, Label::new("M17 Message", title));
+        let style = TextStyle::new(layout.text_size(10.0), theme.foreground, FontId::Regular);
+        let body = ui.add(root, Label::new("", style));
+        ui.set_flex(body, 1);
+        MessageScreen { ui, body }
+    }
+}
+
+/// Spectrum line over a grid, one level from 0 (bottom) to 255 (top) per column
+///
+/// Levels are stretched or squeezed to the width, keeping the peak of each column.
+#[derive(Debug, Clone, PartialEq)]
+struct SpectrumTrace {
+    levels: Vec<u8>,
+    line: Color,
+    fill: Color,
+    grid: Color,
+    /// Marks the receive frequency in the middle
+    center: Color,
+}
+
+impl Widget for SpectrumTrace {
+    fn measure(&self, _fb: &Framebuffer, available: Size) -> Size {
+        available
+    }
+    
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        // A line every 20 dB
+        for division in 1..4 {
+            let y = bounds.y + bounds.height * division / 4;
+            fb.draw_hline(Point::new(bounds.x, y), bounds.width, self.grid);
+        }
+        fb.draw_vline(Point::new(bounds.x + bounds.width / 2, bounds.y), bounds.height, self.center);
+        
+        let levels = resample(&self.levels, bounds.width as usize);
+        let bottom = bounds.y + bounds.height;
+        let mut previous: Option<u16> = None;
+        for (column, &level) in levels.iter().enumerate() {
+            let x = bounds.x + column as u16;
+            let height = (level as u32 * bounds.height as u32 / 255) as u16;
+            let top = bottom - height;
+            fb.draw_vline(Point::new(x, top), height, self.fill);
+            
+            // Join the tops so steep edges stay visible
+            let (from, to) = match previous {
+                Some(before) => (before.min(top), before.max(top)),
+                None => (top, top),
+            };
+            fb.draw_vline(Point::new(x, from.min(bottom - 1)), (to - from).max(1), self.line);
+            previous = Some(top);
+        }
+    }
+}
+
+/// Spectra received over time, the newest at the top, colored by level
+#[derive(Debug, Clone, PartialEq)]
+struct Waterfall {
+    rows: VecDeque<Vec<u8>>,
+    /// Rows kept, at least the height the waterfall gets
+    depth: usize,
+    palette: [Color; WATERFALL_COLORS],
+}
+
+/// Colors from the lowest to the highest level of the waterfall
+const WATERFALL_COLORS: usize = 16;
+
+impl Waterfall {
+    fn new(depth: usize, theme: &Theme) -> Self {
+        // Background to the receive color for the noise, on to the warning color for strong signals
+        let mut palette = [theme.background; WATERFALL_COLORS];
+        let half = WATERFALL_COLORS / 2;
+        for (index, color) in palette.iter_mut().enumerate() {
+            *color = if index < half {
+                mix(theme.background, theme.rx, index as f32 / half as f32)
+            } else {
+                mix(theme.rx, theme.warning, (index - half) as f32 / (WATERFALL_COLORS - 1 - half) as f32)
+            };
+        }
+        Waterfall { rows: VecDeque::new(), depth, palette }
+    }
+    
+    fn push(&mut self, levels: Vec<u8>) {
+        self.rows.push_front(levels);
+        self.rows.truncate(self.depth);
+    }
+}
+
+impl Widget for Waterfall {
+    fn measure(&self, _fb: &Framebuffer, available: Size) -> Size {
+        available
+    }
+    
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        for (row, levels) in self.rows.iter().take(bounds.height as usize).enumerate() {
+            let y = bounds.y + row as u16;
+            let levels = resample(levels, bounds.width as usize);
+            
+            // One rectangle per run of the same color
+            let mut start = 0;
+            while start < levels.len() {
+                let color = self.palette[levels[start] as usize * WATERFALL_COLORS / 256];
+                let mut end = start + 1;
+                while end < levels.len() && self.palette[levels[end] as usize * WATERFALL_COLORS / 256] == color {
+                    end += 1;
+                }
+                fb.fill_rect(Rect::new(bounds.x + start as u16, y, (end - start) as u16, 1), color);
+                start = end;
+            }
+        }
+    }
+}
+
+/// Stretches `levels` to `width` entries, or squeezes them keeping the highest of each group
+fn resample(levels: &[u8], width: usize) -> Vec<u8> {
+    if levels.is_empty() || levels.len() == width {
+        return levels.to_vec();
+    }
+    
+    (0..width)
+        .map(|column| {
+            let start = column * levels.len() / width;
+            let end = ((column + 1) * levels.len() / width).max(start + 1);
+            levels[start..end].iter().copied().max().unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Blends from `from` (0.0) to `to` (1.0)
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+    Color::new(channel(from.r, to.r), channel(from.g, to.g), channel(from.b, to.b), channel(from.a, to.a))
+}
+
+/// Spectrum and waterfall of the running flowgraph below the frequency and scale
+struct SpectrumScreen {
+    ui: Ui,
+    frequency: WidgetId,
+    reference: WidgetId,
+    span: WidgetId,
+    trace: WidgetId,
+    waterfall: WidgetId,
+}
+
+impl SpectrumScreen {
+    fn new(layout: &Layout, theme: &Theme) -> Self {
+        let mut ui = Ui::new(Container::vertical());
+        ui.set_background(theme.background);
+        let root = ui.root();
+        
+        let row = ui.add(root, Container::horizontal()
+            .padding(Insets::symmetric(layout.x(4.0), layout.y(1.0)))
+            .spacing(layout.x(6.0)));
+        let style = TextStyle::new(layout.text_size(10.0), theme.foreground, FontId::Mono).wrap(false);
+        let frequency = ui.add(row, Label::new("", style));
+        ui.set_flex(frequency, 1);
+        let style = TextStyle::new(layout.text_size(9.0), theme.muted, FontId::Regular).wrap(false);
+        let reference = ui.add(row, Label::new("", style));
+        let span = ui.add(row, Label::new("", style));
+        
+        let trace = ui.add(root, SpectrumTrace {
+            levels: Vec::new(),
+            line: theme.foreground,
+            fill: theme.surface,
+            grid: theme.border,
+            center: theme.accent,
+        });
+        ui.set_flex(trace, 2);
+        ui.add(root, Divider::new(theme.border));
+        let waterfall = ui.add(root, Waterfall::new(layout.height as usize, theme));
+        ui.set_flex(waterfall, 3);
+        SpectrumScreen { ui, frequency, reference, span, trace, waterfall }
+    }
+}
+
+/// Which retained screen currently fills the content area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Content {
+    /// Something drawn directly, like the welcome screen
+    Other,
+    Idle,
+    Message,
+    Spectrum,
+    /// A `Ui` passed to `show_ui`
+    Custom,
+}
+
 /// Display manager for rendering UI elements to the framebuffer
 pub struct Display {
     framebuffer: Arc<Mutex<Framebuffer>>,
+    /// Overlay for popups, so dismissing one restores the screen below without a redraw
+    popup_layer: LayerId,
+    theme: Theme,
+    status_bar: StatusBar,
+    idle: IdleScreen,
+    message: MessageScreen,
+    spectrum: SpectrumScreen,
+    content: Content,
 }
 
 impl Display {
     /// Creates a new Display instance with the given framebuffer
-    pub fn new(framebuffer: Framebuffer) -> Self {
+    ///
+    /// Glyphs of the frequency and status readouts are rasterized up front.
+    pub fn new(mut framebuffer: Framebuffer) -> Self {
+        let layout = Layout::of(&framebuffer);
+        let _ = framebuffer.prewarm_glyphs(READOUT_GLYPHS, layout.text_size(16.0), FontId::Mono);
+        let _ = framebuffer.prewarm_glyphs(READOUT_GLYPHS, layout.text_size(10.0), FontId::Regular);
+        
+        let popup_layer = framebuffer.add_layer();
+        framebuffer.set_layer_visible(popup_layer, false);
+        
+        let theme = Theme::default();
         Display {
             framebuffer: Arc::new(Mutex::new(framebuffer)),
+            popup_layer,
+            status_bar: StatusBar::new(&layout, &theme),
+            idle: IdleScreen::new(&layout, &theme),
+            message: MessageScreen::new(&layout, &theme),
+            spectrum: SpectrumScreen::new(&layout, &theme),
+            theme,
+            content: Content::Other,
         }
     }
     
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+    
+    /// Switches to another color theme
+    ///
+    /// Screens are drawn in the new colors the next time they are shown; screens
+    /// built with `show_ui` should compare `theme()` with the theme they were built for.
+    pub fn set_theme(&mut self, theme: Theme) {
+        if theme == self.theme {
+            return;
+        }
+        
+        let layout = match self.framebuffer.lock() {
+            Ok(fb) => Layout::of(&fb),
+            Err(_) => return,
+        };
+        self.status_bar = StatusBar::new(&layout, &theme);
+        self.idle = IdleScreen::new(&layout, &theme);
+        self.message = MessageScreen::new(&layout, &theme);
+        self.spectrum = SpectrumScreen::new(&layout, &theme);
+        self.theme = theme;
+        self.content = Content::Other;
+    }
+    
     /// Shows the welcome screen with LinHT branding
     pub fn show_welcome(&mut self) {
+        self.replace_content(Content::Other);
         if let Ok(mut fb) = self.framebuffer.lock() {
-            let _ = fb.clear_screen(Color::black());
-            
+            let layout = Layout::of(&fb);
+            let title_position = Self::centered(&fb, &layout, ">LinHT_", 38.0, 65.0);
+            let credit_position = Self::centered(&fb, &layout, "by M17 Foundation", 14.0, 90.0);
+            let _ = fb.clear_screen(self.theme.background);
             let _ = fb.write_text(
                 ">LinHT_",
-                Point::new(10, 65),
-                38.0,
-                Color::green(),
+                title_position,
+                layout.text_size(38.0),
+                self.theme.accent,
                 FontId::Regular
             );
             
             let _ = fb.write_text(
                 "by M17 Foundation",
-                Point::new(20, 90),
-                14.0,
-                Color::white(),
+                credit_position,
+                layout.text_size(14.0),
+                self.theme.foreground,
                 FontId::Regular
             );
             
@@ -39,44 +568,30 @@ impl Display {
         }
     }
     
-    /// Shows the idle screen with frequencies and current flowgraph
-    pub fn show_idle(&mut self, flowgraph_name: &str) {
+    /// Shows the idle screen with the tuned frequencies and current flowgraph below the status bar
+    ///
+    /// `mode` tells what up and down do, e.g. the tuning step; it is shown next to the channel.
+    /// Only parts that changed since the previous call are redrawn.
+    pub fn show_idle(&mut self, flowgraph_name: &str, radio: &RadioState, mode: &str) {
+        self.replace_content(Content::Idle);
         if let Ok(mut fb) = self.framebuffer.lock() {
-            let _ = fb.clear_screen(Color::black());
-            
-            self.draw_status_bar(&mut fb, "Ready");
-            
-            let _ = fb.write_text(
-                "RX: 438.300 MHz",
-                Point::new(8, 40),
-                16.0,
-                Color::green(),
-                FontId::Regular
-            );
-            
-            let _ = fb.write_text(
-                "TX: 430.700 MHz",
-                Point::new(8, 60),
-                16.0,
-                Color::new(241, 196, 15, 255),
-                FontId::Regular
-            );
-            
-            let _ = fb.write_text(
-                "Flowgraph:",
-                Point::new(10, 90),
-                10.0,
-                Color::new(149, 165, 166, 255),
-                FontId::Regular
-            );
-            
-            let _ = fb.write_text(
-                flowgraph_name,
-                Point::new(10, 105),
-                12.0,
-                Color::white(),
-                FontId::Regular
-            );
+            let layout = Layout::of(&fb);
+            self.idle.ui.update::<ValueReadout>(self.idle.rx, |readout| readout.set_value(format_frequency(radio.rx_frequency)));
+            self.idle.ui.update::<ValueReadout>(self.idle.tx, |readout| {
+                if radio.tx_inhibit {
+                    readout.set_value("off");
+                    readout.unit.clear();
+                } else {
+                    readout.set_value(format_frequency(radio.tx_frequency));
+                    readout.unit = "MHz".to_string();
+                }
+            });
+            self.idle.ui.update::<Label>(self.idle.channel, |label| label.set_text(radio.channel_name()));
+            self.idle.ui.update::<Label>(self.idle.mode, |label| label.set_text(mode));
+            let offset = if radio.tx_inhibit { "RX only".to_string() } else { format_offset(radio.offset()) };
+            self.idle.ui.update::<Label>(self.idle.offset, |label| label.set_text(offset));
+            self.idle.ui.update::<Label>(self.idle.flowgraph, |label| label.set_text(flowgraph_name));
+            self.idle.ui.render(&mut fb, layout.content_area());
             
             let _ = fb.flush();
         }
@@ -85,76 +600,358 @@ impl Display {
     /// Updates only the status bar without clearing the screen
     pub fn show_status(&mut self, status: &str) {
         if let Ok(mut fb) = self.framebuffer.lock() {
-            self.draw_status_bar(&mut fb, status);
+            let layout = Layout::of(&fb);
+            self.status_bar.ui.update::<Label>(self.status_bar.status, |label| label.set_text(status));
+            self.status_bar.ui.render(&mut fb, layout.status_area());
             let _ = fb.flush();
         }
     }
     
+    /// Updates the clock, battery, signal and state indicators of the status bar
+    ///
+    /// Cheap to call on every tick: only indicators that changed are redrawn.
+    pub fn show_indicators(&mut self, indicators: &StatusIndicators) {
+        self.status_bar.update(indicators);
+        // Directly drawn screens cover the status bar until the next `show_status`
+        if self.content == Content::Other {
+            return;
+        }
+        
+        if let Ok(mut fb) = self.framebuffer.lock() {
+            let layout = Layout::of(&fb);
+            if self.status_bar.ui.render(&mut fb, layout.status_area()) {
+                let _ = fb.flush();
+            }
+        }
+    }
+    
     /// Displays an M17 protocol message with parsed fields
     pub fn show_m17_message(&mut self, message: &str) {
+        self.replace_content(Content::Message);
         if let Ok(mut fb) = self.framebuffer.lock() {
-            for y in 17..128 {
-                for x in 0..160 {
-                    let _ = fb.set_pixel(x, y, Color::black());
-                }
+            let layout = Layout::of(&fb);
+            self.message.ui.update::<Label>(self.message.body, |label| label.set_text(message.trim_end()));
+            self.message.ui.render(&mut fb, layout.content_area());
+            
+            let _ = fb.flush();
+        }
+    }
+    
+    /// Adds a spectrum received from the flowgraph to the trace and the waterfall
+    ///
+    /// Only the bins within the span of `view` are kept, scaled to its reference
+    /// level; the waterfall keeps the scale each line was received with. Nothing
+    /// is drawn until `show_spectrum`.
+    pub fn push_spectrum(&mut self, bins: &[f32], view: &SpectrumView) {
+        let width = match self.framebuffer.lock() {
+            Ok(fb) => fb.width() as usize,
+            Err(_) => return,
+        };
+        let levels: Vec<u8> = view.visible(bins).iter().map(|&db| (view.level(db) * 255.0).round() as u8).collect();
+        let levels = resample(&levels, width);
+        
+        self.spectrum.ui.update::<SpectrumTrace>(self.spectrum.trace, |trace| trace.levels = levels.clone());
+        self.spectrum.ui.update::<Waterfall>(self.spectrum.waterfall, |waterfall| waterfall.push(levels));
+    }
+    
+    /// Empties the trace and the waterfall, e.g. before another flowgraph sends its spectrum
+    pub fn clear_spectrum(&mut self) {
+        self.spectrum.ui.update::<SpectrumTrace>(self.spectrum.trace, |trace| trace.levels.clear());
+        self.spectrum.ui.update::<Waterfall>(self.spectrum.waterfall, |waterfall| waterfall.rows.clear());
+    }
+    
+    /// Shows the spectrum around the receive frequency below the status bar
+    pub fn show_spectrum(&mut self, radio: &RadioState, view: &SpectrumView) {
+        self.replace_content(Content::Spectrum);
+        if let Ok(mut fb) = self.framebuffer.lock() {
+            let layout = Layout::of(&fb);
+            let screen = &mut self.spectrum;
+            screen.ui.update::<Label>(screen.frequency, |label| label.set_text(format_frequency(radio.rx_frequency)));
+            screen.ui.update::<Label>(screen.reference, |label| label.set_text(view.reference_label()));
+            screen.ui.update::<Label>(screen.span, |label| label.set_text(view.span_label()));
+            if screen.ui.render(&mut fb, layout.content_area()) {
+                let _ = fb.flush();
             }
+        }
+    }
+    
+    /// Shows a screen built from widgets below the status bar
+    ///
+    /// Like the built-in screens, it is drawn in full when it replaces another one
+    /// and incrementally afterwards.
+    pub fn show_ui(&mut self, ui: &mut Ui) {
+        if self.content != Content::Custom {
+            ui.invalidate();
+        }
+        self.replace_content(Content::Custom);
+        
+        if let Ok(mut fb) = self.framebuffer.lock() {
+            let layout = Layout::of(&fb);
+            ui.render(&mut fb, layout.content_area());
+            let _ = fb.flush();
+        }
+    }
+    
+    /// Scales a text size of the 160x128 reference layout to this screen
+    pub fn text_size(&self, size: f32) -> f32 {
+        match self.framebuffer.lock() {
+            Ok(fb) => Layout::of(&fb).text_size(size),
+            Err(_) => size,
+        }
+    }
+    
+    /// Redraws the status bar and the next screen shown in full
+    ///
+    /// Needed when a different `Ui` is passed to `show_ui`, which cannot tell
+    /// two trees apart.
+    pub fn invalidate(&mut self) {
+        self.replace_content(Content::Other);
+    }
+    
+    /// Shows a framed message on top of the current screen until `hide_popup`
+    ///
+    /// Screens drawn while the popup is open stay hidden behind it.
+    pub fn show_popup(&mut self, title: &str, message: &str) {
+        if let Ok(mut fb) = self.framebuffer.lock() {
+            let layout = Layout::of(&fb);
+            let frame = Rect::new(
+                layout.x(12.0),
+                layout.y(34.0),
+                layout.width.saturating_sub(2 * layout.x(12.0)),
+                layout.y(72.0),
+            );
+            let radius = layout.x(4.0);
+            
+            fb.set_layer(self.popup_layer);
+            fb.clear_layer(self.popup_layer);
+            fb.fill_rounded_rect(frame, radius, self.theme.surface);
+            fb.draw_rounded_rect(frame, radius, self.theme.border);
             
+            let inset = layout.x(6.0);
+            let inner_width = frame.width.saturating_sub(2 * inset);
+            let title_size = layout.text_size(12.0);
+            let title = fb
+                .ellipsize(title, inner_width, title_size, FontId::Bold)
+                .unwrap_or_else(|_| title.to_string());
             let _ = fb.write_text(
-                "M17 Message",
-                Point::new(10, 30),
-                14.0,
-                Color::green(),
-                FontId::Regular
+                &title,
+                Point::new(frame.x + inset, frame.y + layout.y(16.0)),
+                title_size,
+                self.theme.warning,
+                FontId::Bold
             );
             
-            let lines: Vec<&str> = message.split('\n').collect();
-            let mut y_pos = 50;
-            
-            for line in lines.iter().take(4) {
-                if y_pos > 110 {
-                    break;
-                }
-                
-                let _ = fb.write_text(
-                    line,
-                    Point::new(10, y_pos),
-                    10.0,
-                    Color::white(),
-                    FontId::Regular
-                );
-                
-                y_pos += 15;
-            }
+            let body_top = frame.y + layout.y(22.0);
+            let body = Rect::new(
+                frame.x + inset,
+                body_top,
+                inner_width,
+                (frame.bottom() as u16).saturating_sub(body_top + layout.y(4.0)),
+            );
+            let style = TextStyle::new(layout.text_size(10.0), self.theme.foreground, FontId::Regular).ellipsis(true);
+            let _ = fb.write_text_box(message.trim_end(), body, &style);
             
+            fb.set_layer(LayerId::BASE);
+            fb.set_layer_visible(self.popup_layer, true);
+            let _ = fb.flush();
+        }
+    }
+    
+    /// Removes the popup, revealing the screen underneath
+    pub fn hide_popup(&mut self) {
+        if let Ok(mut fb) = self.framebuffer.lock() {
+            fb.set_layer_visible(self.popup_layer, false);
             let _ = fb.flush();
         }
     }
     
     /// Clears the screen for shutdown
     pub fn show_shutdown(&mut self) {
+        self.replace_content(Content::Other);
         if let Ok(mut fb) = self.framebuffer.lock() {
             let _ = fb.clear_screen(Color::black());
             let _ = fb.flush();
         }
     }
     
-    fn draw_status_bar(&self, fb: &mut Framebuffer, status: &str) {
-        for y in 0..16 {
-            for x in 0..160 {
-                let _ = fb.set_pixel(x, y, Color::new(30, 30, 30, 255));
-            }
+    /// Powers the panel down or back up without losing what is drawn
+    pub fn set_blank(&mut self, blank: bool) -> Result<(), FramebufferError> {
+        match self.framebuffer.lock() {
+            Ok(mut fb) => fb.set_blank(blank),
+            Err(_) => Err(FramebufferError::DeviceError("Framebuffer lock poisoned".to_string())),
+        }
+    }
+    
+    /// Lets the display device handle its events, see `Backend::poll`
+    pub fn poll(&mut self) -> Result<(), FramebufferError> {
+        match self.framebuffer.lock() {
+            Ok(mut fb) => fb.poll(),
+            Err(_) => Err(FramebufferError::DeviceError("Framebuffer lock poisoned".to_string())),
+        }
+    }
+    
+    /// Saves the current screen contents as PNG or PPM, depending on the file extension
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> Result<(), FramebufferError> {
+        match self.framebuffer.lock() {
+            Ok(fb) => fb.save_screenshot(path),
+            Err(_) => Err(FramebufferError::DeviceError("Framebuffer lock poisoned".to_string())),
+        }
+    }
+    
+    /// Returns the baseline start that centers `text` horizontally at reference height `y`
+    fn centered(fb: &Framebuffer, layout: &Layout, text: &str, size: f32, y: f32) -> Point {
+        let width = fb
+            .measure_text(text, layout.text_size(size), FontId::Regular)
+            .map_or(0, |metrics| metrics.width);
+        Point::new(layout.width.saturating_sub(width) / 2, layout.y(y))
+    }
+    
+    /// Switches the content area to another screen, which then has to be drawn in full
+    ///
+    /// Directly drawn screens cover the status bar as well.
+    fn replace_content(&mut self, content: Content) {
+        if content == Content::Other {
+            self.status_bar.ui.invalidate();
+        }
+        if self.content == content {
+            return;
         }
         
-        let _ = fb.write_text(
-            status,
-            Point::new(5, 12),
-            10.0,
-            Color::white(),
-            FontId::Regular
-        );
-        
-        for x in 0..160 {
-            let _ = fb.set_pixel(x, 16, Color::new(123, 123, 123, 255));
+        match content {
+            Content::Other | Content::Custom => {}
+            Content::Idle => self.idle.ui.invalidate(),
+            Content::Message => self.message.ui.invalidate(),
+            Content::Spectrum => self.spectrum.ui.invalidate(),
         }
+        self.content = content;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::framebuffer::PixelFormat;
+
+    fn display() -> Display {
+        let backend = MemoryBackend::new(160, 128, PixelFormat::RGB565);
+        let mut fb = Framebuffer::with_backend(Box::new(backend)).unwrap();
+        fb.load_default_fonts(crate::framebuffer::DEFAULT_FONT_DIR).unwrap();
+        Display::new(fb)
+    }
+
+    /// The screen as rows of RGB pixels, read back from its PPM dump
+    fn screen(display: &Display) -> Vec<Vec<[u8; 3]>> {
+        let fb = display.framebuffer.lock().unwrap();
+        let mut ppm = Vec::new();
+        fb.write_ppm(&mut ppm).unwrap();
+        let header = format!("P6\n{} {}\n255\n", fb.width(), fb.height());
+        assert!(ppm.starts_with(header.as_bytes()));
+        ppm[header.len()..]
+            .chunks_exact(3)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect::<Vec<_>>()
+            .chunks(fb.width() as usize)
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    fn count(rows: &[Vec<[u8; 3]>], area: Rect, color: Color) -> usize {
+        rows[area.y as usize..area.bottom() as usize]
+            .iter()
+            .flat_map(|row| &row[area.x as usize..area.right() as usize])
+            .filter(|pixel| **pixel == [color.r, color.g, color.b])
+            .count()
+    }
+
+    fn content_area() -> Rect {
+        let fb = Framebuffer::with_backend(Box::new(MemoryBackend::new(160, 128, PixelFormat::RGB565))).unwrap();
+        Layout::of(&fb).content_area()
+    }
+
+    #[test]
+    fn layout_scales_the_reference_screen() {
+        let reference = Layout { width: 160, height: 128 };
+        assert_eq!((reference.x(80.0), reference.y(64.0)), (80, 64));
+        assert_eq!(reference.text_size(10.0), 10.0);
+        assert_eq!(reference.content_area(), Rect::new(0, 17, 160, 111));
+
+        let larger = Layout { width: 320, height: 240 };
+        assert_eq!((larger.x(80.0), larger.y(64.0)), (160, 120));
+        // Text follows the axis that grew least
+        assert_eq!(larger.text_size(10.0), 18.75);
+        assert_eq!(larger.status_area(), Rect::new(0, 0, 320, 31));
+        assert_eq!(larger.content_area(), Rect::new(0, 31, 320, 209));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn status_bar_is_scaled_to_the_screen() {
+        let mut fb = Framebuffer::with_backend(Box::new(MemoryBackend::new(320, 240, PixelFormat::RGBA8888))).unwrap();
+        fb.load_default_fonts(crate::framebuffer::DEFAULT_FONT_DIR).unwrap();
+        let mut display = Display::new(fb);
+        let theme = display.theme().clone();
+        display.show_idle("som_m17.py", &RadioState::default(), "");
+        display.show_status("Ready");
+
+        let rows = screen(&display);
+        assert_eq!((rows.len(), rows[0].len()), (240, 320));
+        let bar = |y: u16| count(&rows, Rect::new(0, y, 320, 1), theme.status_bar);
+        assert!(bar(0) > 300 && bar(29) > 300, "status bar not 30 rows high");
+        assert_eq!(bar(40), 0);
+        assert!(count(&rows, Layout { width: 320, height: 240 }.content_area(), theme.rx) > 80, "no RX readout drawn");
+    }
+
+    #[test]
+    fn idle_screen_shows_the_readouts() {
+        let mut display = display();
+        let theme = display.theme().clone();
+        display.show_idle("som_m17.py", &RadioState::default(), "VFO 12.5k");
+
+        let rows = screen(&display);
+        let area = content_area();
+        assert!(count(&rows, area, theme.rx) > 20, "no RX readout drawn");
+        assert!(count(&rows, area, theme.background) > (area.width as usize * area.height as usize) / 2);
+    }
+
+    #[test]
+    fn idle_screen_redraws_what_changed() {
+        let mut display = display();
+        let mut radio = RadioState::default();
+        display.show_idle("som_m17.py", &radio, "");
+        let before = screen(&display);
+
+        radio.tx_inhibit = true;
+        display.show_idle("som_m17.py", &radio, "");
+        assert_ne!(screen(&display), before);
+
+        radio.tx_inhibit = false;
+        display.show_idle("som_m17.py", &radio, "");
+        assert_eq!(screen(&display), before);
+    }
+
+    #[test]
+    fn message_screen_replaces_and_restores_the_idle_screen() {
+        let mut display = display();
+        let theme = display.theme().clone();
+        let radio = RadioState::default();
+        display.show_idle("som_m17.py", &radio, "");
+        let idle = screen(&display);
+        let area = content_area();
+        assert!(count(&idle, area, theme.tx) > 0, "no TX readout drawn");
+
+        display.show_m17_message("SRC: OE3ANC\nDST: @ALL\nTYPE: Voice");
+        let message = screen(&display);
+        assert!(count(&message, area, theme.accent) > 0, "no title drawn");
+        // White text on black blends into grays, seldom pure white at this size
+        let text = message[area.y as usize..area.bottom() as usize]
+            .iter()
+            .flatten()
+            .filter(|[r, g, b]| r == g && g == b && *r > 128)
+            .count();
+        assert!(text > 20, "no message drawn");
+        assert_eq!(count(&message, area, theme.tx), 0, "idle readouts left behind");
+
+        display.show_idle("som_m17.py", &radio, "");
+        assert_eq!(screen(&display), idle);
+    }
+}
@@ -1,29 +1,65 @@
-use std::fs::{File, OpenOptions};
-use std::io;
-use std::os::unix::fs::OpenOptionsExt;
-use std::os::unix::io::AsRawFd;
-use memmap2::{MmapMut, MmapOptions};
-use fontdue::{Font, FontSettings};
-use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use crate::backend::{Backend, FbdevBackend, MemoryBackend};
+use crate::image;
+
+mod bitmap;
+mod dither;
+mod fonts;
+mod glyph_cache;
+mod layers;
+mod primitives;
+mod text;
+
+pub use dither::Dithering;
+pub use fonts::{FontRegistry, DEFAULT_FONT_DIR, EMBEDDED_BOLD_FONT, EMBEDDED_MONO_FONT, EMBEDDED_REGULAR_FONT};
+pub use glyph_cache::DEFAULT_GLYPH_CACHE_BYTES;
+pub use layers::LayerId;
+use glyph_cache::GlyphCache;
+use dither::Quantizer;
+use layers::Layer;
+pub use text::{TextAlign, TextMetrics, TextStyle, READOUT_GLYPHS};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FontId {
     Regular,
+    Bold,
+    /// Fixed-width face for frequency and callsign readouts
+    Mono,
+    /// Wide-coverage face used for characters missing from the other faces
+    Fallback,
+    /// Extra face registered from the font directory
+    Custom(u16),
 }
 
 impl FontId {
-    pub fn default_path(&self) -> &'static str {
+    /// File name of the default face in the font directory
+    pub fn file_name(&self) -> Option<&'static str> {
+        match self {
+            FontId::Regular => Some("DidactGothic-Regular.ttf"),
+            FontId::Bold => Some("DejaVuSans-Bold.ttf"),
+            FontId::Mono => Some("DejaVuSansMono.ttf"),
+            FontId::Fallback => Some("DejaVuSans.ttf"),
+            FontId::Custom(_) => None,
+        }
+    }
+    
+    /// Registry name of a built-in face
+    pub fn name(&self) -> Option<&'static str> {
         match self {
-            FontId::Regular => "fonts/DidactGothic-Regular.ttf",
+            FontId::Regular => Some("regular"),
+            FontId::Bold => Some("bold"),
+            FontId::Mono => Some("mono"),
+            FontId::Fallback => Some("fallback"),
+            FontId::Custom(_) => None,
         }
     }
 }
 
-pub const DISPLAY_WIDTH: u16 = 160;
-pub const DISPLAY_HEIGHT: u16 = 128;
+/// Bytes per pixel of the internal RGBA canvas
 pub const BYTES_PER_PIXEL: usize = 4;
-pub const FRAMEBUFFER_STRIDE: u16 = 160;
-pub const FRAMEBUFFER_SIZE: usize = (FRAMEBUFFER_STRIDE as usize) * (DISPLAY_HEIGHT as usize) * BYTES_PER_PIXEL;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
@@ -39,13 +75,122 @@ impl Color {
     }
     
     #[inline]
-    pub fn to_bytes(&self) -> [u8; 4] {
+    pub fn to_bytes(self) -> [u8; 4] {
         [self.r, self.g, self.b, self.a]
     }
     
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self::new(bytes[0], bytes[1], bytes[2], bytes[3])
+    }
+    
     pub const fn black() -> Self { Self::new(0, 0, 0, 255) }
     pub const fn white() -> Self { Self::new(255, 255, 255, 255) }
     pub const fn green() -> Self { Self::new(0, 255, 0, 255) }
+    pub const fn transparent() -> Self { Self::new(0, 0, 0, 0) }
+}
+
+/// Position and width of one color channel inside a device pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bitfield {
+    pub offset: u32,
+    pub length: u32,
+}
+
+impl Bitfield {
+    pub const fn new(offset: u32, length: u32) -> Self {
+        Self { offset, length }
+    }
+
+    /// Scales an 8-bit channel value down to this field and shifts it into place
+    #[inline]
+    fn pack(&self, value: u8) -> u32 {
+        if self.length == 0 {
+            return 0;
+        }
+        ((value as u32) >> (8 - self.length)) << self.offset
+    }
+}
+
+/// Pixel layout of the device as reported by `fb_var_screeninfo`
+///
+/// Bitfield offsets count from the least significant bit of the pixel word,
+/// which is stored in native byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    pub bits_per_pixel: u32,
+    pub red: Bitfield,
+    pub green: Bitfield,
+    pub blue: Bitfield,
+    pub transp: Bitfield,
+}
+
+impl PixelFormat {
+    /// 32 bpp, bytes R, G, B, A in memory (the layout of the internal canvas)
+    pub const RGBA8888: Self = Self::new(32, Bitfield::new(0, 8), Bitfield::new(8, 8), Bitfield::new(16, 8), Bitfield::new(24, 8));
+    /// 32 bpp, bytes B, G, R, A in memory
+    pub const BGRA8888: Self = Self::new(32, Bitfield::new(16, 8), Bitfield::new(8, 8), Bitfield::new(0, 8), Bitfield::new(24, 8));
+    /// 32 bpp, bytes B, G, R in memory with an unused fourth byte
+    pub const XRGB8888: Self = Self::new(32, Bitfield::new(16, 8), Bitfield::new(8, 8), Bitfield::new(0, 8), Bitfield::new(0, 0));
+    /// 16 bpp, 5 bits red, 6 bits green, 5 bits blue
+    pub const RGB565: Self = Self::new(16, Bitfield::new(11, 5), Bitfield::new(5, 6), Bitfield::new(0, 5), Bitfield::new(0, 0));
+
+    pub const fn new(bits_per_pixel: u32, red: Bitfield, green: Bitfield, blue: Bitfield, transp: Bitfield) -> Self {
+        Self { bits_per_pixel, red, green, blue, transp }
+    }
+
+    #[inline]
+    pub fn bytes_per_pixel(&self) -> usize {
+        (self.bits_per_pixel as usize).div_ceil(8)
+    }
+
+    /// Converts a color into the device pixel word
+    #[inline]
+    pub fn pack(&self, color: Color) -> u32 {
+        self.red.pack(color.r) | self.green.pack(color.g) | self.blue.pack(color.b) | self.transp.pack(color.a)
+    }
+
+    /// Writes a color into `dst`, which must hold exactly one device pixel
+    #[inline]
+    pub fn write_pixel(&self, dst: &mut [u8], color: Color) {
+        let bytes = self.pack(color).to_ne_bytes();
+        let len = dst.len();
+        if cfg!(target_endian = "little") {
+            dst.copy_from_slice(&bytes[..len]);
+        } else {
+            dst.copy_from_slice(&bytes[4 - len..]);
+        }
+    }
+
+    /// Accepts 16, 24 and 32 bpp true color layouts only
+    ///
+    /// Pixels are written whole bytes at a time, so packed 1 bpp (and 8 bpp palette)
+    /// framebuffers are refused. Black and white panels work through drivers that expose
+    /// them as RGB565, as fbtft does for the SSD1306, with `set_monochrome` choosing the
+    /// lit pixels.
+    fn validate(&self) -> Result<(), FramebufferError> {
+        if !matches!(self.bits_per_pixel, 16 | 24 | 32) {
+            return Err(FramebufferError::InvalidDeviceCapabilities(
+                format!("Unsupported bits per pixel: {}, expected 16, 24 or 32", self.bits_per_pixel)
+            ));
+        }
+
+        for (name, field) in [("red", self.red), ("green", self.green), ("blue", self.blue), ("transp", self.transp)] {
+            if field.length > 8 || field.offset + field.length > self.bits_per_pixel {
+                return Err(FramebufferError::InvalidDeviceCapabilities(
+                    format!("Unsupported {} bitfield: offset {}, length {}", name, field.offset, field.length)
+                ));
+            }
+        }
+
+        if self.red.length == 0 || self.green.length == 0 || self.blue.length == 0 {
+            return Err(FramebufferError::InvalidDeviceCapabilities(
+                "Color bitfields missing, grayscale and palette modes are not supported".to_string()
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -65,6 +210,7 @@ pub enum FramebufferError {
     IoError(io::Error),
     InvalidCoordinate { x: u16, y: u16 },
     FontLoadError(String),
+    ImageLoadError(String),
     FontNotLoaded(FontId),
     DeviceError(String),
     InvalidDeviceCapabilities(String),
@@ -79,6 +225,7 @@ impl std::fmt::Display for FramebufferError {
                 write!(f, "Invalid coordinate: ({}, {})", x, y)
             }
             FramebufferError::FontLoadError(msg) => write!(f, "Font load error: {}", msg),
+            FramebufferError::ImageLoadError(msg) => write!(f, "Image load error: {}", msg),
             FramebufferError::FontNotLoaded(font_id) => write!(f, "Font not loaded: {:?}", font_id),
             FramebufferError::DeviceError(msg) => write!(f, "Device error: {}", msg),
             FramebufferError::InvalidDeviceCapabilities(msg) => write!(f, "Invalid device capabilities: {}", msg),
@@ -101,28 +248,25 @@ impl From<io::Error> for FramebufferError {
 pub struct DeviceInfo {
     pub width: u16,
     pub height: u16,
-    pub bits_per_pixel: u32,
+    pub pixel_format: PixelFormat,
     pub line_length: u32,
     pub buffer_size: usize,
 }
 
 impl DeviceInfo {
-    fn validate(&self) -> Result<(), FramebufferError> {
-        if self.width != DISPLAY_WIDTH {
+    pub(crate) fn validate(&self) -> Result<(), FramebufferError> {
+        if self.width == 0 || self.height == 0 {
             return Err(FramebufferError::InvalidDeviceCapabilities(
-                format!("Width mismatch: expected {}, got {}", DISPLAY_WIDTH, self.width)
+                format!("Invalid resolution: {}x{}", self.width, self.height)
             ));
         }
         
-        if self.height != DISPLAY_HEIGHT {
-            return Err(FramebufferError::InvalidDeviceCapabilities(
-                format!("Height mismatch: expected {}, got {}", DISPLAY_HEIGHT, self.height)
-            ));
-        }
+        self.pixel_format.validate()?;
         
-        if self.bits_per_pixel != 32 {
+        let row_size = (self.width as usize) * self.pixel_format.bytes_per_pixel();
+        if (self.line_length as usize) < row_size {
             return Err(FramebufferError::InvalidDeviceCapabilities(
-                format!("Bits per pixel mismatch: expected 32, got {}", self.bits_per_pixel)
+                format!("Line length {} too short for {} pixels", self.line_length, self.width)
             ));
         }
         
@@ -138,31 +282,221 @@ impl DeviceInfo {
     }
 }
 
-struct PixelBuffer<'a> {
-    data: &'a mut [u8],
+/// Clockwise rotation applied when mapping the canvas onto the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl Rotation {
+    pub fn from_degrees(degrees: u16) -> Option<Self> {
+        match degrees {
+            0 => Some(Rotation::Deg0),
+            90 => Some(Rotation::Deg90),
+            180 => Some(Rotation::Deg180),
+            270 => Some(Rotation::Deg270),
+            _ => None,
+        }
+    }
+    
+    #[inline]
+    fn swaps_axes(self) -> bool {
+        matches!(self, Rotation::Deg90 | Rotation::Deg270)
+    }
+}
+
+/// Rotation and mirroring between logical (canvas) and device coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Orientation {
+    rotation: Rotation,
+    mirror_x: bool,
+    mirror_y: bool,
+}
+
+impl Orientation {
+    #[inline]
+    fn is_identity(&self) -> bool {
+        *self == Orientation::default()
+    }
+    
+    /// Returns the logical width and height for the given device size
+    fn logical_size(&self, device_width: u16, device_height: u16) -> (u16, u16) {
+        if self.rotation.swaps_axes() {
+            (device_height, device_width)
+        } else {
+            (device_width, device_height)
+        }
+    }
+    
+    /// Maps a logical pixel to device coordinates; mirroring is applied before rotation
+    #[inline]
+    fn map_to_device(&self, x: u16, y: u16, width: u16, height: u16) -> (u16, u16) {
+        let x = if self.mirror_x { width - 1 - x } else { x };
+        let y = if self.mirror_y { height - 1 - y } else { y };
+        
+        match self.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (height - 1 - y, x),
+            Rotation::Deg180 => (width - 1 - x, height - 1 - y),
+            Rotation::Deg270 => (y, width - 1 - x),
+        }
+    }
+}
+
+/// Axis-aligned rectangle in screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub const fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Builds a rectangle from signed edge coordinates (exclusive end), clipped to `bounds`
+    pub fn from_edges_clipped(x0: i32, y0: i32, x1: i32, y1: i32, bounds: Rect) -> Option<Self> {
+        let left = x0.max(bounds.x as i32);
+        let top = y0.max(bounds.y as i32);
+        let right = x1.min(bounds.right() as i32);
+        let bottom = y1.min(bounds.bottom() as i32);
+
+        if left >= right || top >= bottom {
+            return None;
+        }
+
+        Some(Self::new(left as u16, top as u16, (right - left) as u16, (bottom - top) as u16))
+    }
+
+    #[inline]
+    pub fn right(&self) -> u32 {
+        self.x as u32 + self.width as u32
+    }
+
+    #[inline]
+    pub fn bottom(&self) -> u32 {
+        self.y as u32 + self.height as u32
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// True if the pixel at (`x`, `y`) lies inside the rectangle
+    #[inline]
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.x && y >= self.y && (x as u32) < self.right() && (y as u32) < self.bottom()
+    }
+
+    /// Returns the overlapping area of both rectangles, if any
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        Self::from_edges_clipped(
+            other.x as i32,
+            other.y as i32,
+            other.right() as i32,
+            other.bottom() as i32,
+            *self,
+        )
+    }
+
+    /// Returns the smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+
+        Rect::new(left, top, (right - left as u32) as u16, (bottom - top as u32) as u16)
+    }
+
+    /// True if the rectangles overlap or share an edge
+    fn touches(&self, other: &Rect) -> bool {
+        self.x as u32 <= other.right()
+            && other.x as u32 <= self.right()
+            && self.y as u32 <= other.bottom()
+            && other.y as u32 <= self.bottom()
+    }
+}
+
+/// Upper bound on tracked dirty rectangles before they collapse into one bounding box
+const MAX_DIRTY_RECTS: usize = 8;
+
+/// Set of screen regions modified since the last flush
+#[derive(Debug, Default)]
+struct DirtyRegion {
+    rects: Vec<Rect>,
+}
+
+impl DirtyRegion {
+    fn add(&mut self, rect: Rect) {
+        // Pixels set one by one mostly land in an area already marked
+        if rect.is_empty() || self.rects.iter().any(|dirty| dirty.union(&rect) == *dirty) {
+            return;
+        }
+
+        let mut merged = rect;
+        let mut i = 0;
+        while i < self.rects.len() {
+            if self.rects[i].touches(&merged) {
+                merged = merged.union(&self.rects.swap_remove(i));
+                i = 0;
+            } else {
+                i += 1;
+            }
+        }
+        self.rects.push(merged);
+
+        if self.rects.len() > MAX_DIRTY_RECTS {
+            let bounds = self.rects.iter().skip(1).fold(self.rects[0], |acc, r| acc.union(r));
+            self.rects.clear();
+            self.rects.push(bounds);
+        }
+    }
+
+    fn take(&mut self) -> Vec<Rect> {
+        std::mem::take(&mut self.rects)
+    }
+}
+
+/// In-memory RGBA canvas that all drawing operations render into
+#[derive(Debug, Clone, Default)]
+struct PixelBuffer {
+    data: Vec<u8>,
     width: u16,
     height: u16,
     stride: usize,
 }
 
-impl<'a> PixelBuffer<'a> {
-    fn new(data: &'a mut [u8], width: u16, height: u16, stride: usize) -> Result<Self, FramebufferError> {
-        let required_size = stride * (height as usize);
-        if data.len() < required_size {
-            return Err(FramebufferError::BufferSizeMismatch {
-                expected: required_size,
-                actual: data.len(),
-            });
-        }
-        
-        Ok(Self {
-            data,
+impl PixelBuffer {
+    fn new(width: u16, height: u16, fill: Color) -> Self {
+        let stride = (width as usize) * BYTES_PER_PIXEL;
+
+        let mut buffer = Self {
+            data: vec![0; stride * (height as usize)],
             width,
             height,
             stride,
-        })
+        };
+        if fill != Color::transparent() {
+            buffer.clear(buffer.bounds(), fill);
+        }
+        buffer
     }
-    
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// Blends `color` over the pixel, keeping translucent destinations translucent
     #[inline]
     fn set_pixel(&mut self, x: u16, y: u16, color: Color) -> Result<(), FramebufferError> {
         if x >= self.width || y >= self.height {
@@ -171,280 +505,725 @@ impl<'a> PixelBuffer<'a> {
         
         let offset = (y as usize) * self.stride + (x as usize) * BYTES_PER_PIXEL;
         
-        if offset + BYTES_PER_PIXEL > self.data.len() {
-            return Err(FramebufferError::InvalidCoordinate { x, y });
-        }
-        
         if color.a == 255 {
             let rgba = color.to_bytes();
             self.data[offset..offset + 4].copy_from_slice(&rgba);
         } else if color.a > 0 {
-            let bg_r = self.data[offset];
-            let bg_g = self.data[offset + 1];
-            let bg_b = self.data[offset + 2];
-            
+            // Porter-Duff "over"; a plain mix when the destination is opaque,
+            // while overlay layers that start out transparent keep their coverage
             let alpha = color.a as u32;
-            let inv_alpha = 255 - alpha;
+            let bg_weight = self.data[offset + 3] as u32 * (255 - alpha) / 255;
+            let out_alpha = alpha + bg_weight;
+            let blend = |src: u8, dst: u8| ((src as u32 * alpha + dst as u32 * bg_weight) / out_alpha) as u8;
             
-            self.data[offset] = ((color.r as u32 * alpha + bg_r as u32 * inv_alpha) / 255) as u8;
-            self.data[offset + 1] = ((color.g as u32 * alpha + bg_g as u32 * inv_alpha) / 255) as u8;
-            self.data[offset + 2] = ((color.b as u32 * alpha + bg_b as u32 * inv_alpha) / 255) as u8;
-            self.data[offset + 3] = 255;
+            self.data[offset] = blend(color.r, self.data[offset]);
+            self.data[offset + 1] = blend(color.g, self.data[offset + 1]);
+            self.data[offset + 2] = blend(color.b, self.data[offset + 2]);
+            self.data[offset + 3] = out_alpha as u8;
         }
         
         Ok(())
     }
     
-    fn clear(&mut self, color: Color) -> Result<(), FramebufferError> {
+    /// Overwrites a rectangle that must lie within the buffer, alpha included
+    fn clear(&mut self, rect: Rect, color: Color) {
         let rgba = color.to_bytes();
         
-        for y in 0..self.height {
-            let row_start = (y as usize) * self.stride;
-            let row_end = row_start + (self.width as usize) * BYTES_PER_PIXEL;
-            
-            if row_end > self.data.len() {
-                return Err(FramebufferError::BufferSizeMismatch {
-                    expected: row_end,
-                    actual: self.data.len(),
-                });
+        for y in rect.y..rect.bottom() as u16 {
+            for pixel in self.row_span_mut(&rect, y).chunks_exact_mut(BYTES_PER_PIXEL) {
+                pixel.copy_from_slice(&rgba);
             }
-            
-            for x in 0..self.width {
-                let offset = row_start + (x as usize) * BYTES_PER_PIXEL;
-                self.data[offset..offset + 4].copy_from_slice(&rgba);
+        }
+    }
+
+    /// Fills a rectangle that must lie within the buffer
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if color.a == 255 {
+            self.clear(rect, color);
+        } else if color.a > 0 {
+            for y in rect.y..rect.bottom() as u16 {
+                for x in rect.x..rect.right() as u16 {
+                    let _ = self.set_pixel(x, y, color);
+                }
             }
         }
-        
-        Ok(())
+    }
+
+    /// Returns the bytes of one row segment covered by `rect`
+    #[inline]
+    fn row_span(&self, rect: &Rect, y: u16) -> &[u8] {
+        let start = (y as usize) * self.stride + (rect.x as usize) * BYTES_PER_PIXEL;
+        &self.data[start..start + (rect.width as usize) * BYTES_PER_PIXEL]
+    }
+
+    #[inline]
+    fn row_span_mut(&mut self, rect: &Rect, y: u16) -> &mut [u8] {
+        let start = (y as usize) * self.stride + (rect.x as usize) * BYTES_PER_PIXEL;
+        &mut self.data[start..start + (rect.width as usize) * BYTES_PER_PIXEL]
     }
 }
 
 /// Framebuffer interface for direct display access
+///
+/// Drawing goes into an off-screen canvas; `flush` copies the regions that
+/// changed since the previous flush to the device in a single pass.
+/// All coordinates are logical, i.e. after rotation and mirroring.
+///
+/// Drawing is limited to the innermost rectangle pushed with `push_clip` and
+/// goes to the layer selected with `set_layer`.
 pub struct Framebuffer {
-    mmap: MmapMut,
-    device_info: DeviceInfo,
-    fonts: HashMap<FontId, Font>,
-    glyph_cache: HashMap<(FontId, char, u32), (Vec<u8>, usize, usize)>,
+    backend: Box<dyn Backend>,
+    orientation: Orientation,
+    /// Buffer of the active layer
+    canvas: PixelBuffer,
+    layers: Vec<Layer>,
+    active_layer: usize,
+    /// Reused target for blending overlays during `flush`
+    composite: PixelBuffer,
+    clip_stack: Vec<Rect>,
+    dirty: DirtyRegion,
+    /// Regions of the latest flushes, redrawn into the other pages of a page-flipping backend
+    recent_flushes: VecDeque<Vec<Rect>>,
+    dithering: Dithering,
+    /// Luminance threshold when the panel is driven in black and white
+    monochrome: Option<u8>,
+    fonts: FontRegistry,
+    glyph_cache: GlyphCache,
 }
 
 impl Framebuffer {
     /// Creates a new framebuffer instance for the given device path
     pub fn new(device_path: &str) -> Result<Self, FramebufferError> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .custom_flags(libc::O_SYNC)
-            .open(device_path)?;
-
-        let device_info = Self::get_device_info(&file)?;
-        
+        let backend = FbdevBackend::open(device_path)?;
+        Self::with_backend(Box::new(backend))
+    }
+    
+    /// Creates a framebuffer that renders into memory only, for tests and development
+    pub fn headless(width: u16, height: u16) -> Result<Self, FramebufferError> {
+        Self::with_backend(Box::new(MemoryBackend::new(width, height, PixelFormat::RGBA8888)))
+    }
+    
+    /// Creates a framebuffer that flushes into the given backend
+    pub fn with_backend(backend: Box<dyn Backend>) -> Result<Self, FramebufferError> {
+        let device_info = backend.device_info();
         device_info.validate()?;
-
-        let mmap = unsafe {
-            MmapOptions::new()
-                .len(device_info.buffer_size)
-                .map_mut(&file)
-                .map_err(|e| FramebufferError::DeviceError(format!("Failed to mmap framebuffer: {}", e)))?
-        };
+        
+        let canvas = PixelBuffer::new(device_info.width, device_info.height, Color::black());
 
         Ok(Self {
-            mmap,
-            device_info,
-            fonts: HashMap::new(),
-            glyph_cache: HashMap::new(),
+            backend,
+            orientation: Orientation::default(),
+            canvas,
+            layers: vec![Layer::base()],
+            active_layer: 0,
+            composite: PixelBuffer::default(),
+            clip_stack: Vec::new(),
+            dirty: DirtyRegion::default(),
+            recent_flushes: VecDeque::new(),
+            dithering: Dithering::None,
+            monochrome: None,
+            fonts: FontRegistry::new(),
+            glyph_cache: GlyphCache::new(DEFAULT_GLYPH_CACHE_BYTES),
         })
     }
     
-    fn get_device_info(file: &File) -> Result<DeviceInfo, FramebufferError> {
-        use std::mem;
-        
-        let fd = file.as_raw_fd();
+    /// Returns the detected device geometry and pixel format
+    pub fn device_info(&self) -> &DeviceInfo {
+        self.backend.device_info()
+    }
+    
+    /// Turns the panel off or back on; drawing and flushing keep working while blanked
+    pub fn set_blank(&mut self, blank: bool) -> Result<(), FramebufferError> {
+        self.backend.set_blank(blank)
+    }
+    
+    /// Lets the device handle its events, such as socket viewers connecting, without a redraw
+    pub fn poll(&mut self) -> Result<(), FramebufferError> {
+        self.backend.poll()
+    }
+    
+    /// Rotates the screen content clockwise; all layers and clips are reset
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.orientation.rotation = rotation;
+        self.reset_canvas();
+    }
+    
+    /// Mirrors the screen content horizontally and/or vertically; all layers and clips are reset
+    pub fn set_mirroring(&mut self, horizontal: bool, vertical: bool) {
+        self.orientation.mirror_x = horizontal;
+        self.orientation.mirror_y = vertical;
+        self.reset_canvas();
+    }
+    
+    pub fn rotation(&self) -> Rotation {
+        self.orientation.rotation
+    }
+    
+    fn reset_canvas(&mut self) {
+        let info = self.backend.device_info();
+        let (width, height) = self.orientation.logical_size(info.width, info.height);
+        self.canvas = PixelBuffer::new(width, height, Color::black());
+        self.reset_layers();
+        self.clip_stack.clear();
+        self.dirty.take();
+        self.dirty.add(self.canvas.bounds());
+    }
+    
+    /// Selects how colors are reduced on panels with fewer than 8 bits per channel
+    pub fn set_dithering(&mut self, dithering: Dithering) {
+        self.dithering = dithering;
+        self.dirty.add(self.canvas.bounds());
+    }
+    
+    pub fn dithering(&self) -> Dithering {
+        self.dithering
+    }
+    
+    /// Shows everything in black and white, lighting pixels whose luminance reaches `threshold`
+    ///
+    /// With dithering enabled the threshold is replaced by the dither pattern.
+    /// `None` returns to color output.
+    pub fn set_monochrome(&mut self, threshold: Option<u8>) {
+        self.monochrome = threshold;
+        self.dirty.add(self.canvas.bounds());
+    }
+    
+    /// Width of the drawable area in pixels
+    #[inline]
+    pub fn width(&self) -> u16 {
+        self.canvas.width
+    }
+    
+    /// Height of the drawable area in pixels
+    #[inline]
+    pub fn height(&self) -> u16 {
+        self.canvas.height
+    }
+    
+    /// Restricts drawing to `rect`, within any clip already in effect, until `pop_clip`
+    pub fn push_clip(&mut self, rect: Rect) {
+        let clip = self.clip_rect();
+        let clipped = clip.intersect(&rect).unwrap_or(Rect::new(clip.x, clip.y, 0, 0));
+        self.clip_stack.push(clipped);
+    }
+    
+    /// Restores the clip that was in effect before the last `push_clip`
+    pub fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+    
+    /// The area drawing operations are currently limited to
+    #[inline]
+    pub fn clip_rect(&self) -> Rect {
+        self.clip_stack.last().copied().unwrap_or_else(|| self.canvas.bounds())
+    }
+    
+    /// Sets a single pixel at the specified coordinates
+    ///
+    /// Pixels on screen but outside the clip rectangle are silently dropped.
+    /// Each call marks its pixel for the next flush; the drawing primitives
+    /// instead mark one bounding rectangle per call.
+    #[inline]
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: Color) -> Result<(), FramebufferError> {
+        if !self.clip_rect().contains(x, y) {
+            return if self.canvas.bounds().contains(x, y) {
+                Ok(())
+            } else {
+                Err(FramebufferError::InvalidCoordinate { x, y })
+            };
+        }
         
-        #[repr(C)]
-        struct FbVarScreeninfo {
-            xres: u32,
-            yres: u32,
-            xres_virtual: u32,
-            yres_virtual: u32,
-            xoffset: u32,
-            yoffset: u32,
-            bits_per_pixel: u32,
-            grayscale: u32,
-            padding: [u8; 128],
+        self.canvas.set_pixel(x, y, color)?;
+        self.dirty.add(Rect::new(x, y, 1, 1));
+        Ok(())
+    }
+
+    /// Clears the clip rectangle, the entire screen unless a clip is set, with the specified color
+    pub fn clear_screen(&mut self, color: Color) -> Result<(), FramebufferError> {
+        let clip = self.clip_rect();
+        self.canvas.clear(clip, color);
+        self.dirty.add(clip);
+        Ok(())
+    }
+    
+    /// Blends a pixel given in signed coordinates, dropping it if outside the clip
+    #[inline]
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        let clip = self.clip_rect();
+        if x >= clip.x as i32 && y >= clip.y as i32 && x < clip.right() as i32 && y < clip.bottom() as i32 {
+            let _ = self.canvas.set_pixel(x as u16, y as u16, color);
+        }
+    }
+    
+    /// Marks the area between the given edges (end exclusive) for the next flush
+    fn mark_dirty(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        if let Some(rect) = Rect::from_edges_clipped(x0, y0, x1, y1, self.clip_rect()) {
+            self.dirty.add(rect);
+        }
+    }
+    
+    /// Copies all regions drawn since the last flush to the device,
+    /// converting them into the device orientation and pixel format
+    ///
+    /// If the device fails, the regions stay marked for the next flush.
+    pub fn flush(&mut self) -> Result<(), FramebufferError> {
+        let rects = self.dirty.take();
+        if rects.is_empty() {
+            return Ok(());
         }
         
-        #[repr(C)]
-        struct FbFixScreeninfo {
-            id: [u8; 16],
-            smem_start: usize,
-            smem_len: u32,
-            type_: u32,
-            type_aux: u32,
-            visual: u32,
-            xpanstep: u16,
-            ypanstep: u16,
-            ywrapstep: u16,
-            line_length: u32,
-            padding: [u8; 32],
+        if let Err(e) = self.present_rects(&rects) {
+            for rect in rects {
+                self.dirty.add(rect);
+            }
+            return Err(e);
         }
         
-        const FBIOGET_VSCREENINFO: libc::c_ulong = 0x4600;
-        const FBIOGET_FSCREENINFO: libc::c_ulong = 0x4602;
+        let pages = self.backend.page_count();
+        if pages > 1 {
+            self.recent_flushes.push_back(rects);
+            while self.recent_flushes.len() >= pages {
+                self.recent_flushes.pop_front();
+            }
+        }
+        Ok(())
+    }
+    
+    fn present_rects(&mut self, rects: &[Rect]) -> Result<(), FramebufferError> {
+        // The page about to be drawn last received the flush `pages` flushes ago
+        let rects = if self.backend.page_count() > 1 {
+            let mut region = DirtyRegion::default();
+            for rect in self.recent_flushes.iter().flatten().chain(rects) {
+                region.add(*rect);
+            }
+            region.take()
+        } else {
+            rects.to_vec()
+        };
         
-        let mut var_info: FbVarScreeninfo = unsafe { mem::zeroed() };
-        let mut fix_info: FbFixScreeninfo = unsafe { mem::zeroed() };
+        let info = self.backend.device_info().clone();
+        let mut quantizer = Quantizer::new(info.pixel_format, self.dithering, self.monochrome);
+        let source = layers::composite(&self.canvas, &self.layers, self.active_layer, &rects, &mut self.composite)
+            .unwrap_or_else(|| layers::buffer_at(&self.canvas, &self.layers, self.active_layer, 0));
+        let buffer = self.backend.buffer_mut();
         
-        let var_result = unsafe {
-            libc::ioctl(fd, FBIOGET_VSCREENINFO, &mut var_info as *mut _)
-        };
+        if self.orientation.is_identity() {
+            Self::copy_rects(source, &rects, &info, &mut quantizer, buffer)?;
+            return self.backend.present(&rects);
+        }
         
-        let fix_result = unsafe {
-            libc::ioctl(fd, FBIOGET_FSCREENINFO, &mut fix_info as *mut _)
-        };
+        Self::copy_rects_transformed(source, self.orientation, &rects, &info, &mut quantizer, buffer)?;
         
-        if var_result == 0 && fix_result == 0 {
-            Ok(DeviceInfo {
-                width: var_info.xres as u16,
-                height: var_info.yres as u16,
-                bits_per_pixel: var_info.bits_per_pixel,
-                line_length: fix_info.line_length,
-                buffer_size: fix_info.smem_len as usize,
-            })
-        } else {
-            Ok(DeviceInfo {
-                width: DISPLAY_WIDTH,
-                height: DISPLAY_HEIGHT,
-                bits_per_pixel: 32,
-                line_length: (FRAMEBUFFER_STRIDE as u32) * BYTES_PER_PIXEL as u32,
-                buffer_size: FRAMEBUFFER_SIZE,
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        let device_rects: Vec<Rect> = rects
+            .iter()
+            .map(|rect| {
+                let (x0, y0) = self.orientation.map_to_device(rect.x, rect.y, width, height);
+                let (x1, y1) = self.orientation.map_to_device(
+                    rect.right() as u16 - 1,
+                    rect.bottom() as u16 - 1,
+                    width,
+                    height,
+                );
+                Rect::new(x0.min(x1), y0.min(y1), x0.abs_diff(x1) + 1, y0.abs_diff(y1) + 1)
             })
+            .collect();
+        
+        self.backend.present(&device_rects)
+    }
+    
+    fn copy_rects(
+        canvas: &PixelBuffer,
+        rects: &[Rect],
+        info: &DeviceInfo,
+        quantizer: &mut Quantizer,
+        buffer: &mut [u8],
+    ) -> Result<(), FramebufferError> {
+        let line_length = info.line_length as usize;
+        let format = info.pixel_format;
+        let device_bpp = format.bytes_per_pixel();
+        let passthrough = quantizer.is_passthrough();
+        let mut colors = Vec::new();
+
+        for rect in rects {
+            let dst_x = (rect.x as usize) * device_bpp;
+            let span = (rect.width as usize) * device_bpp;
+            quantizer.start_rect(rect.width);
+
+            for y in rect.y..rect.bottom() as u16 {
+                let dst = (y as usize) * line_length + dst_x;
+                if dst + span > buffer.len() {
+                    return Err(FramebufferError::BufferSizeMismatch {
+                        expected: dst + span,
+                        actual: buffer.len(),
+                    });
+                }
+
+                let src = canvas.row_span(rect, y);
+                let dst = &mut buffer[dst..dst + span];
+                
+                if !passthrough {
+                    quantizer.quantize_row(rect.x, y, src, &mut colors);
+                    for (color, out) in colors.iter().zip(dst.chunks_exact_mut(device_bpp)) {
+                        format.write_pixel(out, *color);
+                    }
+                } else if format == PixelFormat::RGBA8888 {
+                    dst.copy_from_slice(src);
+                } else {
+                    for (pixel, out) in src.chunks_exact(BYTES_PER_PIXEL).zip(dst.chunks_exact_mut(device_bpp)) {
+                        format.write_pixel(out, Color::from_bytes(pixel));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+    
+    /// Per-pixel copy used when the canvas is rotated or mirrored
+    fn copy_rects_transformed(
+        canvas: &PixelBuffer,
+        orientation: Orientation,
+        rects: &[Rect],
+        info: &DeviceInfo,
+        quantizer: &mut Quantizer,
+        buffer: &mut [u8],
+    ) -> Result<(), FramebufferError> {
+        let line_length = info.line_length as usize;
+        let format = info.pixel_format;
+        let device_bpp = format.bytes_per_pixel();
+        let (width, height) = (canvas.width, canvas.height);
+        let mut colors = Vec::new();
+        
+        for rect in rects {
+            quantizer.start_rect(rect.width);
+            
+            for y in rect.y..rect.bottom() as u16 {
+                let src = canvas.row_span(rect, y);
+                if quantizer.is_passthrough() {
+                    colors.clear();
+                    colors.extend(src.chunks_exact(BYTES_PER_PIXEL).map(Color::from_bytes));
+                } else {
+                    quantizer.quantize_row(rect.x, y, src, &mut colors);
+                }
+                
+                for (x, color) in (rect.x..).zip(&colors) {
+                    let (dx, dy) = orientation.map_to_device(x, y, width, height);
+                    let dst = (dy as usize) * line_length + (dx as usize) * device_bpp;
+                    if dst + device_bpp > buffer.len() {
+                        return Err(FramebufferError::BufferSizeMismatch {
+                            expected: dst + device_bpp,
+                            actual: buffer.len(),
+                        });
+                    }
+                    
+                    format.write_pixel(&mut buffer[dst..dst + device_bpp], *color);
+                }
+            }
         }
+        
+        Ok(())
+    }
+    
+    /// Writes the current screen contents as a binary PPM image
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> Result<(), FramebufferError> {
+        let frame = self.composited();
+        image::write_ppm(writer, &frame.data, frame.width, frame.height)?;
+        Ok(())
     }
     
-    /// Loads a font from the specified path or uses the default path for the font ID
-    pub fn load_font(&mut self, font_id: FontId, font_path: Option<&str>) -> Result<(), FramebufferError> {
-        let path = font_path.unwrap_or_else(|| font_id.default_path());
-        let font = Self::load_font_from_path(path)?;
-        self.fonts.insert(font_id, font);
+    /// Writes the current screen contents as a PNG image
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> Result<(), FramebufferError> {
+        let frame = self.composited();
+        image::write_png(writer, &frame.data, frame.width, frame.height)?;
         Ok(())
     }
     
-    fn load_font_from_path(font_path: &str) -> Result<Font, FramebufferError> {
-        let font_data = std::fs::read(font_path)
-            .map_err(|e| FramebufferError::FontLoadError(format!("Failed to read font file '{}': {}", font_path, e)))?;
+    /// Saves a screenshot of all visible layers, as PNG if the path ends in `.png` and PPM otherwise
+    ///
+    /// The layers hold everything drawn so far, including changes not yet flushed.
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> Result<(), FramebufferError> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(File::create(path)?);
         
-        let font_settings = FontSettings {
-            scale: 40.0,
-            ..FontSettings::default()
-        };
+        let is_png = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if is_png {
+            self.write_png(&mut writer)?;
+        } else {
+            self.write_ppm(&mut writer)?;
+        }
         
-        Font::from_bytes(font_data, font_settings)
-            .map_err(|e| FramebufferError::FontLoadError(format!("Failed to parse font '{}': {}", font_path, e)))
+        writer.flush()?;
+        Ok(())
     }
+}
 
-    /// Sets a single pixel at the specified coordinates
-    #[inline]
-    pub fn set_pixel(&mut self, x: u16, y: u16, color: Color) -> Result<(), FramebufferError> {
-        let mut buffer = PixelBuffer::new(
-            &mut self.mmap[..],
-            self.device_info.width,
-            self.device_info.height,
-            self.device_info.line_length as usize,
-        )?;
-        
-        buffer.set_pixel(x, y, color)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// What a `TestBackend` was asked to present, and whether it fails
+    #[derive(Default)]
+    struct Presented {
+        regions: Vec<Vec<Rect>>,
+        fail: bool,
     }
 
-    /// Clears the entire screen with the specified color
-    pub fn clear_screen(&mut self, color: Color) -> Result<(), FramebufferError> {
-        let mut buffer = PixelBuffer::new(
-            &mut self.mmap[..],
-            self.device_info.width,
-            self.device_info.height,
-            self.device_info.line_length as usize,
-        )?;
-        
-        buffer.clear(color)
+    /// Memory display that records the regions presented and can be made to fail
+    struct TestBackend {
+        memory: MemoryBackend,
+        pages: usize,
+        presented: Arc<Mutex<Presented>>,
     }
-    
-    /// Renders text at the specified position with the given font and color
-    pub fn write_text(
-        &mut self,
-        text: &str,
-        position: Point,
-        size: f32,
-        color: Color,
-        font_id: FontId,
-    ) -> Result<(), FramebufferError> {
-        if !self.fonts.contains_key(&font_id) {
-            return Err(FramebufferError::FontNotLoaded(font_id));
+
+    impl Backend for TestBackend {
+        fn device_info(&self) -> &DeviceInfo {
+            self.memory.device_info()
         }
-        
-        let mut cursor_x = position.x as f32;
-        let cursor_y = position.y as i32;
-
-        let mut max_ascent = 0;
-        for ch in text.chars() {
-            if ch == ' ' { continue; }
-            let font = self.fonts.get(&font_id).unwrap();
-            let (metrics, _) = font.rasterize(ch, size);
-            let ascent = metrics.height as i32 + metrics.ymin;
-            max_ascent = max_ascent.max(ascent);
-        }
-
-        for ch in text.chars() {
-            if ch == ' ' {
-                cursor_x += size * 0.3;
-                continue;
-            }
 
-            let size_key = (size * 100.0) as u32;
-            let cache_key = (font_id, ch, size_key);
-            
-            let (metrics, bitmap) = if let Some((cached_bitmap, _width, _height)) = self.glyph_cache.get(&cache_key) {
-                let font = self.fonts.get(&font_id).unwrap();
-                let (metrics, _) = font.rasterize(ch, size);
-                (metrics, cached_bitmap.clone())
-            } else {
-                let font = self.fonts.get(&font_id).unwrap();
-                let (metrics, bitmap) = font.rasterize(ch, size);
-                self.glyph_cache.insert(cache_key, (bitmap.clone(), metrics.width, metrics.height));
-                (metrics, bitmap)
-            };
-            
-            let glyph_x = (cursor_x + metrics.xmin as f32).round() as i32;
-            let char_ascent = metrics.height as i32 + metrics.ymin;
-            let glyph_y = cursor_y - max_ascent + (max_ascent - char_ascent);
-
-            for y in 0..metrics.height {
-                for x in 0..metrics.width {
-                    let pixel_x = glyph_x + x as i32;
-                    let pixel_y = glyph_y + y as i32;
-
-                    if pixel_x >= 0 && pixel_x < self.device_info.width as i32 &&
-                       pixel_y >= 0 && pixel_y < self.device_info.height as i32 {
-                        
-                        let bitmap_index = y * metrics.width + x;
-                        if bitmap_index < bitmap.len() {
-                            let alpha = bitmap[bitmap_index];
-                            if alpha > 0 {
-                                let blended_color = Color::new(color.r, color.g, color.b, alpha);
-                                self.set_pixel(pixel_x as u16, pixel_y as u16, blended_color)?;
-                            }
-                        }
-                    }
-                }
+        fn buffer_mut(&mut self) -> &mut [u8] {
+            self.memory.buffer_mut()
+        }
+
+        fn present(&mut self, regions: &[Rect]) -> Result<(), FramebufferError> {
+            let mut presented = self.presented.lock().unwrap();
+            if presented.fail {
+                return Err(FramebufferError::DeviceError("unplugged".to_string()));
             }
+            presented.regions.push(regions.to_vec());
+            Ok(())
+        }
 
-            cursor_x += metrics.advance_width;
+        fn page_count(&self) -> usize {
+            self.pages
         }
+    }
 
-        Ok(())
+    fn recorded(pages: usize) -> (Framebuffer, Arc<Mutex<Presented>>) {
+        let presented = Arc::new(Mutex::new(Presented::default()));
+        let backend = TestBackend {
+            memory: MemoryBackend::new(160, 128, PixelFormat::RGB565),
+            pages,
+            presented: presented.clone(),
+        };
+        (Framebuffer::with_backend(Box::new(backend)).unwrap(), presented)
     }
-    
-    /// Flushes any pending operations (currently a no-op)
-    #[inline]
-    pub fn flush(&self) -> Result<(), FramebufferError> {
-        Ok(())
+
+    #[test]
+    fn rect_intersect_and_union() {
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(5, 5, 10, 10);
+        assert_eq!(a.intersect(&b), Some(Rect::new(5, 5, 5, 5)));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 15, 15));
+        assert_eq!(a.intersect(&Rect::new(10, 0, 5, 5)), None);
+    }
+
+    #[test]
+    fn rect_from_edges_is_clipped() {
+        let bounds = Rect::new(0, 0, 160, 128);
+        assert_eq!(Rect::from_edges_clipped(-5, -5, 10, 10, bounds), Some(Rect::new(0, 0, 10, 10)));
+        assert_eq!(Rect::from_edges_clipped(150, 120, 200, 200, bounds), Some(Rect::new(150, 120, 10, 8)));
+        assert_eq!(Rect::from_edges_clipped(170, 0, 180, 10, bounds), None);
+    }
+
+    #[test]
+    fn dirty_rects_merge_when_touching() {
+        let mut dirty = DirtyRegion::default();
+        dirty.add(Rect::new(0, 0, 10, 10));
+        dirty.add(Rect::new(10, 0, 10, 10));
+        dirty.add(Rect::new(100, 100, 5, 5));
+        dirty.add(Rect::new(50, 50, 0, 5));
+
+        let mut rects = dirty.take();
+        rects.sort_by_key(|rect| rect.x);
+        assert_eq!(rects, vec![Rect::new(0, 0, 20, 10), Rect::new(100, 100, 5, 5)]);
+        assert!(dirty.take().is_empty());
+    }
+
+    #[test]
+    fn dirty_rects_collapse_into_bounding_box() {
+        let mut dirty = DirtyRegion::default();
+        for i in 0..=MAX_DIRTY_RECTS as u16 {
+            dirty.add(Rect::new(i * 10, i * 10, 2, 2));
+        }
+
+        let last = MAX_DIRTY_RECTS as u16 * 10;
+        assert_eq!(dirty.take(), vec![Rect::new(0, 0, last + 2, last + 2)]);
+    }
+
+    #[test]
+    fn dirty_rects_ignore_areas_already_marked() {
+        let mut dirty = DirtyRegion::default();
+        dirty.add(Rect::new(0, 0, 10, 10));
+        dirty.add(Rect::new(40, 0, 10, 10));
+        dirty.add(Rect::new(5, 5, 1, 1));
+        dirty.add(Rect::new(0, 0, 10, 10));
+        assert_eq!(dirty.rects, vec![Rect::new(0, 0, 10, 10), Rect::new(40, 0, 10, 10)]);
+    }
+
+    #[test]
+    fn primitives_mark_one_bounding_rect() {
+        let mut fb = Framebuffer::headless(160, 128).unwrap();
+        fb.load_default_fonts(DEFAULT_FONT_DIR).unwrap();
+
+        fb.draw_line(Point::new(10, 100), Point::new(50, 20), Color::white());
+        assert_eq!(fb.dirty.take(), vec![Rect::new(10, 20, 41, 81)]);
+
+        fb.draw_circle(Point::new(80, 64), 10, Color::white());
+        assert_eq!(fb.dirty.take(), vec![Rect::new(70, 54, 21, 21)]);
+
+        fb.write_text("Wide text", Point::new(20, 40), 12.0, Color::white(), FontId::Regular).unwrap();
+        let rects = fb.dirty.take();
+        assert_eq!(rects.len(), 1);
+        assert!(rects[0].width > 40 && rects[0].bottom() <= 40 + 4);
+
+        for x in 0..20 {
+            fb.set_pixel(x, 5, Color::white()).unwrap();
+        }
+        assert_eq!(fb.dirty.take(), vec![Rect::new(0, 5, 20, 1)]);
+    }
+
+    #[test]
+    fn failed_flushes_keep_their_regions() {
+        let (mut fb, presented) = recorded(1);
+        fb.fill_rect(Rect::new(10, 10, 20, 20), Color::white());
+        presented.lock().unwrap().fail = true;
+        assert!(fb.flush().is_err());
+
+        presented.lock().unwrap().fail = false;
+        fb.flush().unwrap();
+        fb.flush().unwrap();
+        assert_eq!(presented.lock().unwrap().regions, vec![vec![Rect::new(10, 10, 20, 20)]]);
+    }
+
+    #[test]
+    fn clips_nest_and_restore() {
+        let mut fb = Framebuffer::headless(160, 128).unwrap();
+        fb.push_clip(Rect::new(10, 10, 100, 50));
+        fb.push_clip(Rect::new(50, 0, 100, 30));
+        assert_eq!(fb.clip_rect(), Rect::new(50, 10, 60, 20));
+
+        fb.push_clip(Rect::new(0, 100, 10, 10));
+        assert!(fb.clip_rect().is_empty());
+        fb.dirty.take();
+        fb.clear_screen(Color::white()).unwrap();
+        assert!(fb.dirty.take().is_empty());
+        assert!(fb.set_pixel(0, 100, Color::white()).is_ok());
+        assert!(fb.set_pixel(160, 0, Color::white()).is_err());
+
+        fb.pop_clip();
+        assert_eq!(fb.clip_rect(), Rect::new(50, 10, 60, 20));
+        fb.pop_clip();
+        fb.pop_clip();
+        fb.pop_clip();
+        assert_eq!(fb.clip_rect(), Rect::new(0, 0, 160, 128));
+    }
+
+    #[test]
+    fn page_flipping_redraws_the_previous_flush_too() {
+        let (mut fb, presented) = recorded(2);
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(100, 100, 10, 10);
+        let c = Rect::new(100, 0, 10, 10);
+
+        for rect in [a, b, c] {
+            fb.fill_rect(rect, Color::white());
+            fb.flush().unwrap();
+        }
+        // Nothing drawn, so nothing to bring the other page up to date with
+        fb.flush().unwrap();
+
+        let mut regions = std::mem::take(&mut presented.lock().unwrap().regions);
+        for rects in &mut regions {
+            rects.sort_by_key(|rect| (rect.x, rect.y));
+        }
+        assert_eq!(regions, vec![vec![a], vec![a, b], vec![c, b]]);
+    }
+
+    #[test]
+    fn single_pages_only_redraw_the_new_region() {
+        let (mut fb, presented) = recorded(1);
+        fb.fill_rect(Rect::new(0, 0, 10, 10), Color::white());
+        fb.flush().unwrap();
+        fb.fill_rect(Rect::new(100, 100, 10, 10), Color::white());
+        fb.flush().unwrap();
+
+        let regions = std::mem::take(&mut presented.lock().unwrap().regions);
+        assert_eq!(regions, vec![vec![Rect::new(0, 0, 10, 10)], vec![Rect::new(100, 100, 10, 10)]]);
+    }
+
+    /// Logical corners of a 160x128 screen: top left, top right, bottom left, bottom right
+    const CORNERS: [(u16, u16); 4] = [(0, 0), (159, 0), (0, 127), (159, 127)];
+
+    fn device_corners(orientation: Orientation) -> [(u16, u16); 4] {
+        CORNERS.map(|(x, y)| orientation.map_to_device(x, y, 160, 128))
+    }
+
+    #[test]
+    fn rotation_moves_the_corners_clockwise() {
+        let rotated = |rotation| device_corners(Orientation { rotation, ..Orientation::default() });
+        assert_eq!(rotated(Rotation::Deg0), CORNERS);
+        assert_eq!(rotated(Rotation::Deg90), [(127, 0), (127, 159), (0, 0), (0, 159)]);
+        assert_eq!(rotated(Rotation::Deg180), [(159, 127), (0, 127), (159, 0), (0, 0)]);
+        assert_eq!(rotated(Rotation::Deg270), [(0, 159), (0, 0), (127, 159), (127, 0)]);
+    }
+
+    #[test]
+    fn mirroring_swaps_the_corners_before_rotating() {
+        for rotation in [Rotation::Deg0, Rotation::Deg90, Rotation::Deg180, Rotation::Deg270] {
+            let [top_left, top_right, bottom_left, bottom_right] = device_corners(Orientation { rotation, ..Orientation::default() });
+            let mirrored = |mirror_x, mirror_y| device_corners(Orientation { rotation, mirror_x, mirror_y });
+
+            assert_eq!(mirrored(true, false), [top_right, top_left, bottom_right, bottom_left], "{:?}", rotation);
+            assert_eq!(mirrored(false, true), [bottom_left, bottom_right, top_left, top_right], "{:?}", rotation);
+            assert_eq!(mirrored(true, true), [bottom_right, bottom_left, top_right, top_left], "{:?}", rotation);
+        }
+    }
+
+    #[test]
+    fn rotated_flushes_present_device_regions() {
+        let (mut fb, presented) = recorded(1);
+        fb.set_rotation(Rotation::Deg90);
+        fb.set_mirroring(true, false);
+        assert_eq!((fb.width(), fb.height()), (128, 160));
+        fb.flush().unwrap();
+
+        // Mirrored into the top right corner, which rotates into the bottom right of the device
+        fb.fill_rect(Rect::new(0, 0, 10, 2), Color::white());
+        fb.flush().unwrap();
+        assert_eq!(presented.lock().unwrap().regions.last().unwrap(), &vec![Rect::new(158, 118, 2, 10)]);
+    }
+
+    #[test]
+    fn pack_scales_channels_into_bitfields() {
+        let color = Color::new(0xFF, 0x80, 0x08, 0x40);
+        assert_eq!(PixelFormat::RGB565.pack(color), 0xF800 | (0x20 << 5) | 0x01);
+        assert_eq!(PixelFormat::RGBA8888.pack(color), 0x4008_80FF);
+        assert_eq!(PixelFormat::BGRA8888.pack(color), 0x40FF_8008);
+        assert_eq!(PixelFormat::XRGB8888.pack(color), 0x00FF_8008);
+    }
+
+    #[test]
+    fn write_pixel_stores_the_device_word() {
+        let mut rgb565 = [0u8; 2];
+        PixelFormat::RGB565.write_pixel(&mut rgb565, Color::new(0xFF, 0, 0, 255));
+        assert_eq!(u16::from_ne_bytes(rgb565), 0xF800);
+
+        let mut rgba = [0u8; 4];
+        PixelFormat::RGBA8888.write_pixel(&mut rgba, Color::new(1, 2, 3, 4));
+        assert_eq!(rgba, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn validate_refuses_unsupported_layouts() {
+        assert!(PixelFormat::RGB565.validate().is_ok());
+        assert!(PixelFormat::XRGB8888.validate().is_ok());
+
+        let mono = PixelFormat::new(1, Bitfield::new(0, 1), Bitfield::new(0, 1), Bitfield::new(0, 1), Bitfield::new(0, 0));
+        assert!(mono.validate().is_err());
+
+        let gray = PixelFormat::new(16, Bitfield::new(0, 8), Bitfield::new(0, 0), Bitfield::new(0, 0), Bitfield::new(0, 0));
+        assert!(gray.validate().is_err());
     }
 }
@@ -0,0 +1,46 @@
+// Bitmap blitting; pixels are alpha blended through the canvas
+
+use super::{Framebuffer, Point, Rect};
+use crate::image::Bitmap;
+
+impl Framebuffer {
+    /// Draws a bitmap with its top-left corner at `position`
+    pub fn draw_bitmap(&mut self, bitmap: &Bitmap, position: Point) {
+        let size = bitmap.bounds();
+        self.draw_bitmap_region(bitmap, size, Rect::new(position.x, position.y, size.width, size.height));
+    }
+
+    /// Draws a bitmap enlarged or shrunk by `scale`, e.g. 2.0 for double size
+    pub fn draw_bitmap_scaled(&mut self, bitmap: &Bitmap, position: Point, scale: f32) {
+        let width = (bitmap.width() as f32 * scale).round() as u16;
+        let height = (bitmap.height() as f32 * scale).round() as u16;
+        self.draw_bitmap_region(bitmap, bitmap.bounds(), Rect::new(position.x, position.y, width, height));
+    }
+
+    /// Draws the `source` area of a bitmap stretched to fill `dest`
+    ///
+    /// Scaling uses nearest-neighbour sampling, which keeps pixel-art icons crisp.
+    /// Use `Bitmap::tile` to pick a `source` cell from a sprite sheet.
+    pub fn draw_bitmap_region(&mut self, bitmap: &Bitmap, source: Rect, dest: Rect) {
+        let source = match bitmap.bounds().intersect(&source) {
+            Some(source) => source,
+            None => return,
+        };
+        let clipped = match self.clip_rect().intersect(&dest) {
+            Some(clipped) => clipped,
+            None => return,
+        };
+
+        for y in clipped.y..clipped.bottom() as u16 {
+            let src_y = source.y as u32 + (y - dest.y) as u32 * source.height as u32 / dest.height as u32;
+
+            for x in clipped.x..clipped.right() as u16 {
+                let src_x = source.x as u32 + (x - dest.x) as u32 * source.width as u32 / dest.width as u32;
+                let color = bitmap.pixel(src_x as u16, src_y as u16);
+                let _ = self.canvas.set_pixel(x, y, color);
+            }
+        }
+
+        self.dirty.add(clipped);
+    }
+}
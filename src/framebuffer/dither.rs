@@ -0,0 +1,224 @@
+// Color reduction for panels with fewer than 8 bits per channel
+
+use super::{Color, PixelFormat, BYTES_PER_PIXEL};
+
+/// How canvas colors are reduced to the device color depth when flushing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dithering {
+    /// Drop the low bits of every channel
+    #[default]
+    None,
+    /// 4x4 Bayer matrix; a region looks the same whenever it is redrawn
+    Ordered,
+    /// Error diffusion; smoother gradients, but the pattern depends on the
+    /// region being flushed, so partial redraws can leave faint seams
+    FloydSteinberg,
+}
+
+impl Dithering {
+    /// Parses `none`, `ordered`/`bayer` or `floyd-steinberg`/`fs`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" => Some(Dithering::None),
+            "ordered" | "bayer" => Some(Dithering::Ordered),
+            "floyd-steinberg" | "floydsteinberg" | "fs" => Some(Dithering::FloydSteinberg),
+            _ => None,
+        }
+    }
+}
+
+/// Threshold offsets of the 4x4 Bayer matrix, in sixteenths
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+/// Turns canvas rows into colors that the device format stores exactly
+///
+/// In monochrome mode the luminance is reduced to black and white, which
+/// suits SSD1306-class OLEDs that fbtft exposes as RGB565.
+pub(super) struct Quantizer {
+    dithering: Dithering,
+    monochrome: Option<u8>,
+    /// Bit length of the red, green and blue fields
+    lengths: [u32; 3],
+    /// Floyd-Steinberg error for the current and the next row, one pixel of padding on each side
+    errors: Vec<[f32; 3]>,
+    next_errors: Vec<[f32; 3]>,
+}
+
+impl Quantizer {
+    pub(super) fn new(format: PixelFormat, dithering: Dithering, monochrome: Option<u8>) -> Self {
+        Self {
+            dithering,
+            monochrome,
+            lengths: [format.red.length, format.green.length, format.blue.length],
+            errors: Vec::new(),
+            next_errors: Vec::new(),
+        }
+    }
+
+    /// True if canvas colors can be written unchanged
+    pub(super) fn is_passthrough(&self) -> bool {
+        self.monochrome.is_none() && self.dithering == Dithering::None
+    }
+
+    /// Resets the diffused error before quantizing the rows of a new rectangle
+    pub(super) fn start_rect(&mut self, width: u16) {
+        if self.dithering == Dithering::FloydSteinberg {
+            self.errors = vec![[0.0; 3]; width as usize + 2];
+            self.next_errors = vec![[0.0; 3]; width as usize + 2];
+        }
+    }
+
+    /// Quantizes one row of canvas pixels starting at (`x0`, `y`) into `out`
+    pub(super) fn quantize_row(&mut self, x0: u16, y: u16, src: &[u8], out: &mut Vec<Color>) {
+        out.clear();
+
+        let (channels, max_levels) = match self.monochrome {
+            Some(_) => (1, [1.0; 3]),
+            None => (3, self.lengths.map(|length| ((1u32 << length) - 1) as f32)),
+        };
+
+        for (i, pixel) in src.chunks_exact(BYTES_PER_PIXEL).enumerate() {
+            let x = x0 as usize + i;
+            let values = match self.monochrome {
+                Some(_) => [luminance(pixel), 0.0, 0.0],
+                None => [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32],
+            };
+
+            let mut levels = [0u32; 3];
+            for c in 0..channels {
+                let max = max_levels[c];
+                let value = values[c] * max / 255.0;
+
+                let level = match (self.dithering, self.monochrome) {
+                    (Dithering::None, Some(threshold)) => f32::from(values[0] >= threshold as f32),
+                    (Dithering::None, None) => value.floor(),
+                    (Dithering::Ordered, _) => {
+                        let offset = (BAYER_4X4[y as usize % 4][x % 4] as f32 + 0.5) / 16.0;
+                        (value + offset).floor()
+                    }
+                    (Dithering::FloydSteinberg, _) => {
+                        let wanted = value + self.errors[i + 1][c];
+                        let level = wanted.round().clamp(0.0, max);
+                        let error = wanted - level;
+
+                        self.errors[i + 2][c] += error * 7.0 / 16.0;
+                        self.next_errors[i][c] += error * 3.0 / 16.0;
+                        self.next_errors[i + 1][c] += error * 5.0 / 16.0;
+                        self.next_errors[i + 2][c] += error / 16.0;
+                        level
+                    }
+                };
+                levels[c] = level.clamp(0.0, max) as u32;
+            }
+
+            out.push(self.color_of(levels, pixel[3]));
+        }
+
+        if self.dithering == Dithering::FloydSteinberg {
+            std::mem::swap(&mut self.errors, &mut self.next_errors);
+            self.next_errors.fill([0.0; 3]);
+        }
+    }
+
+    /// Expands field levels back to 8-bit channels that `PixelFormat::pack` maps onto the same levels
+    fn color_of(&self, levels: [u32; 3], alpha: u8) -> Color {
+        if self.monochrome.is_some() {
+            let value = if levels[0] > 0 { 255 } else { 0 };
+            return Color::new(value, value, value, alpha);
+        }
+
+        let expand = |level: u32, length: u32| (level << (8 - length)) as u8;
+        Color::new(
+            expand(levels[0], self.lengths[0]),
+            expand(levels[1], self.lengths[1]),
+            expand(levels[2], self.lengths[2]),
+            alpha,
+        )
+    }
+}
+
+/// Perceived brightness of an RGBA pixel (ITU-R BT.601 weights), 0.0 to 255.0
+fn luminance(pixel: &[u8]) -> f32 {
+    0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray_row(value: u8, width: usize) -> Vec<u8> {
+        [value, value, value, 255].repeat(width)
+    }
+
+    fn quantize(quantizer: &mut Quantizer, rows: &[Vec<u8>]) -> Vec<Vec<Color>> {
+        quantizer.start_rect((rows[0].len() / BYTES_PER_PIXEL) as u16);
+        rows.iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let mut out = Vec::new();
+                quantizer.quantize_row(0, y as u16, row, &mut out);
+                out
+            })
+            .collect()
+    }
+
+    fn white_count(rows: &[Vec<Color>]) -> usize {
+        rows.iter().flatten().filter(|color| color.r == 255).count()
+    }
+
+    #[test]
+    fn parses_dithering_names() {
+        assert_eq!(Dithering::from_name(" Bayer "), Some(Dithering::Ordered));
+        assert_eq!(Dithering::from_name("fs"), Some(Dithering::FloydSteinberg));
+        assert_eq!(Dithering::from_name("off"), Some(Dithering::None));
+        assert_eq!(Dithering::from_name("random"), None);
+    }
+
+    #[test]
+    fn passthrough_only_without_dithering_and_monochrome() {
+        assert!(Quantizer::new(PixelFormat::RGB565, Dithering::None, None).is_passthrough());
+        assert!(!Quantizer::new(PixelFormat::RGB565, Dithering::Ordered, None).is_passthrough());
+        assert!(!Quantizer::new(PixelFormat::RGB565, Dithering::None, Some(128)).is_passthrough());
+    }
+
+    #[test]
+    fn colors_land_on_device_levels() {
+        for dithering in [Dithering::None, Dithering::Ordered, Dithering::FloydSteinberg] {
+            let mut quantizer = Quantizer::new(PixelFormat::RGB565, dithering, None);
+            let rows: Vec<Vec<u8>> = (0..4).map(|y| (0..64).flat_map(|x| [x * 4, y * 60, 255 - x * 4, 255]).collect()).collect();
+            for color in quantize(&mut quantizer, &rows).iter().flatten() {
+                assert_eq!((color.r & 0x07, color.g & 0x03, color.b & 0x07), (0, 0, 0), "{:?} with {:?}", color, dithering);
+                assert_eq!(color.a, 255);
+            }
+        }
+
+        let mut quantizer = Quantizer::new(PixelFormat::RGB565, Dithering::None, None);
+        assert_eq!(quantize(&mut quantizer, &[gray_row(0x87, 1)])[0][0], Color::new(0x80, 0x84, 0x80, 255));
+    }
+
+    #[test]
+    fn monochrome_thresholds_luminance() {
+        let mut quantizer = Quantizer::new(PixelFormat::RGB565, Dithering::None, Some(128));
+        let row = [[127, 127, 127, 255], [128, 128, 128, 255], [255, 0, 0, 255], [0, 255, 0, 255]].concat();
+        let colors = &quantize(&mut quantizer, &[row])[0];
+        assert_eq!(colors.iter().map(|color| color.r).collect::<Vec<_>>(), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn dithered_gray_mixes_black_and_white() {
+        let rows = vec![gray_row(128, 16); 16];
+        for dithering in [Dithering::Ordered, Dithering::FloydSteinberg] {
+            let mut quantizer = Quantizer::new(PixelFormat::RGB565, dithering, Some(128));
+            let white = white_count(&quantize(&mut quantizer, &rows));
+            assert!((96..=160).contains(&white), "{} of 256 white with {:?}", white, dithering);
+        }
+
+        let mut quantizer = Quantizer::new(PixelFormat::RGB565, Dithering::Ordered, Some(128));
+        assert_eq!(quantize(&mut quantizer, &rows), quantize(&mut quantizer, &rows));
+    }
+}
@@ -0,0 +1,192 @@
+// Font registry: built-in faces plus extra faces loaded from a font directory
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use fontdue::{Font, FontSettings};
+use super::{FontId, FramebufferError};
+
+/// Regular face compiled into the binary so the GUI starts from any working directory
+pub const EMBEDDED_REGULAR_FONT: &[u8] = include_bytes!("../../fonts/DidactGothic-Regular.ttf");
+
+/// Bold face compiled into the binary, for titles and badges
+pub const EMBEDDED_BOLD_FONT: &[u8] = include_bytes!("../../fonts/DejaVuSans-Bold.ttf");
+
+/// Monospace face compiled into the binary, for readouts and editors
+pub const EMBEDDED_MONO_FONT: &[u8] = include_bytes!("../../fonts/DejaVuSansMono.ttf");
+
+/// Directory searched for font files unless configured otherwise
+pub const DEFAULT_FONT_DIR: &str = "fonts";
+
+/// Loaded font faces, addressable by `FontId` or by name
+pub struct FontRegistry {
+    faces: HashMap<FontId, Font>,
+    names: HashMap<String, FontId>,
+    fallback: Option<FontId>,
+    directory: PathBuf,
+    next_custom: u16,
+}
+
+impl Default for FontRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FontRegistry {
+    pub fn new() -> Self {
+        Self {
+            faces: HashMap::new(),
+            names: HashMap::new(),
+            fallback: None,
+            directory: PathBuf::from(DEFAULT_FONT_DIR),
+            next_custom: 0,
+        }
+    }
+
+    /// Sets the directory used for the fallback face and for extra faces
+    pub fn set_directory<P: Into<PathBuf>>(&mut self, directory: P) {
+        self.directory = directory.into();
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    #[inline]
+    pub fn get(&self, font_id: FontId) -> Option<&Font> {
+        self.faces.get(&font_id)
+    }
+
+    #[inline]
+    pub fn contains(&self, font_id: FontId) -> bool {
+        self.faces.contains_key(&font_id)
+    }
+
+    /// Sets the face consulted for characters the requested face cannot render
+    pub fn set_fallback(&mut self, font_id: Option<FontId>) {
+        self.fallback = font_id;
+    }
+
+    /// Returns the face that renders `ch`: `font_id` itself, or the fallback
+    /// face if only that one has the glyph
+    pub fn resolve(&self, font_id: FontId, ch: char) -> Option<(FontId, &Font)> {
+        let primary = self.faces.get(&font_id)?;
+        if ch.is_whitespace() || primary.has_glyph(ch) {
+            return Some((font_id, primary));
+        }
+
+        self.fallback
+            .filter(|&fallback_id| fallback_id != font_id)
+            .and_then(|fallback_id| self.faces.get(&fallback_id).map(|font| (fallback_id, font)))
+            .filter(|(_, font)| font.has_glyph(ch))
+            .or(Some((font_id, primary)))
+    }
+
+    /// Looks up a face by name: "regular", "bold", "mono" or the file stem of an extra face
+    pub fn id_by_name(&self, name: &str) -> Option<FontId> {
+        self.names.get(&name.to_lowercase()).copied()
+    }
+
+    /// Parses font data and registers it under `font_id`
+    pub fn load_bytes(&mut self, font_id: FontId, data: &[u8], source: &str) -> Result<(), FramebufferError> {
+        let font_settings = FontSettings {
+            scale: 40.0,
+            ..FontSettings::default()
+        };
+
+        let font = Font::from_bytes(data, font_settings)
+            .map_err(|e| FramebufferError::FontLoadError(format!("Failed to parse font '{}': {}", source, e)))?;
+        self.faces.insert(font_id, font);
+        if let Some(name) = font_id.name() {
+            self.names.insert(name.to_string(), font_id);
+        }
+        Ok(())
+    }
+
+    /// Loads a font file and registers it under `font_id`
+    pub fn load_file(&mut self, font_id: FontId, path: &Path) -> Result<(), FramebufferError> {
+        let data = std::fs::read(path).map_err(|e| {
+            FramebufferError::FontLoadError(format!("Failed to read font file '{}': {}", path.display(), e))
+        })?;
+        self.load_bytes(font_id, &data, &path.display().to_string())
+    }
+
+    /// Loads the default face for a built-in font ID
+    ///
+    /// Regular, bold and mono come from the embedded fonts; the fallback face, too large to
+    /// embed, is read from the font directory.
+    pub fn load_default(&mut self, font_id: FontId) -> Result<(), FramebufferError> {
+        match font_id {
+            FontId::Regular => self.load_bytes(FontId::Regular, EMBEDDED_REGULAR_FONT, "embedded"),
+            FontId::Bold => self.load_bytes(FontId::Bold, EMBEDDED_BOLD_FONT, "embedded bold"),
+            FontId::Mono => self.load_bytes(FontId::Mono, EMBEDDED_MONO_FONT, "embedded mono"),
+            FontId::Custom(_) => Err(FramebufferError::FontLoadError(
+                format!("No default face for {:?}", font_id)
+            )),
+            FontId::Fallback => {
+                let path = self.directory.join(font_id.file_name().unwrap_or_default());
+                self.load_file(font_id, &path)
+            }
+        }
+    }
+
+    /// Loads all built-in faces; a missing fallback face disables glyph fallback
+    pub fn load_builtin(&mut self) -> Result<(), FramebufferError> {
+        for font_id in [FontId::Regular, FontId::Bold, FontId::Mono] {
+            self.load_default(font_id)?;
+        }
+
+        match self.load_default(FontId::Fallback) {
+            Ok(()) => self.fallback = Some(FontId::Fallback),
+            Err(e) => println!("[Fonts] {}, glyph fallback disabled", e),
+        }
+
+        Ok(())
+    }
+
+    /// Registers every `.ttf`/`.otf` file in the font directory that is not a built-in face
+    ///
+    /// Extra faces get `FontId::Custom` IDs and can be found by file stem with `id_by_name`.
+    pub fn load_directory(&mut self) -> Result<Vec<FontId>, FramebufferError> {
+        let builtin: Vec<&str> = [FontId::Regular, FontId::Bold, FontId::Mono, FontId::Fallback]
+            .iter()
+            .filter_map(|id| id.file_name())
+            .collect();
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.directory)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                let is_font = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf"));
+                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                is_font && !builtin.contains(&file_name)
+            })
+            .collect();
+        paths.sort();
+
+        let mut loaded = Vec::new();
+        for path in paths {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_lowercase();
+            let font_id = match self.names.get(&stem) {
+                Some(&existing) => existing,
+                None => {
+                    let id = FontId::Custom(self.next_custom);
+                    self.next_custom += 1;
+                    id
+                }
+            };
+
+            match self.load_file(font_id, &path) {
+                Ok(()) => {
+                    self.names.insert(stem, font_id);
+                    loaded.push(font_id);
+                }
+                Err(e) => println!("[Fonts] Skipping {}", e),
+            }
+        }
+
+        Ok(loaded)
+    }
+}
@@ -0,0 +1,167 @@
+// Rasterized glyph cache with a least-recently-used memory bound
+
+use std::collections::HashMap;
+use fontdue::{Font, Metrics};
+use super::FontId;
+
+/// Default memory budget for cached glyph bitmaps
+pub const DEFAULT_GLYPH_CACHE_BYTES: usize = 256 * 1024;
+
+/// Bookkeeping cost per entry on top of the bitmap itself
+const ENTRY_OVERHEAD: usize = 64;
+
+/// Font, character and size in hundredths of a pixel
+type GlyphKey = (FontId, char, u32);
+
+/// A glyph rasterized once, with the metrics needed to place it
+pub struct CachedGlyph {
+    pub metrics: Metrics,
+    pub bitmap: Vec<u8>,
+}
+
+struct Entry {
+    glyph: CachedGlyph,
+    last_used: u64,
+}
+
+/// Glyph bitmaps keyed by font, character and size
+///
+/// When the memory budget is exceeded the least recently used glyphs are dropped.
+pub struct GlyphCache {
+    entries: HashMap<GlyphKey, Entry>,
+    capacity_bytes: usize,
+    used_bytes: usize,
+    clock: u64,
+}
+
+impl GlyphCache {
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity_bytes,
+            used_bytes: 0,
+            clock: 0,
+        }
+    }
+
+    #[inline]
+    fn key(font_id: FontId, ch: char, size: f32) -> GlyphKey {
+        (font_id, ch, (size * 100.0) as u32)
+    }
+
+    #[inline]
+    fn entry_cost(glyph: &CachedGlyph) -> usize {
+        glyph.bitmap.len() + ENTRY_OVERHEAD
+    }
+
+    /// Returns the cached glyph, rasterizing it on a miss
+    pub fn get_or_rasterize(&mut self, font: &Font, font_id: FontId, ch: char, size: f32) -> &CachedGlyph {
+        let key = Self::key(font_id, ch, size);
+        self.clock += 1;
+
+        if !self.entries.contains_key(&key) {
+            let (metrics, bitmap) = font.rasterize(ch, size);
+            let glyph = CachedGlyph { metrics, bitmap };
+            let cost = Self::entry_cost(&glyph);
+
+            self.evict_until_fits(cost);
+            self.used_bytes += cost;
+            self.entries.insert(key, Entry { glyph, last_used: self.clock });
+        }
+
+        let entry = self.entries.get_mut(&key).expect("glyph inserted above");
+        entry.last_used = self.clock;
+        &entry.glyph
+    }
+
+    /// Changes the memory budget, evicting glyphs if the cache is now too large
+    pub fn set_capacity(&mut self, capacity_bytes: usize) {
+        self.capacity_bytes = capacity_bytes;
+        self.evict_until_fits(0);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+
+    /// Approximate memory used by cached glyphs in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.used_bytes
+    }
+
+    fn evict_until_fits(&mut self, incoming: usize) {
+        while self.used_bytes + incoming > self.capacity_bytes {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+
+            match oldest.and_then(|key| self.entries.remove(&key)) {
+                Some(entry) => self.used_bytes -= Self::entry_cost(&entry.glyph),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::EMBEDDED_REGULAR_FONT;
+    use fontdue::FontSettings;
+
+    fn font() -> Font {
+        Font::from_bytes(EMBEDDED_REGULAR_FONT, FontSettings::default()).unwrap()
+    }
+
+    /// The same glyph under three IDs, so every entry costs the same
+    const IDS: [FontId; 3] = [FontId::Regular, FontId::Bold, FontId::Mono];
+
+    fn cost(font: &Font) -> usize {
+        font.rasterize('A', 12.0).1.len() + ENTRY_OVERHEAD
+    }
+
+    #[test]
+    fn hits_do_not_rasterize_again() {
+        let font = font();
+        let mut cache = GlyphCache::new(DEFAULT_GLYPH_CACHE_BYTES);
+        cache.get_or_rasterize(&font, FontId::Regular, 'A', 12.0);
+        cache.get_or_rasterize(&font, FontId::Regular, 'A', 12.0);
+
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.memory_usage(), cost(&font));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_glyph() {
+        let font = font();
+        let mut cache = GlyphCache::new(2 * cost(&font));
+        cache.get_or_rasterize(&font, IDS[0], 'A', 12.0);
+        cache.get_or_rasterize(&font, IDS[1], 'A', 12.0);
+        cache.get_or_rasterize(&font, IDS[0], 'A', 12.0);
+        cache.get_or_rasterize(&font, IDS[2], 'A', 12.0);
+
+        assert!(cache.entries.contains_key(&GlyphCache::key(IDS[0], 'A', 12.0)));
+        assert!(!cache.entries.contains_key(&GlyphCache::key(IDS[1], 'A', 12.0)));
+        assert!(cache.entries.contains_key(&GlyphCache::key(IDS[2], 'A', 12.0)));
+        assert_eq!(cache.memory_usage(), 2 * cost(&font));
+    }
+
+    #[test]
+    fn shrinking_evicts_until_within_budget() {
+        let font = font();
+        let mut cache = GlyphCache::new(DEFAULT_GLYPH_CACHE_BYTES);
+        for id in IDS {
+            cache.get_or_rasterize(&font, id, 'A', 12.0);
+        }
+
+        cache.set_capacity(cost(&font));
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.entries.contains_key(&GlyphCache::key(IDS[2], 'A', 12.0)));
+
+        cache.clear();
+        assert_eq!(cache.memory_usage(), 0);
+    }
+}
@@ -0,0 +1,249 @@
+// Drawing layers; overlays are blended over the base canvas when flushing
+
+use std::borrow::Cow;
+
+use super::{Color, Framebuffer, PixelBuffer, Rect, BYTES_PER_PIXEL};
+
+/// Handle of a drawing layer returned by `Framebuffer::add_layer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerId(usize);
+
+impl LayerId {
+    /// The opaque bottom layer every framebuffer starts drawing into
+    pub const BASE: LayerId = LayerId(0);
+}
+
+/// Pixels of one layer plus whether they take part in compositing
+///
+/// The buffer of the active layer is moved into `Framebuffer::canvas` while it
+/// is being drawn into, so its slot here holds an empty placeholder.
+#[derive(Debug)]
+pub(super) struct Layer {
+    buffer: PixelBuffer,
+    visible: bool,
+}
+
+impl Layer {
+    pub(super) fn base() -> Self {
+        Self {
+            buffer: PixelBuffer::default(),
+            visible: true,
+        }
+    }
+}
+
+impl Framebuffer {
+    /// Adds a transparent layer on top of all existing layers
+    ///
+    /// Overlays let popups be shown and removed without redrawing what lies below.
+    pub fn add_layer(&mut self) -> LayerId {
+        self.layers.push(Layer {
+            buffer: PixelBuffer::new(self.width(), self.height(), Color::transparent()),
+            visible: true,
+        });
+        self.mark_all_dirty();
+        LayerId(self.layers.len() - 1)
+    }
+
+    /// Directs all following drawing operations to `layer`
+    pub fn set_layer(&mut self, layer: LayerId) {
+        if layer.0 == self.active_layer || layer.0 >= self.layers.len() {
+            return;
+        }
+
+        let target = std::mem::take(&mut self.layers[layer.0].buffer);
+        self.layers[self.active_layer].buffer = std::mem::replace(&mut self.canvas, target);
+        self.active_layer = layer.0;
+    }
+
+    /// The layer drawing operations currently go to
+    pub fn layer(&self) -> LayerId {
+        LayerId(self.active_layer)
+    }
+
+    /// Erases a layer, to black for the base layer and to transparent for overlays
+    pub fn clear_layer(&mut self, layer: LayerId) {
+        if layer.0 >= self.layers.len() {
+            return;
+        }
+
+        let fill = if layer == LayerId::BASE { Color::black() } else { Color::transparent() };
+        let buffer = if layer.0 == self.active_layer {
+            &mut self.canvas
+        } else {
+            &mut self.layers[layer.0].buffer
+        };
+        buffer.clear(buffer.bounds(), fill);
+
+        if self.layers[layer.0].visible {
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Shows or hides an overlay without touching its contents
+    ///
+    /// The base layer is always visible.
+    pub fn set_layer_visible(&mut self, layer: LayerId, visible: bool) {
+        if layer == LayerId::BASE || layer.0 >= self.layers.len() {
+            return;
+        }
+
+        if self.layers[layer.0].visible != visible {
+            self.layers[layer.0].visible = visible;
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Reallocates every layer at the current canvas size, dropping their contents
+    pub(super) fn reset_layers(&mut self) {
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        for (index, layer) in self.layers.iter_mut().enumerate() {
+            layer.buffer = match index {
+                _ if index == self.active_layer => PixelBuffer::default(),
+                0 => PixelBuffer::new(width, height, Color::black()),
+                _ => PixelBuffer::new(width, height, Color::transparent()),
+            };
+        }
+        if self.active_layer != 0 {
+            self.canvas = PixelBuffer::new(width, height, Color::transparent());
+        }
+    }
+
+    /// The full screen as it appears on the device, with all visible layers blended
+    pub(super) fn composited(&self) -> Cow<'_, PixelBuffer> {
+        let mut scratch = PixelBuffer::default();
+        let bounds = [self.canvas.bounds()];
+        match composite(&self.canvas, &self.layers, self.active_layer, &bounds, &mut scratch) {
+            Some(_) => Cow::Owned(scratch),
+            None => Cow::Borrowed(buffer_at(&self.canvas, &self.layers, self.active_layer, 0)),
+        }
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty.add(self.canvas.bounds());
+    }
+}
+
+/// Returns the buffer of layer `index`, which is the canvas if that layer is active
+pub(super) fn buffer_at<'a>(canvas: &'a PixelBuffer, layers: &'a [Layer], active: usize, index: usize) -> &'a PixelBuffer {
+    if index == active {
+        canvas
+    } else {
+        &layers[index].buffer
+    }
+}
+
+/// Blends all visible layers inside `rects` into `scratch`
+///
+/// Returns `None` without touching `scratch` when no overlay is visible, in which
+/// case the base layer can be copied to the device as it is.
+pub(super) fn composite<'a>(
+    canvas: &PixelBuffer,
+    layers: &[Layer],
+    active: usize,
+    rects: &[Rect],
+    scratch: &'a mut PixelBuffer,
+) -> Option<&'a PixelBuffer> {
+    let overlays: Vec<&PixelBuffer> = (1..layers.len())
+        .filter(|&index| layers[index].visible)
+        .map(|index| buffer_at(canvas, layers, active, index))
+        .collect();
+    if overlays.is_empty() {
+        return None;
+    }
+
+    let base = buffer_at(canvas, layers, active, 0);
+    if scratch.width != base.width || scratch.height != base.height {
+        *scratch = PixelBuffer::new(base.width, base.height, Color::black());
+    }
+
+    for rect in rects {
+        for y in rect.y..rect.bottom() as u16 {
+            scratch.row_span_mut(rect, y).copy_from_slice(base.row_span(rect, y));
+
+            for overlay in &overlays {
+                let row = overlay.row_span(rect, y);
+                for (x, pixel) in (rect.x..).zip(row.chunks_exact(BYTES_PER_PIXEL)) {
+                    if pixel[3] > 0 {
+                        let _ = scratch.set_pixel(x, y, Color::from_bytes(pixel));
+                    }
+                }
+            }
+        }
+    }
+
+    Some(scratch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::new(255, 0, 0, 255);
+    const BLUE: Color = Color::new(0, 0, 255, 255);
+
+    fn screen_pixel(fb: &Framebuffer, x: u16, y: u16) -> Color {
+        let frame = fb.composited();
+        let offset = (y as usize) * frame.stride + (x as usize) * BYTES_PER_PIXEL;
+        Color::from_bytes(&frame.data[offset..offset + BYTES_PER_PIXEL])
+    }
+
+    #[test]
+    fn overlays_cover_the_base_where_drawn() {
+        let mut fb = Framebuffer::headless(40, 30).unwrap();
+        fb.fill_rect(Rect::new(0, 0, 40, 30), RED);
+        let popup = fb.add_layer();
+        fb.set_layer(popup);
+        fb.fill_rect(Rect::new(10, 10, 5, 5), BLUE);
+
+        assert_eq!(screen_pixel(&fb, 12, 12), BLUE);
+        assert_eq!(screen_pixel(&fb, 5, 5), RED);
+
+        fb.set_layer_visible(popup, false);
+        assert_eq!(screen_pixel(&fb, 12, 12), RED);
+        fb.set_layer_visible(popup, true);
+        assert_eq!(screen_pixel(&fb, 12, 12), BLUE);
+
+        fb.clear_layer(popup);
+        assert_eq!(screen_pixel(&fb, 12, 12), RED);
+    }
+
+    #[test]
+    fn layers_keep_their_contents_while_inactive() {
+        let mut fb = Framebuffer::headless(40, 30).unwrap();
+        let popup = fb.add_layer();
+        fb.set_layer(popup);
+        fb.fill_rect(Rect::new(0, 0, 5, 5), BLUE);
+        fb.set_layer(LayerId::BASE);
+        assert_eq!(fb.layer(), LayerId::BASE);
+
+        // Drawn below the overlay, so only visible outside it
+        fb.fill_rect(Rect::new(0, 0, 10, 10), RED);
+        assert_eq!(screen_pixel(&fb, 2, 2), BLUE);
+        assert_eq!(screen_pixel(&fb, 7, 7), RED);
+    }
+
+    #[test]
+    fn translucent_overlays_blend_with_the_base() {
+        let mut fb = Framebuffer::headless(40, 30).unwrap();
+        let shade = fb.add_layer();
+        fb.set_layer(shade);
+        fb.fill_rect(Rect::new(0, 0, 40, 30), Color::new(255, 255, 255, 128));
+
+        let pixel = screen_pixel(&fb, 20, 15);
+        assert!((127..=129).contains(&pixel.r) && pixel.r == pixel.b, "{:?}", pixel);
+    }
+
+    #[test]
+    fn layer_changes_redraw_the_screen() {
+        let mut fb = Framebuffer::headless(40, 30).unwrap();
+        fb.dirty.take();
+        let popup = fb.add_layer();
+        assert_eq!(fb.dirty.take(), [Rect::new(0, 0, 40, 30)]);
+
+        fb.set_layer_visible(popup, true);
+        assert!(fb.dirty.take().is_empty());
+        fb.set_layer_visible(popup, false);
+        assert_eq!(fb.dirty.take(), [Rect::new(0, 0, 40, 30)]);
+    }
+}
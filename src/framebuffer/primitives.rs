@@ -0,0 +1,354 @@
+// Geometric drawing primitives; everything is clipped against the clip rectangle
+
+use super::{Color, Framebuffer, Point, Rect};
+
+impl Framebuffer {
+    /// Fills a rectangle with the given color
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if let Some(clipped) = self.clip_rect().intersect(&rect) {
+            self.canvas.fill_rect(clipped, color);
+            self.dirty.add(clipped);
+        }
+    }
+
+    /// Draws a one pixel wide rectangle outline
+    pub fn draw_rect(&mut self, rect: Rect, color: Color) {
+        if rect.is_empty() {
+            return;
+        }
+
+        let bottom = rect.y + rect.height - 1;
+        self.draw_hline(Point::new(rect.x, rect.y), rect.width, color);
+        if rect.height > 1 {
+            self.draw_hline(Point::new(rect.x, bottom), rect.width, color);
+        }
+        if rect.height > 2 {
+            let inner = rect.height - 2;
+            self.draw_vline(Point::new(rect.x, rect.y + 1), inner, color);
+            if rect.width > 1 {
+                self.draw_vline(Point::new(rect.x + rect.width - 1, rect.y + 1), inner, color);
+            }
+        }
+    }
+
+    /// Draws a horizontal line of `length` pixels starting at `start`
+    pub fn draw_hline(&mut self, start: Point, length: u16, color: Color) {
+        self.fill_rect(Rect::new(start.x, start.y, length, 1), color);
+    }
+
+    /// Draws a vertical line of `length` pixels starting at `start`
+    pub fn draw_vline(&mut self, start: Point, length: u16, color: Color) {
+        self.fill_rect(Rect::new(start.x, start.y, 1, length), color);
+    }
+
+    /// Draws a straight line between two points using Bresenham's algorithm
+    pub fn draw_line(&mut self, from: Point, to: Point, color: Color) {
+        self.line(from, to, color, false);
+        self.mark_line_dirty(from, to);
+    }
+
+    /// Draws connected line segments through all points
+    pub fn draw_polyline(&mut self, points: &[Point], color: Color) {
+        for (i, segment) in points.windows(2).enumerate() {
+            // Shared vertices are plotted once so translucent colors blend evenly
+            self.line(segment[0], segment[1], color, i > 0);
+            self.mark_line_dirty(segment[0], segment[1]);
+        }
+    }
+
+    /// Draws a rectangle outline with rounded corners
+    pub fn draw_rounded_rect(&mut self, rect: Rect, radius: u16, color: Color) {
+        let radius = radius.min(rect.width / 2).min(rect.height / 2);
+        if radius == 0 {
+            self.draw_rect(rect, color);
+            return;
+        }
+
+        let (x0, y0) = (rect.x as i32, rect.y as i32);
+        let (x1, y1) = (rect.right() as i32 - 1, rect.bottom() as i32 - 1);
+        let r = radius as i32;
+        let straight_w = rect.width - 2 * radius;
+        let straight_h = rect.height - 2 * radius;
+
+        self.draw_hline(Point::new(rect.x + radius, rect.y), straight_w, color);
+        self.draw_hline(Point::new(rect.x + radius, y1 as u16), straight_w, color);
+        self.draw_vline(Point::new(rect.x, rect.y + radius), straight_h, color);
+        self.draw_vline(Point::new(x1 as u16, rect.y + radius), straight_h, color);
+
+        // Corner centers sit `radius` pixels inside each edge; the first octant
+        // point lies on the straight segments already drawn above
+        let (left, right) = (x0 + r, x1 - r);
+        let (top, bottom) = (y0 + r, y1 - r);
+        for (dx, dy) in octant_points(r).into_iter().skip(1) {
+            for (px, py) in unique_pairs(dx, dy) {
+                self.plot(left - px, top - py, color);
+                self.plot(right + px, top - py, color);
+                self.plot(left - px, bottom + py, color);
+                self.plot(right + px, bottom + py, color);
+            }
+        }
+        self.mark_dirty(x0, y0, x1 + 1, y1 + 1);
+    }
+
+    /// Fills a rectangle with rounded corners
+    pub fn fill_rounded_rect(&mut self, rect: Rect, radius: u16, color: Color) {
+        let radius = radius.min(rect.width / 2).min(rect.height / 2);
+        if radius == 0 {
+            self.fill_rect(rect, color);
+            return;
+        }
+
+        let r = radius as f32;
+        for row in 0..rect.height {
+            let corner_row = if row < radius {
+                Some(radius - 1 - row)
+            } else if row >= rect.height - radius {
+                Some(row - (rect.height - radius))
+            } else {
+                None
+            };
+
+            let inset = corner_row.map_or(0, |dy| {
+                let offset = dy as f32 + 0.5;
+                (r - (r * r - offset * offset).max(0.0).sqrt()).round() as u16
+            });
+
+            self.draw_hline(
+                Point::new(rect.x + inset, rect.y + row),
+                rect.width - 2 * inset,
+                color,
+            );
+        }
+    }
+
+    /// Draws a circle outline
+    pub fn draw_circle(&mut self, center: Point, radius: u16, color: Color) {
+        let (cx, cy, r) = (center.x as i32, center.y as i32, radius as i32);
+
+        for (dx, dy) in octant_points(r) {
+            for (px, py) in symmetric_points(dx, dy) {
+                self.plot(cx + px, cy + py, color);
+            }
+        }
+        self.mark_dirty(cx - r, cy - r, cx + r + 1, cy + r + 1);
+    }
+
+    /// Draws a filled circle
+    pub fn fill_circle(&mut self, center: Point, radius: u16, color: Color) {
+        let (cx, cy, r) = (center.x as i32, center.y as i32, radius as i32);
+        let r_sq = (radius as f32 + 0.5).powi(2);
+
+        for dy in -r..=r {
+            let half = (r_sq - (dy * dy) as f32).max(0.0).sqrt() as i32;
+            for x in cx - half..=cx + half {
+                self.plot(x, cy + dy, color);
+            }
+        }
+        self.mark_dirty(cx - r, cy - r, cx + r + 1, cy + r + 1);
+    }
+
+    /// Draws part of a circle outline
+    ///
+    /// Angles are in degrees, measured clockwise from the positive x axis (3 o'clock).
+    /// The arc runs clockwise from `start_deg` to `end_deg`.
+    pub fn draw_arc(&mut self, center: Point, radius: u16, start_deg: f32, end_deg: f32, color: Color) {
+        let (cx, cy, r) = (center.x as i32, center.y as i32, radius as i32);
+        let start = start_deg.rem_euclid(360.0);
+        let sweep = end_deg - start_deg;
+        if sweep <= 0.0 {
+            return;
+        }
+
+        for (dx, dy) in octant_points(r) {
+            for (px, py) in symmetric_points(dx, dy) {
+                let angle = (py as f32).atan2(px as f32).to_degrees().rem_euclid(360.0);
+                if sweep >= 360.0 || (angle - start).rem_euclid(360.0) <= sweep {
+                    self.plot(cx + px, cy + py, color);
+                }
+            }
+        }
+        self.mark_dirty(cx - r, cy - r, cx + r + 1, cy + r + 1);
+    }
+
+    fn line(&mut self, from: Point, to: Point, color: Color, skip_first: bool) {
+        let (mut x, mut y) = (from.x as i32, from.y as i32);
+        let (x1, y1) = (to.x as i32, to.y as i32);
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let sx = if x < x1 { 1 } else { -1 };
+        let sy = if y < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let mut first = true;
+
+        loop {
+            if !(first && skip_first) {
+                self.plot(x, y, color);
+            }
+            first = false;
+
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn mark_line_dirty(&mut self, from: Point, to: Point) {
+        let (x0, x1) = (from.x.min(to.x) as i32, from.x.max(to.x) as i32);
+        let (y0, y1) = (from.y.min(to.y) as i32, from.y.max(to.y) as i32);
+        self.mark_dirty(x0, y0, x1 + 1, y1 + 1);
+    }
+}
+
+/// Midpoint circle points for one octant, as (x, y) offsets with 0 <= x <= y
+fn octant_points(radius: i32) -> Vec<(i32, i32)> {
+    let mut points = Vec::with_capacity(radius as usize + 1);
+    let (mut x, mut y) = (0, radius);
+    let mut d = 1 - radius;
+
+    while x <= y {
+        points.push((x, y));
+        x += 1;
+        if d < 0 {
+            d += 2 * x + 1;
+        } else {
+            y -= 1;
+            d += 2 * (x - y) + 1;
+        }
+    }
+
+    points
+}
+
+/// The octant point and its mirror across the diagonal, without duplicates
+fn unique_pairs(x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> {
+    let mirrored = (x != y).then_some((y, x));
+    std::iter::once((x, y)).chain(mirrored)
+}
+
+/// All eight reflections of an octant point, without duplicates
+fn symmetric_points(x: i32, y: i32) -> Vec<(i32, i32)> {
+    let mut points = Vec::with_capacity(8);
+    for (px, py) in unique_pairs(x, y) {
+        for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+            let point = (px * sx, py * sy);
+            if !points.contains(&point) {
+                points.push(point);
+            }
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INK: Color = Color::new(255, 255, 255, 255);
+
+    fn canvas() -> Framebuffer {
+        let mut fb = Framebuffer::headless(40, 30).unwrap();
+        fb.clear_screen(Color::black()).unwrap();
+        fb.dirty.take();
+        fb
+    }
+
+    fn inked(fb: &Framebuffer, x: u16, y: u16) -> bool {
+        let offset = (y as usize) * fb.canvas.stride + (x as usize) * 4;
+        Color::from_bytes(&fb.canvas.data[offset..offset + 4]) == INK
+    }
+
+    fn inked_pixels(fb: &Framebuffer) -> Vec<(u16, u16)> {
+        (0..fb.canvas.height)
+            .flat_map(|y| (0..fb.canvas.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| inked(fb, x, y))
+            .collect()
+    }
+
+    #[test]
+    fn lines_run_between_both_ends() {
+        let mut fb = canvas();
+        fb.draw_line(Point::new(7, 4), Point::new(2, 2), INK);
+        assert_eq!(inked_pixels(&fb), [(2, 2), (3, 2), (4, 3), (5, 3), (6, 4), (7, 4)]);
+        assert_eq!(fb.dirty.take(), [Rect::new(2, 2, 6, 3)]);
+    }
+
+    #[test]
+    fn rect_outline_leaves_the_inside() {
+        let mut fb = canvas();
+        fb.draw_rect(Rect::new(5, 5, 4, 3), INK);
+        assert_eq!(inked_pixels(&fb).len(), 10);
+        assert!(inked(&fb, 8, 7) && !inked(&fb, 6, 6));
+    }
+
+    #[test]
+    fn circles_are_symmetric() {
+        let mut fb = canvas();
+        fb.draw_circle(Point::new(20, 15), 5, INK);
+        let pixels = inked_pixels(&fb);
+        for (x, y) in [(25, 15), (15, 15), (20, 10), (20, 20)] {
+            assert!(pixels.contains(&(x, y)), "({}, {}) missing", x, y);
+        }
+        for &(x, y) in &pixels {
+            assert!(pixels.contains(&(40 - x, y)) && pixels.contains(&(x, 30 - y)));
+        }
+        assert!(!inked(&fb, 20, 15));
+
+        fb.fill_circle(Point::new(20, 15), 5, INK);
+        assert!(inked(&fb, 20, 15) && inked(&fb, 24, 17));
+        assert!(!inked(&fb, 24, 19));
+    }
+
+    #[test]
+    fn arcs_run_clockwise_from_three_o_clock() {
+        let mut fb = canvas();
+        fb.draw_arc(Point::new(20, 15), 6, 0.0, 90.0, INK);
+        let pixels = inked_pixels(&fb);
+        assert!(pixels.contains(&(26, 15)) && pixels.contains(&(20, 21)));
+        assert!(pixels.iter().all(|&(x, y)| x >= 20 && y >= 15));
+
+        let mut fb = canvas();
+        fb.draw_arc(Point::new(20, 15), 6, 90.0, 90.0, INK);
+        assert!(inked_pixels(&fb).is_empty());
+    }
+
+    #[test]
+    fn rounded_rects_cut_the_corners() {
+        let mut fb = canvas();
+        fb.fill_rounded_rect(Rect::new(0, 0, 20, 10), 4, INK);
+        assert!(!inked(&fb, 0, 0) && !inked(&fb, 19, 9));
+        assert!(inked(&fb, 10, 0) && inked(&fb, 0, 5) && inked(&fb, 10, 5));
+
+        let mut fb = canvas();
+        fb.draw_rounded_rect(Rect::new(0, 0, 20, 10), 4, INK);
+        assert!(!inked(&fb, 0, 0) && !inked(&fb, 10, 5));
+        assert!(inked(&fb, 10, 0) && inked(&fb, 19, 5) && inked(&fb, 10, 9));
+        assert_eq!(fb.dirty.take(), [Rect::new(0, 0, 20, 10)]);
+    }
+
+    #[test]
+    fn primitives_stay_within_the_clip() {
+        let mut fb = canvas();
+        fb.push_clip(Rect::new(10, 10, 10, 10));
+        fb.fill_circle(Point::new(10, 10), 5, INK);
+        fb.draw_line(Point::new(0, 12), Point::new(39, 12), INK);
+
+        assert!(inked_pixels(&fb).iter().all(|&(x, y)| (10..20).contains(&x) && (10..20).contains(&y)));
+        assert!(inked(&fb, 10, 10) && inked(&fb, 19, 12));
+        assert!(!inked(&fb, 9, 12) && !inked(&fb, 20, 12));
+        assert_eq!(fb.dirty.take(), [Rect::new(10, 10, 10, 6)]);
+
+        fb.pop_clip();
+        fb.draw_line(Point::new(0, 12), Point::new(39, 12), INK);
+        assert!(inked(&fb, 9, 12));
+    }
+}
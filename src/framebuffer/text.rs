@@ -0,0 +1,551 @@
+// Font loading, text rendering, measurement and box layout
+
+use std::path::{Path, PathBuf};
+use fontdue::Font;
+use super::{Color, FontId, FontRegistry, Framebuffer, FramebufferError, Point, Rect};
+
+/// Horizontal placement of lines inside a text box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Size of a rendered string in pixels
+///
+/// `ascent` and `descent` come from the font's line metrics, so they are the
+/// same for every string of a given font and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextMetrics {
+    pub width: u16,
+    pub ascent: u16,
+    pub descent: u16,
+}
+
+impl TextMetrics {
+    /// Height of one line of text without extra spacing
+    pub fn height(&self) -> u16 {
+        self.ascent + self.descent
+    }
+}
+
+/// How `write_text_box` renders text inside its bounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub size: f32,
+    pub color: Color,
+    pub font: FontId,
+    pub align: TextAlign,
+    /// Multiplier on the font's natural line height
+    pub line_spacing: f32,
+    /// Wrap at word boundaries; otherwise every paragraph stays on one line
+    pub wrap: bool,
+    /// End text that does not fit with an ellipsis instead of cutting it off
+    pub ellipsis: bool,
+}
+
+impl TextStyle {
+    /// Left aligned, wrapped text with an ellipsis on overflow
+    pub fn new(size: f32, color: Color, font: FontId) -> Self {
+        Self {
+            size,
+            color,
+            font,
+            align: TextAlign::Left,
+            line_spacing: 1.0,
+            wrap: true,
+            ellipsis: true,
+        }
+    }
+
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn ellipsis(mut self, ellipsis: bool) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+}
+
+/// Characters of frequency and callsign readouts, worth rasterizing at startup
+pub const READOUT_GLYPHS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ.,:/-@#";
+
+/// Places characters one after another, applying kerning and font fallback
+#[derive(Clone)]
+struct Shaper<'a> {
+    fonts: &'a FontRegistry,
+    font_id: FontId,
+    size: f32,
+    previous: Option<(FontId, char)>,
+    /// Pen position after the last pushed character, relative to the start
+    cursor: f32,
+}
+
+/// A character resolved to the face that renders it, with its pen position
+struct ShapedChar<'a> {
+    font_id: FontId,
+    font: &'a Font,
+    x: f32,
+}
+
+impl<'a> Shaper<'a> {
+    fn new(fonts: &'a FontRegistry, font_id: FontId, size: f32) -> Result<Self, FramebufferError> {
+        if !fonts.contains(font_id) {
+            return Err(FramebufferError::FontNotLoaded(font_id));
+        }
+        Ok(Self { fonts, font_id, size, previous: None, cursor: 0.0 })
+    }
+
+    fn push(&mut self, ch: char) -> ShapedChar<'a> {
+        let (font_id, font) = self
+            .fonts
+            .resolve(self.font_id, ch)
+            .expect("primary face checked in Shaper::new");
+
+        // Kerning pairs only exist within one face
+        let kern = match self.previous {
+            Some((previous_id, previous_ch)) if previous_id == font_id => {
+                font.horizontal_kern(previous_ch, ch, self.size).unwrap_or(0.0)
+            }
+            _ => 0.0,
+        };
+
+        let x = self.cursor + kern;
+        self.cursor = x + font.metrics(ch, self.size).advance_width;
+        self.previous = Some((font_id, ch));
+
+        ShapedChar { font_id, font, x }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        for ch in text.chars() {
+            self.push(ch);
+        }
+    }
+
+    /// Starts over at the beginning of a new line
+    fn clear(&mut self) {
+        self.previous = None;
+        self.cursor = 0.0;
+    }
+}
+
+fn text_width(fonts: &FontRegistry, font_id: FontId, text: &str, size: f32) -> Result<f32, FramebufferError> {
+    let mut shaper = Shaper::new(fonts, font_id, size)?;
+    shaper.push_str(text);
+    Ok(shaper.cursor)
+}
+
+impl Framebuffer {
+    /// Loads a font from the specified path, or the default face for the font ID
+    ///
+    /// Without a path, regular, bold and mono use the embedded fonts and the
+    /// fallback face is read from the font directory.
+    pub fn load_font(&mut self, font_id: FontId, font_path: Option<&str>) -> Result<(), FramebufferError> {
+        self.glyph_cache.clear();
+        match font_path {
+            Some(path) => self.fonts.load_file(font_id, Path::new(path)),
+            None => self.fonts.load_default(font_id),
+        }
+    }
+    
+    /// Loads the regular, bold and mono faces plus the fallback and any extra faces in `font_dir`
+    ///
+    /// The embedded faces are always there; everything in `font_dir` is optional.
+    pub fn load_default_fonts<P: Into<PathBuf>>(&mut self, font_dir: P) -> Result<(), FramebufferError> {
+        self.glyph_cache.clear();
+        self.fonts.set_directory(font_dir);
+        self.fonts.load_builtin()?;
+        
+        match self.fonts.load_directory() {
+            Ok(extra) if !extra.is_empty() => println!("[Fonts] Loaded {} extra faces", extra.len()),
+            Ok(_) => {}
+            Err(e) => println!("[Fonts] Cannot read font directory {}: {}", self.fonts.directory().display(), e),
+        }
+        Ok(())
+    }
+    
+    /// Looks up a loaded face by name, see `FontRegistry::id_by_name`
+    pub fn font_by_name(&self, name: &str) -> Option<FontId> {
+        self.fonts.id_by_name(name)
+    }
+    
+    /// Renders text at the specified position with the given font and color
+    ///
+    /// `position` is the left end of the baseline. Kerning is applied, and
+    /// characters missing from the face are taken from the fallback face.
+    pub fn write_text(
+        &mut self,
+        text: &str,
+        position: Point,
+        size: f32,
+        color: Color,
+        font_id: FontId,
+    ) -> Result<(), FramebufferError> {
+        let bounds = self.clip_rect();
+        let mut shaper = Shaper::new(&self.fonts, font_id, size)?;
+        let origin_x = position.x as f32;
+        let baseline = position.y as i32;
+        // Marked once for the whole text rather than glyph by glyph
+        let mut drawn: Option<Rect> = None;
+
+        for ch in text.chars() {
+            let shaped = shaper.push(ch);
+            if ch.is_whitespace() {
+                continue;
+            }
+
+            let glyph = self.glyph_cache.get_or_rasterize(shaped.font, shaped.font_id, ch, size);
+            let metrics = glyph.metrics;
+            
+            let glyph_x = (origin_x + shaped.x + metrics.xmin as f32).round() as i32;
+            let glyph_y = baseline - (metrics.height as i32 + metrics.ymin);
+
+            if let Some(visible) = Rect::from_edges_clipped(
+                glyph_x,
+                glyph_y,
+                glyph_x + metrics.width as i32,
+                glyph_y + metrics.height as i32,
+                bounds,
+            ) {
+                for y in visible.y..visible.bottom() as u16 {
+                    let row = (y as i32 - glyph_y) as usize * metrics.width;
+                    for x in visible.x..visible.right() as u16 {
+                        let alpha = glyph.bitmap[row + (x as i32 - glyph_x) as usize];
+                        if alpha > 0 {
+                            self.canvas.set_pixel(x, y, Color::new(color.r, color.g, color.b, alpha))?;
+                        }
+                    }
+                }
+                drawn = Some(drawn.map_or(visible, |drawn| drawn.union(&visible)));
+            }
+        }
+
+        if let Some(drawn) = drawn {
+            self.dirty.add(drawn);
+        }
+        Ok(())
+    }
+    
+    /// Rasterizes `chars` ahead of time so the first frame using them is not slowed down
+    pub fn prewarm_glyphs(&mut self, chars: &str, size: f32, font_id: FontId) -> Result<(), FramebufferError> {
+        if !self.fonts.contains(font_id) {
+            return Err(FramebufferError::FontNotLoaded(font_id));
+        }
+        for ch in chars.chars().filter(|ch| !ch.is_whitespace()) {
+            if let Some((resolved_id, font)) = self.fonts.resolve(font_id, ch) {
+                self.glyph_cache.get_or_rasterize(font, resolved_id, ch, size);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Limits the memory used for cached glyph bitmaps
+    pub fn set_glyph_cache_limit(&mut self, bytes: usize) {
+        self.glyph_cache.set_capacity(bytes);
+    }
+    
+    /// Approximate memory currently used by cached glyphs in bytes
+    pub fn glyph_cache_usage(&self) -> usize {
+        self.glyph_cache.memory_usage()
+    }
+    
+    fn font(&self, font_id: FontId) -> Result<&Font, FramebufferError> {
+        self.fonts.get(font_id).ok_or(FramebufferError::FontNotLoaded(font_id))
+    }
+    
+    /// Measures a single line of text as `write_text` would render it
+    pub fn measure_text(&self, text: &str, size: f32, font_id: FontId) -> Result<TextMetrics, FramebufferError> {
+        let font = self.font(font_id)?;
+        let width = text_width(&self.fonts, font_id, text, size)?.ceil() as u16;
+        
+        let (ascent, descent) = match font.horizontal_line_metrics(size) {
+            Some(line) => (line.ascent.ceil() as u16, (-line.descent).ceil() as u16),
+            None => (size.ceil() as u16, 0),
+        };
+        
+        Ok(TextMetrics { width, ascent, descent })
+    }
+    
+    /// Shortens `text` so it fits into `max_width`, ending it with an ellipsis if cut
+    pub fn ellipsize(&self, text: &str, max_width: u16, size: f32, font_id: FontId) -> Result<String, FramebufferError> {
+        ellipsize_with(&self.fonts, font_id, text, max_width as f32, size)
+    }
+    
+    /// Breaks text into the lines `write_text_box` would draw inside `bounds`
+    ///
+    /// Paragraphs are separated by `\n`. Lines that do not fit vertically are
+    /// dropped; with `style.ellipsis` the last visible line ends in an ellipsis.
+    pub fn layout_text(&self, text: &str, bounds: Rect, style: &TextStyle) -> Result<Vec<String>, FramebufferError> {
+        let fonts = &self.fonts;
+        let max_width = bounds.width as f32;
+        let size = style.size;
+        
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            if style.wrap {
+                wrap_paragraph(fonts, style.font, paragraph, max_width, size, &mut lines)?;
+            } else {
+                lines.push(paragraph.to_string());
+            }
+        }
+        
+        let metrics = self.measure_text("", size, style.font)?;
+        let line_height = self.line_height(style)?;
+        let max_lines = if bounds.height < metrics.height() {
+            0
+        } else {
+            ((bounds.height - metrics.height()) as f32 / line_height) as usize + 1
+        };
+        
+        let truncated = lines.len() > max_lines;
+        lines.truncate(max_lines);
+        
+        if style.ellipsis {
+            let last = lines.len().saturating_sub(1);
+            for (i, line) in lines.iter_mut().enumerate() {
+                if i == last && truncated {
+                    let marker = ellipsis_marker(fonts, style.font);
+                    let mut shortened = line.trim_end().to_string();
+                    while !shortened.is_empty()
+                        && text_width(fonts, style.font, &format!("{}{}", shortened, marker), size)? > max_width
+                    {
+                        shortened.pop();
+                    }
+                    *line = format!("{}{}", shortened.trim_end(), marker);
+                } else {
+                    *line = ellipsize_with(fonts, style.font, line, max_width, size)?;
+                }
+            }
+        }
+        
+        Ok(lines)
+    }
+    
+    /// Draws text wrapped and aligned inside `bounds`, returning the number of lines drawn
+    ///
+    /// The first line's ascent touches the top of `bounds`.
+    pub fn write_text_box(&mut self, text: &str, bounds: Rect, style: &TextStyle) -> Result<usize, FramebufferError> {
+        let lines = self.layout_text(text, bounds, style)?;
+        let metrics = self.measure_text("", style.size, style.font)?;
+        let line_height = self.line_height(style)?;
+        
+        for (i, line) in lines.iter().enumerate() {
+            let width = self.measure_text(line, style.size, style.font)?.width.min(bounds.width);
+            let x = match style.align {
+                TextAlign::Left => bounds.x,
+                TextAlign::Center => bounds.x + (bounds.width - width) / 2,
+                TextAlign::Right => bounds.x + (bounds.width - width),
+            };
+            let baseline = bounds.y as f32 + metrics.ascent as f32 + i as f32 * line_height;
+            
+            self.write_text(line, Point::new(x, baseline.round() as u16), style.size, style.color, style.font)?;
+        }
+        
+        Ok(lines.len())
+    }
+    
+    /// Distance between the baselines of consecutive lines in a text box
+    pub fn line_height(&self, style: &TextStyle) -> Result<f32, FramebufferError> {
+        let font = self.font(style.font)?;
+        let natural = font
+            .horizontal_line_metrics(style.size)
+            .map_or(style.size, |line| line.new_line_size);
+        Ok((natural * style.line_spacing).max(1.0))
+    }
+}
+
+fn ellipsis_marker(fonts: &FontRegistry, font_id: FontId) -> &'static str {
+    let has_ellipsis = fonts
+        .resolve(font_id, '\u{2026}')
+        .is_some_and(|(_, font)| font.has_glyph('\u{2026}'));
+    if has_ellipsis { "\u{2026}" } else { "..." }
+}
+
+fn ellipsize_with(
+    fonts: &FontRegistry,
+    font_id: FontId,
+    text: &str,
+    max_width: f32,
+    size: f32,
+) -> Result<String, FramebufferError> {
+    if text_width(fonts, font_id, text, size)? <= max_width {
+        return Ok(text.to_string());
+    }
+    
+    let marker = ellipsis_marker(fonts, font_id);
+    let marker_width = text_width(fonts, font_id, marker, size)?;
+    let mut shaper = Shaper::new(fonts, font_id, size)?;
+    let mut result = String::new();
+    
+    for ch in text.chars() {
+        shaper.push(ch);
+        if shaper.cursor + marker_width > max_width {
+            break;
+        }
+        result.push(ch);
+    }
+    
+    Ok(format!("{}{}", result.trim_end(), marker))
+}
+
+/// Greedy word wrap; words wider than a whole line are split between characters
+fn wrap_paragraph(
+    fonts: &FontRegistry,
+    font_id: FontId,
+    paragraph: &str,
+    max_width: f32,
+    size: f32,
+    lines: &mut Vec<String>,
+) -> Result<(), FramebufferError> {
+    let mut line = String::new();
+    // Shaped up to the end of `line`, so each word is measured on top of it rather
+    // than shaping the whole line again
+    let mut shaper = Shaper::new(fonts, font_id, size)?;
+    
+    for word in paragraph.split(' ').filter(|w| !w.is_empty()) {
+        let mut extended = shaper.clone();
+        if !line.is_empty() {
+            extended.push(' ');
+        }
+        extended.push_str(word);
+        
+        if extended.cursor <= max_width {
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+            shaper = extended;
+            continue;
+        }
+        
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        shaper.clear();
+        
+        let mut alone = shaper.clone();
+        alone.push_str(word);
+        if alone.cursor <= max_width {
+            line.push_str(word);
+            shaper = alone;
+            continue;
+        }
+        
+        for ch in word.chars() {
+            let mut extended = shaper.clone();
+            extended.push(ch);
+            if !line.is_empty() && extended.cursor > max_width {
+                lines.push(std::mem::replace(&mut line, ch.to_string()));
+                shaper.clear();
+                shaper.push(ch);
+            } else {
+                line.push(ch);
+                shaper = extended;
+            }
+        }
+    }
+    
+    lines.push(line);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::framebuffer::PixelFormat;
+
+    fn framebuffer() -> Framebuffer {
+        let mut fb = Framebuffer::with_backend(Box::new(MemoryBackend::new(160, 128, PixelFormat::RGB565))).unwrap();
+        fb.load_default_fonts(crate::framebuffer::DEFAULT_FONT_DIR).unwrap();
+        fb
+    }
+
+    fn style() -> TextStyle {
+        TextStyle::new(12.0, Color::white(), FontId::Regular)
+    }
+
+    fn width(fb: &Framebuffer, line: &str) -> u16 {
+        fb.measure_text(line, 12.0, FontId::Regular).unwrap().width
+    }
+
+    #[test]
+    fn wraps_words_within_the_width() {
+        let fb = framebuffer();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let lines = fb.layout_text(text, Rect::new(0, 0, 60, 500), &style()).unwrap();
+
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| width(&fb, line) <= 60), "{:?}", lines);
+        assert_eq!(lines.join(" "), text);
+    }
+
+    #[test]
+    fn splits_words_wider_than_a_line() {
+        let fb = framebuffer();
+        let word = "Supercalifragilisticexpialidocious";
+        let lines = fb.layout_text(word, Rect::new(0, 0, 40, 500), &style()).unwrap();
+
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| width(&fb, line) <= 40), "{:?}", lines);
+        assert_eq!(lines.concat(), word);
+    }
+
+    #[test]
+    fn keeps_paragraphs_apart() {
+        let fb = framebuffer();
+        let bounds = Rect::new(0, 0, 160, 500);
+        assert_eq!(fb.layout_text("one\ntwo  three", bounds, &style()).unwrap(), ["one", "two three"]);
+        assert_eq!(fb.layout_text("one\ntwo  three", bounds, &style().wrap(false)).unwrap(), ["one", "two  three"]);
+    }
+
+    #[test]
+    fn drops_lines_below_the_box() {
+        let fb = framebuffer();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let one_line = fb.measure_text("", 12.0, FontId::Regular).unwrap().height();
+
+        assert_eq!(fb.layout_text(text, Rect::new(0, 0, 60, one_line), &style()).unwrap().len(), 1);
+        assert!(fb.layout_text(text, Rect::new(0, 0, 60, one_line - 1), &style()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ellipsis_marks_cut_text() {
+        let fb = framebuffer();
+        let one_line = fb.measure_text("", 12.0, FontId::Regular).unwrap().height();
+        let text = "The quick brown fox jumps over the lazy dog";
+        let lines = fb.layout_text(text, Rect::new(0, 0, 60, one_line), &style().ellipsis(true)).unwrap();
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with('\u{2026}') || lines[0].ends_with("..."), "{:?}", lines);
+        assert!(width(&fb, &lines[0]) <= 60);
+
+        let short = fb.layout_text("Short", Rect::new(0, 0, 60, one_line), &style().ellipsis(true)).unwrap();
+        assert_eq!(short, ["Short"]);
+    }
+
+    #[test]
+    fn ellipsize_shortens_to_fit() {
+        let fb = framebuffer();
+        assert_eq!(fb.ellipsize("Fits", 100, 12.0, FontId::Regular).unwrap(), "Fits");
+
+        let cut = fb.ellipsize("Much too long for the space", 50, 12.0, FontId::Regular).unwrap();
+        assert!(cut.len() < "Much too long for the space".len());
+        assert!(width(&fb, &cut) <= 50);
+    }
+}
@@ -0,0 +1,513 @@
+// Minimal image codecs: QOI/BMP loading for icons, PPM/PNG export for screenshots
+
+use std::io::{self, Write};
+use std::path::Path;
+use crate::framebuffer::{Color, FramebufferError, Rect};
+
+/// RGBA image held in memory, ready to be drawn with `Framebuffer::draw_bitmap`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Wraps raw RGBA pixel data, row by row without padding
+    pub fn from_rgba(width: u16, height: u16, data: Vec<u8>) -> Result<Self, FramebufferError> {
+        let expected = (width as usize) * (height as usize) * 4;
+        if data.len() != expected {
+            return Err(FramebufferError::BufferSizeMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        Ok(Self { width, height, data })
+    }
+
+    /// Decodes a QOI or BMP image, detected from its magic bytes
+    ///
+    /// Works well with `include_bytes!` for images embedded in the binary. Images are at
+    /// most 4096 pixels wide and tall.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FramebufferError> {
+        if bytes.starts_with(b"qoif") {
+            decode_qoi(bytes)
+        } else if bytes.starts_with(b"BM") {
+            decode_bmp(bytes)
+        } else {
+            Err(FramebufferError::ImageLoadError("Unknown image format, expected QOI or BMP".to_string()))
+        }
+    }
+
+    /// Loads a QOI or BMP image from disk
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FramebufferError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| {
+            FramebufferError::ImageLoadError(format!("Failed to read image '{}': {}", path.display(), e))
+        })?;
+
+        Self::from_bytes(&bytes).map_err(|e| match e {
+            FramebufferError::ImageLoadError(msg) => {
+                FramebufferError::ImageLoadError(format!("'{}': {}", path.display(), msg))
+            }
+            other => other,
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The whole image as a rectangle at the origin
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// Returns the source area of cell `index` in a sprite sheet of equally sized tiles,
+    /// counted left to right, top to bottom
+    pub fn tile(&self, index: u16, tile_width: u16, tile_height: u16) -> Rect {
+        let columns = (self.width / tile_width.max(1)).max(1);
+        Rect::new(
+            (index % columns) * tile_width,
+            (index / columns) * tile_height,
+            tile_width,
+            tile_height,
+        )
+    }
+
+    /// Raw RGBA pixel data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the pixel at the given position; the caller keeps it in bounds
+    #[inline]
+    pub fn pixel(&self, x: u16, y: u16) -> Color {
+        let offset = ((y as usize) * (self.width as usize) + x as usize) * 4;
+        Color::from_bytes(&self.data[offset..offset + 4])
+    }
+}
+
+fn image_error(msg: &str) -> FramebufferError {
+    FramebufferError::ImageLoadError(msg.to_string())
+}
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Widest and tallest image loaded, far beyond any icon but small enough that a bad header
+/// cannot ask for gigabytes
+const MAX_IMAGE_SIDE: u32 = 4096;
+
+fn checked_size(width: u32, height: u32) -> Result<(u16, u16), FramebufferError> {
+    if width == 0 || height == 0 || width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+        return Err(FramebufferError::ImageLoadError(format!("Unsupported image size {}x{}", width, height)));
+    }
+    Ok((width as u16, height as u16))
+}
+
+/// Decodes a "Quite OK Image" file (https://qoiformat.org)
+fn decode_qoi(bytes: &[u8]) -> Result<Bitmap, FramebufferError> {
+    const HEADER_LEN: usize = 14;
+    const OP_RGB: u8 = 0xFE;
+    const OP_RGBA: u8 = 0xFF;
+    // A run op repeats the previous pixel at most 62 times
+    const MAX_PIXELS_PER_BYTE: usize = 62;
+
+    let width = read_u32_be(bytes, 4).ok_or_else(|| image_error("Truncated QOI header"))?;
+    let height = read_u32_be(bytes, 8).ok_or_else(|| image_error("Truncated QOI header"))?;
+    let (width, height) = checked_size(width, height)?;
+
+    let pixel_count = (width as usize) * (height as usize);
+    if pixel_count > bytes.len().saturating_sub(HEADER_LEN) * MAX_PIXELS_PER_BYTE {
+        return Err(image_error("Truncated QOI data"));
+    }
+    // Bounded by the input length checked above, not just the header
+    let mut data = Vec::with_capacity(pixel_count * 4);
+    let mut index = [[0u8; 4]; 64];
+    let mut px = [0u8, 0, 0, 255];
+    let mut run = 0u8;
+    let mut pos = HEADER_LEN;
+
+    let next = |pos: &mut usize| -> Result<u8, FramebufferError> {
+        let byte = *bytes.get(*pos).ok_or_else(|| image_error("Truncated QOI data"))?;
+        *pos += 1;
+        Ok(byte)
+    };
+
+    for _ in 0..pixel_count {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let op = next(&mut pos)?;
+            match op {
+                OP_RGB => {
+                    px[0] = next(&mut pos)?;
+                    px[1] = next(&mut pos)?;
+                    px[2] = next(&mut pos)?;
+                }
+                OP_RGBA => {
+                    px[0] = next(&mut pos)?;
+                    px[1] = next(&mut pos)?;
+                    px[2] = next(&mut pos)?;
+                    px[3] = next(&mut pos)?;
+                }
+                _ => match op >> 6 {
+                    0 => px = index[op as usize],
+                    1 => {
+                        px[0] = px[0].wrapping_add(((op >> 4) & 0x03).wrapping_sub(2));
+                        px[1] = px[1].wrapping_add(((op >> 2) & 0x03).wrapping_sub(2));
+                        px[2] = px[2].wrapping_add((op & 0x03).wrapping_sub(2));
+                    }
+                    2 => {
+                        let dg = (op & 0x3F).wrapping_sub(32);
+                        let second = next(&mut pos)?;
+                        px[0] = px[0].wrapping_add(dg.wrapping_add(second >> 4).wrapping_sub(8));
+                        px[1] = px[1].wrapping_add(dg);
+                        px[2] = px[2].wrapping_add(dg.wrapping_add(second & 0x0F).wrapping_sub(8));
+                    }
+                    _ => run = op & 0x3F,
+                },
+            }
+
+            let hash = (px[0] as usize * 3 + px[1] as usize * 5 + px[2] as usize * 7 + px[3] as usize * 11) % 64;
+            index[hash] = px;
+        }
+
+        data.extend_from_slice(&px);
+    }
+
+    Bitmap::from_rgba(width, height, data)
+}
+
+/// Decodes an uncompressed 24 or 32 bpp Windows bitmap
+///
+/// 32 bpp files with `BI_BITFIELDS` masks keep their alpha channel.
+fn decode_bmp(bytes: &[u8]) -> Result<Bitmap, FramebufferError> {
+    const BI_RGB: u32 = 0;
+    const BI_BITFIELDS: u32 = 3;
+    const BI_ALPHABITFIELDS: u32 = 6;
+
+    let truncated = || image_error("Truncated BMP header");
+    let data_offset = read_u32_le(bytes, 10).ok_or_else(truncated)? as usize;
+    let header_size = read_u32_le(bytes, 14).ok_or_else(truncated)?;
+    let raw_width = read_u32_le(bytes, 18).ok_or_else(truncated)? as i32;
+    let raw_height = read_u32_le(bytes, 22).ok_or_else(truncated)? as i32;
+    let bits_per_pixel = read_u16_le(bytes, 28).ok_or_else(truncated)?;
+    let compression = read_u32_le(bytes, 30).ok_or_else(truncated)?;
+
+    if header_size < 40 {
+        return Err(image_error("Unsupported BMP header version"));
+    }
+
+    let (width, height) = checked_size(raw_width.unsigned_abs(), raw_height.unsigned_abs())?;
+    let top_down = raw_height < 0;
+
+    // Channel masks in R, G, B, A order
+    let masks = match (bits_per_pixel, compression) {
+        (24, BI_RGB) => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0],
+        (32, BI_RGB) => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0],
+        (32, BI_BITFIELDS) | (32, BI_ALPHABITFIELDS) => {
+            let alpha = if header_size >= 56 || compression == BI_ALPHABITFIELDS {
+                read_u32_le(bytes, 66).ok_or_else(truncated)?
+            } else {
+                0
+            };
+            [
+                read_u32_le(bytes, 54).ok_or_else(truncated)?,
+                read_u32_le(bytes, 58).ok_or_else(truncated)?,
+                read_u32_le(bytes, 62).ok_or_else(truncated)?,
+                alpha,
+            ]
+        }
+        _ => {
+            return Err(FramebufferError::ImageLoadError(format!(
+                "Unsupported BMP format: {} bpp, compression {}",
+                bits_per_pixel, compression
+            )))
+        }
+    };
+
+    let extract = |value: u32, mask: u32| -> u8 {
+        if mask == 0 {
+            return 255;
+        }
+        let shifted = (value & mask) >> mask.trailing_zeros();
+        let bits = (mask >> mask.trailing_zeros()).count_ones();
+        if bits >= 8 {
+            (shifted >> (bits - 8)) as u8
+        } else {
+            ((shifted * 255) / ((1 << bits) - 1)) as u8
+        }
+    };
+
+    let bytes_per_pixel = (bits_per_pixel / 8) as usize;
+    let row_size = ((width as usize) * bytes_per_pixel).div_ceil(4) * 4;
+    let pixels_end = data_offset + (height as usize - 1) * row_size + (width as usize) * bytes_per_pixel;
+    if bytes.len() < pixels_end {
+        return Err(image_error("Truncated BMP pixel data"));
+    }
+    // Bounded by the input length checked above, not just the header
+    let mut data = Vec::with_capacity((width as usize) * (height as usize) * 4);
+
+    for y in 0..height as usize {
+        let src_row = if top_down { y } else { height as usize - 1 - y };
+        let row_start = data_offset + src_row * row_size;
+        let row = bytes
+            .get(row_start..row_start + (width as usize) * bytes_per_pixel)
+            .ok_or_else(|| image_error("Truncated BMP pixel data"))?;
+
+        for pixel in row.chunks_exact(bytes_per_pixel) {
+            let value = if bytes_per_pixel == 4 {
+                u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]])
+            } else {
+                u32::from_le_bytes([pixel[0], pixel[1], pixel[2], 0])
+            };
+            data.extend_from_slice(&[
+                extract(value, masks[0]),
+                extract(value, masks[1]),
+                extract(value, masks[2]),
+                extract(value, masks[3]),
+            ]);
+        }
+    }
+
+    Bitmap::from_rgba(width, height, data)
+}
+
+/// Writes RGBA pixel data as a binary PPM (P6) image, dropping the alpha channel
+pub fn write_ppm<W: Write>(writer: &mut W, rgba: &[u8], width: u16, height: u16) -> io::Result<()> {
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+
+    let mut row = Vec::with_capacity((width as usize) * 3);
+    for line in rgba.chunks_exact((width as usize) * 4).take(height as usize) {
+        row.clear();
+        for pixel in line.chunks_exact(4) {
+            row.extend_from_slice(&pixel[..3]);
+        }
+        writer.write_all(&row)?;
+    }
+
+    Ok(())
+}
+
+/// Writes RGBA pixel data as an 8-bit RGB PNG image
+///
+/// The image data is stored with uncompressed deflate blocks, which keeps the
+/// encoder tiny; screenshots of a small LCD are only a few tens of kilobytes.
+pub fn write_png<W: Write>(writer: &mut W, rgba: &[u8], width: u16, height: u16) -> io::Result<()> {
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_png_chunk(writer, b"IHDR", &header)?;
+
+    let mut raw = Vec::with_capacity((height as usize) * ((width as usize) * 3 + 1));
+    for line in rgba.chunks_exact((width as usize) * 4).take(height as usize) {
+        raw.push(0);
+        for pixel in line.chunks_exact(4) {
+            raw.extend_from_slice(&pixel[..3]);
+        }
+    }
+    write_png_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+
+    write_png_chunk(writer, b"IEND", &[])
+}
+
+fn write_png_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let crc = !crc32_update(crc32_update(0xFFFF_FFFF, kind), data);
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// Wraps data in a zlib stream made of stored (uncompressed) deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(is_last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qoi(width: u32, height: u32, ops: &[u8]) -> Vec<u8> {
+        let mut bytes = b"qoif".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&[4, 0]);
+        bytes.extend_from_slice(ops);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        bytes
+    }
+
+    /// 24 bpp bottom-up bitmap from rows of BGR pixels, top row first
+    fn bmp(rows: &[&[[u8; 3]]]) -> Vec<u8> {
+        let width = rows[0].len();
+        let row_size = (width * 3).div_ceil(4) * 4;
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&((54 + row_size * rows.len()) as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&54u32.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&(width as u32).to_le_bytes());
+        bytes.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&24u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 24]);
+        for row in rows.iter().rev() {
+            let start = bytes.len();
+            row.iter().for_each(|pixel| bytes.extend_from_slice(pixel));
+            bytes.resize(start + row_size, 0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_qoi_ops() {
+        // RGBA red, a run of one, RGB blue, then blue again from the index
+        let bytes = qoi(2, 2, &[0xFF, 255, 0, 0, 255, 0xC0, 0xFE, 0, 0, 255, 46]);
+        let bitmap = Bitmap::from_bytes(&bytes).unwrap();
+
+        assert_eq!((bitmap.width(), bitmap.height()), (2, 2));
+        assert_eq!(bitmap.data(), [255, 0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn rejects_qoi_larger_than_its_data() {
+        let mut truncated = qoi(2, 2, &[0xFE, 1, 2, 3]);
+        truncated.truncate(18);
+        assert!(Bitmap::from_bytes(&truncated).is_err());
+        assert!(Bitmap::from_bytes(&qoi(4096, 4096, &[0xC0])).is_err());
+        assert!(Bitmap::from_bytes(&qoi(65535, 65535, &[0xFD; 64])).is_err());
+        assert!(Bitmap::from_bytes(&qoi(0, 1, &[])).is_err());
+    }
+
+    #[test]
+    fn decodes_bottom_up_bmp() {
+        let bytes = bmp(&[&[[0, 0, 255], [0, 255, 0]], &[[255, 0, 0], [255, 255, 255]]]);
+        let bitmap = Bitmap::from_bytes(&bytes).unwrap();
+
+        assert_eq!((bitmap.width(), bitmap.height()), (2, 2));
+        assert_eq!(bitmap.pixel(0, 0), Color::new(255, 0, 0, 255));
+        assert_eq!(bitmap.pixel(1, 0), Color::new(0, 255, 0, 255));
+        assert_eq!(bitmap.pixel(0, 1), Color::new(0, 0, 255, 255));
+        assert_eq!(bitmap.pixel(1, 1), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn rejects_bmp_larger_than_its_data() {
+        let mut bytes = bmp(&[&[[0, 0, 0]]]);
+        bytes[22..26].copy_from_slice(&4000u32.to_le_bytes());
+        assert!(Bitmap::from_bytes(&bytes).is_err());
+
+        bytes[18..22].copy_from_slice(&65535u32.to_le_bytes());
+        assert!(Bitmap::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(!crc32_update(0xFFFF_FFFF, b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn writes_png_with_stored_image_data() {
+        let rgba = [255, 0, 0, 255, 0, 0, 255, 128];
+        let mut png = Vec::new();
+        write_png(&mut png, &rgba, 2, 1).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let mut chunks = Vec::new();
+        let mut pos = 8;
+        while pos < png.len() {
+            let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind = &png[pos + 4..pos + 8];
+            let data = &png[pos + 8..pos + 8 + len];
+            let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+            assert_eq!(crc, !crc32_update(crc32_update(0xFFFF_FFFF, kind), data));
+            chunks.push((kind.to_vec(), data.to_vec()));
+            pos += 12 + len;
+        }
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], (b"IHDR".to_vec(), vec![0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]));
+        assert_eq!(chunks[2], (b"IEND".to_vec(), vec![]));
+
+        let raw = [0, 255, 0, 0, 0, 0, 255];
+        let mut idat = vec![0x78, 0x01, 1, 7, 0, !7, 0xFF];
+        idat.extend_from_slice(&raw);
+        idat.extend_from_slice(&adler32(&raw).to_be_bytes());
+        assert_eq!(chunks[1], (b"IDAT".to_vec(), idat));
+    }
+
+    #[test]
+    fn writes_ppm_without_alpha() {
+        let mut ppm = Vec::new();
+        write_ppm(&mut ppm, &[1, 2, 3, 4, 5, 6, 7, 8], 2, 1).unwrap();
+        assert_eq!(ppm, b"P6\n2 1\n255\n\x01\x02\x03\x05\x06\x07");
+    }
+}
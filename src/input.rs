@@ -10,6 +10,17 @@ pub enum InputEvent {
     PreviousFlowgraph,
     Run,
     Stop,
+    /// Leave the current screen
+    Back,
+    /// Open the settings menu
+    Menu,
+    /// A keypad digit from 0 to 9
+    Digit(u8),
+    /// Switch what up and down do on the idle screen
+    Mode,
+    /// Cycle the tuning step
+    Step,
+    Screenshot,
     Quit,
 }
 
@@ -36,6 +47,12 @@ impl InputHandler {
                         b'-' => Some(InputEvent::PreviousFlowgraph),
                         b'r' | b'R' => Some(InputEvent::Run),
                         b's' | b'S' => Some(InputEvent::Stop),
+                        b'b' | b'B' | 0x08 | 0x7f => Some(InputEvent::Back),
+                        b'm' | b'M' => Some(InputEvent::Menu),
+                        digit @ b'0'..=b'9' => Some(InputEvent::Digit(digit - b'0')),
+                        b'*' => Some(InputEvent::Mode),
+                        b'#' => Some(InputEvent::Step),
+                        b'p' | b'P' => Some(InputEvent::Screenshot),
                         b'q' | b'Q' => Some(InputEvent::Quit),
                         _ => None,
                     };
@@ -84,7 +101,11 @@ impl InputHandler {
                                     Key::KEY_DOWN => Some(InputEvent::PreviousFlowgraph),
                                     Key::KEY_ENTER => Some(InputEvent::Run),
                                     Key::KEY_ESC => Some(InputEvent::Stop),
-                                    _ => None,
+                                    Key::KEY_BACK | Key::KEY_BACKSPACE => Some(InputEvent::Back),
+                                    Key::KEY_MENU => Some(InputEvent::Menu),
+                                    Key::KEY_NUMERIC_STAR | Key::KEY_KPASTERISK => Some(InputEvent::Mode),
+                                    Key::KEY_NUMERIC_POUND => Some(InputEvent::Step),
+                                    key => keypad_digit(key).map(InputEvent::Digit),
                                 };
                                 
                                 if let Some(event) = input_event {
@@ -112,6 +133,31 @@ impl InputHandler {
     }
 }
 
+impl Default for InputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps number row, numeric keypad and phone keypad keys to their digit
+fn keypad_digit(key: evdev::Key) -> Option<u8> {
+    use evdev::Key;
+
+    let digits = [
+        [Key::KEY_0, Key::KEY_KP0, Key::KEY_NUMERIC_0],
+        [Key::KEY_1, Key::KEY_KP1, Key::KEY_NUMERIC_1],
+        [Key::KEY_2, Key::KEY_KP2, Key::KEY_NUMERIC_2],
+        [Key::KEY_3, Key::KEY_KP3, Key::KEY_NUMERIC_3],
+        [Key::KEY_4, Key::KEY_KP4, Key::KEY_NUMERIC_4],
+        [Key::KEY_5, Key::KEY_KP5, Key::KEY_NUMERIC_5],
+        [Key::KEY_6, Key::KEY_KP6, Key::KEY_NUMERIC_6],
+        [Key::KEY_7, Key::KEY_KP7, Key::KEY_NUMERIC_7],
+        [Key::KEY_8, Key::KEY_KP8, Key::KEY_NUMERIC_8],
+        [Key::KEY_9, Key::KEY_KP9, Key::KEY_NUMERIC_9],
+    ];
+    digits.iter().position(|keys| keys.contains(&key)).map(|digit| digit as u8)
+}
+
 /// Find the keypad device by scanning /sys/class/input/*/name
 fn find_keypad_device() -> Option<PathBuf> {
     let input_dir = PathBuf::from("/sys/class/input");
@@ -5,14 +5,49 @@
 //! - M17 protocol parsing
 //! - Flowgraph management
 //! - GPIO-ready input handling
+//! - Backlight dimming and display blanking when idle
+//! - Retained widget toolkit with a layout pass
+//! - Screen stack with push/pop navigation
+//! - Settings menu persisted to a config file
+//! - Color themes loaded from files, switchable at runtime
+//! - Status bar with clock, battery, signal and transmit indicators
+//! - Radio state shown on the idle screen and passed to flowgraphs
+//! - Frequency entry and step tuning within the amateur bands
+//! - Channel memories in CHIRP compatible CSV files
+//! - Spectrum and waterfall of the running flowgraph
 
+pub mod backend;
 pub mod framebuffer;
+pub mod image;
 pub mod display;
 pub mod m17;
 pub mod input;
+pub mod power;
+pub mod widgets;
+pub mod navigation;
+pub mod memory;
+pub mod menu;
+pub mod radio;
+pub mod settings;
+pub mod spectrum;
+pub mod status;
+pub mod theme;
 
 // Re-export main types
-pub use framebuffer::{Framebuffer, Color, Point, FontId, FramebufferError};
+pub use framebuffer::{Framebuffer, Color, Point, Rect, FontId, FramebufferError, PixelFormat, Bitfield, DeviceInfo, Rotation, LayerId, Dithering, TextAlign, TextMetrics, TextStyle};
+pub use framebuffer::{FontRegistry, DEFAULT_FONT_DIR, EMBEDDED_BOLD_FONT, EMBEDDED_MONO_FONT, EMBEDDED_REGULAR_FONT, DEFAULT_GLYPH_CACHE_BYTES, READOUT_GLYPHS};
+pub use backend::{Backend, FbdevBackend, MemoryBackend, SocketBackend, SpiLcdBackend, SpiLcdConfig};
 pub use display::Display;
+pub use image::Bitmap;
 pub use m17::parse_m17_line;
 pub use input::{InputEvent, InputHandler};
+pub use power::{Backlight, PowerManager, PowerState};
+pub use widgets::{Ui, Widget, WidgetId};
+pub use navigation::{Screen, ScreenStack, Transition};
+pub use memory::{Channel, MemoryBank};
+pub use menu::{ChoiceEditor, FrequencyEntry, MenuEntry, MenuScreen, NumberEditor, TextEditor};
+pub use radio::RadioState;
+pub use settings::Settings;
+pub use spectrum::{SpectrumReceiver, SpectrumView};
+pub use status::{Activity, Battery, FlowgraphState, StatusIndicators};
+pub use theme::Theme;
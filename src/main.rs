@@ -4,33 +4,44 @@ use std::process::{Command, Child, Stdio};
 use std::io::{BufReader, BufRead};
 use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-mod framebuffer;
-mod display;
-mod m17;
-mod input;
+use linht_gui::{Framebuffer, Display, parse_m17_line, InputEvent, InputHandler, Rotation, Dithering, DEFAULT_FONT_DIR};
+use linht_gui::{Backlight, PowerManager, ScreenStack, Settings, Theme};
+use linht_gui::{Activity, Battery, Channel, FlowgraphState, MemoryBank, SpectrumReceiver, StatusIndicators};
+use linht_gui::radio::{format_frequency, format_offset};
+use linht_gui::memory::flowgraph_mode;
+use linht_gui::status::{parse_radio_report, signal_level, ClockTime, RadioReport};
+use linht_gui::backend::{Backend, FbdevBackend, SocketBackend, SpiLcdBackend, SpiLcdConfig, DEFAULT_SOCKET_PATH};
 
-use framebuffer::{Framebuffer, FontId};
-use display::Display;
-use m17::parse_m17_line;
-use input::{InputEvent, InputHandler};
+mod screens;
 
-struct AppState {
+use screens::HomeScreen;
+
+/// Settings, flowgraphs and the running process, shared by all screens
+pub struct AppState {
+    settings: Settings,
+    settings_path: PathBuf,
+    memories: MemoryBank,
+    memories_path: PathBuf,
     flowgraphs: Vec<PathBuf>,
     current_index: usize,
     running_process: Option<Child>,
     process_output_rx: Option<Receiver<String>>,
     last_message: Option<String>,
-    display: Display,
-    input_handler: InputHandler,
+    /// Last signal strength reported by the flowgraph, in dBm
+    rssi: Option<f32>,
+    ptt: bool,
+    /// The flowgraph failed to start or exited on its own
+    failed: bool,
+    /// When the running flowgraph last sent its spectrum
+    spectrum_at: Option<Instant>,
+    /// When settings changed by a held key were last changed, unsaved since
+    settings_changed_at: Option<Instant>,
 }
 
 impl AppState {
-    fn new(framebuffer_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut fb = Framebuffer::new(framebuffer_path)?;
-        fb.load_font(FontId::Regular, Some("fonts/DidactGothic-Regular.ttf"))?;
-        
+    fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let flowgraphs = discover_flowgraphs("flowgraphs")?;
         if flowgraphs.is_empty() {
             return Err("No flowgraphs found in flowgraphs/ directory".into());
@@ -41,17 +52,188 @@ impl AppState {
             println!("[Main]   [{}] {}", i, fg.file_name().unwrap_or_default().to_string_lossy());
         }
         
+        let settings_path = Settings::default_path();
+        let mut settings = match Settings::load(&settings_path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                println!("[Main] Failed to read {}: {}", settings_path.display(), e);
+                Settings::default()
+            }
+        };
+        println!("[Main] Settings file: {}", settings_path.display());
+        timeout_from_env("LINHT_DIM_TIMEOUT", &mut settings.dim_timeout);
+        timeout_from_env("LINHT_BLANK_TIMEOUT", &mut settings.blank_timeout);
+        
+        let memories_path = MemoryBank::default_path();
+        let memories = match MemoryBank::load(&memories_path) {
+            Ok(memories) => memories,
+            Err(e) => {
+                println!("[Main] Failed to read {}: {}", memories_path.display(), e);
+                MemoryBank::default()
+            }
+        };
+        println!("[Main] {} memories in {}", memories.len(), memories_path.display());
+        
+        let current_index = settings
+            .default_flowgraph
+            .as_ref()
+            .and_then(|name| flowgraphs.iter().position(|path| path.file_name().is_some_and(|file| file == name.as_str())))
+            .unwrap_or(0);
+        
         Ok(AppState {
+            settings,
+            settings_path,
+            memories,
+            memories_path,
             flowgraphs,
-            current_index: 0,
+            current_index,
             running_process: None,
             process_output_rx: None,
             last_message: None,
-            display: Display::new(fb),
-            input_handler: InputHandler::new(),
+            rssi: None,
+            ptt: false,
+            failed: false,
+            spectrum_at: None,
+            settings_changed_at: None,
         })
     }
     
+    fn save_settings(&mut self) {
+        self.settings_changed_at = None;
+        match self.settings.save(&self.settings_path) {
+            Ok(()) => println!("[Main] Settings saved"),
+            Err(e) => println!("[Main] Failed to save {}: {}", self.settings_path.display(), e),
+        }
+    }
+    
+    /// Saves the settings once they stopped changing for `SETTINGS_SAVE_DELAY`
+    ///
+    /// For changes made by repeated key presses, like tuning, so that holding
+    /// a key does not rewrite the file over and over.
+    fn save_settings_later(&mut self) {
+        self.settings_changed_at = Some(Instant::now());
+    }
+    
+    /// Saves settings left by `save_settings_later`, right away if `now` is set
+    fn save_pending_settings(&mut self, now: bool) {
+        if self.settings_changed_at.is_some_and(|changed| now || changed.elapsed() >= SETTINGS_SAVE_DELAY) {
+            self.save_settings();
+        }
+    }
+    
+    fn save_memories(&self) {
+        match self.memories.save(&self.memories_path) {
+            Ok(()) => println!("[Main] Memories saved"),
+            Err(e) => println!("[Main] Failed to save {}: {}", self.memories_path.display(), e),
+        }
+    }
+    
+    /// Index of the memory the radio is tuned to
+    fn current_channel(&self) -> Option<usize> {
+        self.settings.radio.memory.and_then(|location| self.memories.find(location))
+    }
+    
+    /// Tunes to a memory and selects its flowgraph, if it names one that exists
+    ///
+    /// Returns false for memories outside the bands, which are left alone. The
+    /// settings are not saved.
+    fn recall_channel(&mut self, index: usize) -> bool {
+        let Some(channel) = self.memories.channels.get(index).cloned() else {
+            return false;
+        };
+        let radio = channel.radio_state();
+        if !radio.in_band() {
+            println!("[Main] Memory {} ({} MHz) is out of band", channel.location, format_frequency(channel.rx_frequency));
+            return false;
+        }
+        println!("[Main] Recalled memory {}: {}", channel.location, channel.label());
+        self.settings.radio = radio;
+        
+        if let Some(flowgraph) = &channel.flowgraph {
+            let position = self.flowgraph_names().position(|name| name == *flowgraph);
+            match position {
+                Some(position) => self.current_index = position,
+                None => println!("[Main] Flowgraph {} of memory {} not found", flowgraph, channel.name),
+            }
+        }
+        self.failed = false;
+        true
+    }
+    
+    /// Recalls the next or previous memory within the bands, wrapping around
+    fn step_channel(&mut self, forward: bool) {
+        let count = self.memories.len();
+        let mut index = self.current_channel();
+        for _ in 0..count {
+            let next = match index {
+                Some(index) if forward => (index + 1) % count,
+                Some(index) => (index + count - 1) % count,
+                None if forward => 0,
+                None => count - 1,
+            };
+            if self.recall_channel(next) {
+                self.save_settings_later();
+                return;
+            }
+            index = Some(next);
+        }
+    }
+    
+    /// Stores the current frequencies and flowgraph as a memory, replacing one of the same name
+    ///
+    /// The mode follows the flowgraph where its name tells it; the other columns
+    /// of a replaced memory, such as its tone and comment, are kept.
+    fn store_channel(&mut self, name: String) {
+        let radio = &self.settings.radio;
+        let flowgraph = self.current_flowgraph_name();
+        let existing = self.memories.position(&name);
+        let mut channel = match existing {
+            Some(index) => self.memories.channels[index].clone(),
+            None => Channel::new(0, name, radio.rx_frequency),
+        };
+        channel.rx_frequency = radio.rx_frequency;
+        channel.tx_frequency = radio.tx_frequency;
+        channel.tx_inhibit = radio.tx_inhibit;
+        if let Some(mode) = flowgraph_mode(&flowgraph) {
+            channel.mode = mode.to_string();
+        }
+        channel.flowgraph = Some(flowgraph);
+        
+        let index = match existing {
+            Some(index) => {
+                self.memories.channels[index] = channel;
+                index
+            }
+            None => {
+                self.memories.push(channel);
+                self.memories.len() - 1
+            }
+        };
+        self.save_memories();
+        if self.recall_channel(index) {
+            self.save_settings();
+        }
+    }
+    
+    fn delete_channel(&mut self, index: usize) {
+        if index >= self.memories.len() {
+            return;
+        }
+        if self.current_channel() == Some(index) {
+            self.settings.radio.leave_channel();
+            self.save_settings();
+        }
+        let channel = self.memories.channels.remove(index);
+        println!("[Main] Deleted memory {}: {}", channel.location, channel.name);
+        self.save_memories();
+    }
+    
+    fn flowgraph_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.flowgraphs
+            .iter()
+            .map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string())
+    }
+    
     fn current_flowgraph_name(&self) -> String {
         self.flowgraphs[self.current_index]
             .file_name()
@@ -65,6 +247,7 @@ impl AppState {
             return;
         }
         self.current_index = (self.current_index + 1) % self.flowgraphs.len();
+        self.failed = false;
         println!("[Main] Switched to flowgraph: {}", self.current_flowgraph_name());
     }
     
@@ -77,6 +260,7 @@ impl AppState {
         } else {
             self.current_index -= 1;
         }
+        self.failed = false;
         println!("[Main] Switched to flowgraph: {}", self.current_flowgraph_name());
     }
     
@@ -85,13 +269,25 @@ impl AppState {
         
         let flowgraph_path = &self.flowgraphs[self.current_index];
         println!("[Main] Starting flowgraph: {}", flowgraph_path.display());
+        let radio = &self.settings.radio;
+        println!(
+            "[Main] Tuned to {} ({} MHz, {})",
+            radio.channel_name(),
+            format_frequency(radio.rx_frequency),
+            format_offset(radio.offset()),
+        );
         
+        self.failed = true;
         let mut child = Command::new("python3")
             .arg(flowgraph_path)
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
             .stdin(Stdio::null())
             .env("PYTHONUNBUFFERED", "1")
+            .env("LINHT_CALLSIGN", &self.settings.callsign)
+            .env("LINHT_VOLUME", self.settings.volume.to_string())
+            .envs(self.settings.radio.environment())
+            .envs(self.current_channel().map(|index| self.memories.channels[index].environment()).unwrap_or_default())
             .spawn()?;
         
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
@@ -99,19 +295,17 @@ impl AppState {
         
         thread::spawn(move || {
             let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    if line.contains("LSF_CRC_OK") {
-                        if tx.send(line).is_err() {
-                            break;
-                        }
-                    }
+            for line in reader.lines().map_while(Result::ok) {
+                let wanted = line.contains("LSF_CRC_OK") || parse_radio_report(&line).is_some();
+                if wanted && tx.send(line).is_err() {
+                    break;
                 }
             }
         });
         
         self.running_process = Some(child);
         self.process_output_rx = Some(rx);
+        self.failed = false;
         
         println!("[Main] Flowgraph started");
         Ok(())
@@ -125,25 +319,34 @@ impl AppState {
         }
         self.process_output_rx = None;
         self.last_message = None;
+        self.rssi = None;
+        self.ptt = false;
+        self.failed = false;
+        self.spectrum_at = None;
     }
     
-    fn check_process_output(&mut self) {
-        if let Some(rx) = &self.process_output_rx {
+    /// Collects output of the running flowgraph, returning true if an M17 message arrived
+    fn check_process_output(&mut self) -> bool {
+        let mut received = false;
+        while let Some(rx) = &self.process_output_rx {
             match rx.try_recv() {
                 Ok(line) => {
                     if let Some(message) = parse_m17_line(&line) {
                         println!("[M17] Message: {}", message);
-                        self.display.show_m17_message(&message);
                         self.last_message = Some(message);
+                        received = true;
+                    }
+                    match parse_radio_report(&line) {
+                        Some(RadioReport::Rssi(dbm)) => self.rssi = Some(dbm),
+                        Some(RadioReport::Ptt(pressed)) => self.ptt = pressed,
+                        None => {}
                     }
                 }
                 Err(TryRecvError::Disconnected) => {
                     println!("[Main] Process output ended");
-                    self.running_process = None;
-                    self.process_output_rx = None;
-                }
-                Err(TryRecvError::Empty) => {
+                    self.process_ended();
                 }
+                Err(TryRecvError::Empty) => break,
             }
         }
         
@@ -151,31 +354,216 @@ impl AppState {
             match child.try_wait() {
                 Ok(Some(status)) => {
                     println!("[Main] Process exited with: {:?}", status);
-                    self.running_process = None;
-                    self.process_output_rx = None;
+                    self.process_ended();
                 }
                 Ok(None) => {
                 }
                 Err(e) => {
                     println!("[Main] Error checking process status: {}", e);
-                    self.running_process = None;
-                    self.process_output_rx = None;
+                    self.process_ended();
                 }
             }
         }
+        
+        received
     }
     
-    fn is_running(&self) -> bool {
-        self.running_process.is_some()
+    /// Forgets a flowgraph that exited without being stopped
+    fn process_ended(&mut self) {
+        self.running_process = None;
+        self.process_output_rx = None;
+        self.rssi = None;
+        self.ptt = false;
+        self.failed = true;
+        self.spectrum_at = None;
     }
     
-    fn update_display(&mut self) {
+    fn flowgraph_state(&self) -> FlowgraphState {
         if self.is_running() {
-            self.display.show_status(&format!("Running: {}", self.current_flowgraph_name()));
+            FlowgraphState::Running
+        } else if self.failed {
+            FlowgraphState::Failed
         } else {
-            self.display.show_idle(&self.current_flowgraph_name());
+            FlowgraphState::Stopped
         }
     }
+    
+    /// Indicators for the status bar, reading the battery if there is one
+    fn status_indicators(&self, battery: Option<&mut Battery>) -> StatusIndicators {
+        let activity = match (self.is_running(), self.ptt) {
+            (false, _) => Activity::Idle,
+            (true, false) => Activity::Receiving,
+            (true, true) => Activity::Transmitting,
+        };
+        
+        StatusIndicators {
+            clock: ClockTime::now(),
+            battery: battery.and_then(|battery| battery.level().ok()),
+            signal: self.rssi.map(signal_level),
+            activity,
+            flowgraph: self.flowgraph_state(),
+        }
+    }
+    
+    fn is_running(&self) -> bool {
+        self.running_process.is_some()
+    }
+    
+    /// Whether the running flowgraph is sending its spectrum
+    fn has_spectrum(&self) -> bool {
+        self.spectrum_at.is_some_and(|received| received.elapsed() < SPECTRUM_TIMEOUT)
+    }
+}
+
+/// How long settings changed by a held key wait before they are saved
+const SETTINGS_SAVE_DELAY: Duration = Duration::from_secs(3);
+
+/// How long the spectrum stays on screen after the flowgraph stopped sending it
+const SPECTRUM_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Listens for spectra on `LINHT_SPECTRUM_ADDR`, going without if the address is taken
+fn spectrum_receiver() -> Option<SpectrumReceiver> {
+    let address = SpectrumReceiver::default_address();
+    match SpectrumReceiver::bind(&address) {
+        Ok(receiver) => {
+            println!("[Main] Listening for spectra on {}", address);
+            Some(receiver)
+        }
+        Err(e) => {
+            println!("[Main] Failed to listen for spectra on {}: {}", address, e);
+            None
+        }
+    }
+}
+
+/// Opens the display and applies the orientation and color settings from the environment
+fn open_display() -> Result<Display, Box<dyn std::error::Error>> {
+    let mut fb = Framebuffer::with_backend(open_backend()?)?;
+    
+    if let Ok(degrees) = std::env::var("LINHT_ROTATION") {
+        match degrees.trim().parse().ok().and_then(Rotation::from_degrees) {
+            Some(rotation) => fb.set_rotation(rotation),
+            None => println!("[Main] Ignoring invalid LINHT_ROTATION: {}", degrees),
+        }
+    }
+    if let Ok(mirror) = std::env::var("LINHT_MIRROR") {
+        fb.set_mirroring(mirror.contains('x'), mirror.contains('y'));
+    }
+    if let Ok(name) = std::env::var("LINHT_DITHER") {
+        match Dithering::from_name(&name) {
+            Some(dithering) => fb.set_dithering(dithering),
+            None => println!("[Main] Ignoring invalid LINHT_DITHER: {}", name),
+        }
+    }
+    if let Ok(threshold) = std::env::var("LINHT_MONO") {
+        match threshold.trim().parse() {
+            Ok(threshold) => fb.set_monochrome(Some(threshold)),
+            Err(_) => println!("[Main] Ignoring invalid LINHT_MONO: {}", threshold),
+        }
+    }
+    fb.load_default_fonts(font_directory())?;
+    
+    Ok(Display::new(fb))
+}
+
+/// Switches the display to the theme called `name`, keeping the current one if it cannot be loaded
+fn apply_theme(display: &mut Display, name: &str) {
+    if display.theme().name == name {
+        return;
+    }
+
+    match Theme::named(name, Theme::default_dir()) {
+        Ok(theme) => {
+            println!("[Main] Using theme {}", theme.name);
+            display.set_theme(theme);
+        }
+        Err(e) => println!("[Main] Failed to load theme {}: {}", name, e),
+    }
+}
+
+/// Sets up idle dimming and blanking with the timeouts from the settings
+fn power_manager(settings: &Settings) -> PowerManager {
+    let backlight = Backlight::discover();
+    match &backlight {
+        Some(light) => println!("[Main] Using backlight {}", light.path().display()),
+        None => println!("[Main] No backlight found, idle timeout only blanks the display"),
+    }
+    
+    let mut power = PowerManager::new(backlight);
+    power.set_timeouts(settings.dim_after(), settings.blank_after());
+    power
+}
+
+/// Opens the display selected by `LINHT_DISPLAY`
+///
+/// - `fbdev[:<device>]` (default, `/dev/fb0`)
+/// - `spi:<spidev>` with `LINHT_SPI_DC`, optional `LINHT_SPI_RESET` and `LINHT_SPI_SPEED`
+/// - `socket[:<path>]` to develop on a PC with `tools/linht_viewer.py`
+///
+/// SPI panels and the socket use `LINHT_SCREEN_SIZE` (default `160x128`).
+fn open_backend() -> Result<Box<dyn Backend>, Box<dyn std::error::Error>> {
+    let spec = std::env::var("LINHT_DISPLAY").unwrap_or_else(|_| "fbdev".to_string());
+    let (kind, target) = match spec.split_once(':') {
+        Some((kind, target)) => (kind, Some(target)),
+        None => (spec.as_str(), None),
+    };
+    
+    let (width, height) = match std::env::var("LINHT_SCREEN_SIZE") {
+        Ok(size) => size
+            .split_once('x')
+            .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+            .ok_or_else(|| format!("Invalid LINHT_SCREEN_SIZE: {}", size))?,
+        Err(_) => (160, 128),
+    };
+    
+    match kind {
+        "fbdev" => Ok(Box::new(FbdevBackend::open(target.unwrap_or("/dev/fb0"))?)),
+        "spi" => {
+            let device = target.ok_or("LINHT_DISPLAY=spi needs a device, e.g. spi:/dev/spidev0.0")?;
+            let number = |name: &str| -> Result<Option<u32>, Box<dyn std::error::Error>> {
+                match std::env::var(name) {
+                    Ok(value) => Ok(Some(value.trim().parse().map_err(|_| format!("Invalid {}: {}", name, value))?)),
+                    Err(_) => Ok(None),
+                }
+            };
+            
+            let mut config = SpiLcdConfig::st7735(device, number("LINHT_SPI_DC")?.ok_or("LINHT_SPI_DC is not set")?);
+            config.reset_gpio = number("LINHT_SPI_RESET")?;
+            if let Some(speed) = number("LINHT_SPI_SPEED")? {
+                config.speed_hz = speed;
+            }
+            config.width = width;
+            config.height = height;
+            Ok(Box::new(SpiLcdBackend::open(config)?))
+        }
+        "socket" => Ok(Box::new(SocketBackend::bind(target.unwrap_or(DEFAULT_SOCKET_PATH), width, height)?)),
+        _ => Err(format!("Unknown LINHT_DISPLAY: {}", spec).into()),
+    }
+}
+
+/// Overrides an idle timeout setting with the seconds in the environment variable `name`
+fn timeout_from_env(name: &str, seconds: &mut u32) {
+    let Ok(value) = std::env::var(name) else {
+        return;
+    };
+    
+    match value.trim().parse() {
+        Ok(value) => *seconds = value,
+        Err(_) => println!("[Main] Ignoring invalid {}: {}", name, value),
+    }
+}
+
+/// Font directory from `LINHT_FONT_DIR`, else `fonts/` next to the binary, else `fonts/`
+fn font_directory() -> PathBuf {
+    if let Ok(dir) = std::env::var("LINHT_FONT_DIR") {
+        return PathBuf::from(dir);
+    }
+    
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_FONT_DIR)))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_FONT_DIR))
 }
 
 fn discover_flowgraphs(dir: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
@@ -196,63 +584,100 @@ fn discover_flowgraphs(dir: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Er
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("[Main] Starting...");
-    println!("[Main] Commands: + (next), - (previous), r (run), s (stop), q (quit)");
+    println!("[Main] Commands: + (next), - (previous), r (run), s (stop), b (back), m (menu), 0-9 (frequency), * (mode), # (step), p (screenshot), q (quit)");
     
-    let mut state = AppState::new("/dev/fb0")?;
+    let mut display = open_display()?;
+    let mut state = AppState::new()?;
+    let input_handler = InputHandler::new();
+    let mut power = power_manager(&state.settings);
+    let mut battery = Battery::discover();
+    match &battery {
+        Some(battery) => println!("[Main] Using battery {}", battery.path().display()),
+        None => println!("[Main] No battery found"),
+    }
+    let mut spectrum = spectrum_receiver();
+    let mut screens: ScreenStack<AppState> = ScreenStack::new(Box::new(HomeScreen::default()));
     
-    state.display.show_welcome();
+    apply_theme(&mut display, &state.settings.theme);
+    display.show_welcome();
     thread::sleep(Duration::from_secs(2));
+    screens.render(&mut display, &state);
     
     let mut last_display_update = Instant::now();
     
     loop {
-        if let Some(event) = state.input_handler.check_input() {
-            match event {
-                InputEvent::NextFlowgraph => {
-                    if !state.is_running() {
-                        state.next_flowgraph();
-                        state.update_display();
-                    }
-                }
-                InputEvent::PreviousFlowgraph => {
-                    if !state.is_running() {
-                        state.previous_flowgraph();
-                        state.update_display();
-                    }
-                }
-                InputEvent::Run => {
-                    if !state.is_running() {
-                        if let Err(e) = state.start_flowgraph() {
-                            println!("[Main] Failed to start flowgraph: {}", e);
+        if let Some(event) = input_handler.check_input() {
+            // The key press that turns the screen back on is not acted upon
+            let woke = power.wake(&mut display);
+            display.hide_popup();
+            
+            if !woke {
+                match event {
+                    InputEvent::Screenshot => {
+                        let timestamp = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or_default();
+                        let path = format!("screenshot-{}.png", timestamp);
+                        match display.save_screenshot(&path) {
+                            Ok(()) => println!("[Main] Screenshot saved to {}", path),
+                            Err(e) => println!("[Main] Failed to save screenshot: {}", e),
                         }
-                        state.update_display();
                     }
-                }
-                InputEvent::Stop => {
-                    if state.is_running() {
+                    InputEvent::Quit => {
+                        println!("[Main] Exiting...");
                         state.stop_flowgraph();
-                        state.update_display();
+                        state.save_pending_settings(true);
+                        break;
+                    }
+                    event => {
+                        screens.handle_input(&event, &mut state);
+                        power.set_timeouts(state.settings.dim_after(), state.settings.blank_after());
+                        apply_theme(&mut display, &state.settings.theme);
                     }
                 }
-                InputEvent::Quit => {
-                    println!("[Main] Exiting...");
-                    state.stop_flowgraph();
-                    break;
+            }
+            screens.render(&mut display, &state);
+        }
+        
+        if state.check_process_output() {
+            power.wake(&mut display);
+            screens.render(&mut display, &state);
+        }
+        
+        // Spectra sent while nothing runs are stale, but still have to be drained
+        if let Some(bins) = spectrum.as_mut().and_then(|receiver| receiver.receive()) {
+            if state.is_running() {
+                if state.spectrum_at.is_none() {
+                    display.clear_spectrum();
+                }
+                state.spectrum_at = Some(Instant::now());
+                display.push_spectrum(&bins, &state.settings.spectrum);
+                if !power.is_blanked() {
+                    screens.render(&mut display, &state);
                 }
             }
         }
         
-        state.check_process_output();
+        state.save_pending_settings(false);
+        power.update(&mut display);
+        if let Err(e) = display.poll() {
+            println!("[Main] Display error: {}", e);
+        }
         
-        if last_display_update.elapsed() > Duration::from_secs(1) {
-            state.update_display();
+        if !power.is_blanked() {
+            display.show_indicators(&state.status_indicators(battery.as_mut()));
+        }
+        
+        if !power.is_blanked() && last_display_update.elapsed() > Duration::from_secs(1) {
+            screens.render(&mut display, &state);
             last_display_update = Instant::now();
         }
         
         thread::sleep(Duration::from_millis(50));
     }
     
-    state.display.show_shutdown();
+    display.show_shutdown();
     
     Ok(())
-}
\ No newline at end of file
+}
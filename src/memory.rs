@@ -0,0 +1,588 @@
+//! Channel memories stored in the CSV format CHIRP exports and imports
+//!
+//! Columns are found by their header, so files exported by CHIRP for any radio
+//! load as long as they have `Name` and `Frequency`. LinHT specific values go
+//! into two extra columns, `Flowgraph` and `M17CAN`, which CHIRP ignores:
+//!
+//! ```text
+//! Location,Name,Frequency,Duplex,Offset,Tone,rToneFreq,cToneFreq,...,Mode,...,Power,Comment,...,Flowgraph,M17CAN
+//! 1,OE3XPA,438.825000,-,7.600000,,88.5,88.5,...,DV,...,High,,...,som_m17.py,0
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::radio::{band_of, RadioState};
+
+/// Memory file used when neither `LINHT_MEMORIES` nor `HOME` is set
+pub const DEFAULT_MEMORY_FILE: &str = "linht-memories.csv";
+
+/// Columns written by `MemoryBank::to_csv`: CHIRP's, then the LinHT ones
+pub const CSV_COLUMNS: [&str; 23] = [
+    "Location", "Name", "Frequency", "Duplex", "Offset", "Tone", "rToneFreq", "cToneFreq",
+    "DtcsCode", "DtcsPolarity", "RxDtcsCode", "CrossMode", "Mode", "TStep", "Skip", "Power",
+    "Comment", "URCALL", "RPT1CALL", "RPT2CALL", "DVCODE", "Flowgraph", "M17CAN",
+];
+
+/// Columns with a `Channel` field of their own; the rest go to `Channel::other`
+const MODELED_COLUMNS: [&str; 10] = [
+    "Location", "Name", "Frequency", "Duplex", "Offset", "Mode", "Power", "Comment", "Flowgraph", "M17CAN",
+];
+
+/// A stored channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel {
+    /// Position in the radio's memory, kept so the file round-trips through CHIRP
+    pub location: u32,
+    pub name: String,
+    /// Receive frequency in Hz
+    pub rx_frequency: u32,
+    /// Transmit frequency in Hz, the receive frequency while `tx_inhibit` is set
+    pub tx_frequency: u32,
+    /// Receive only, stored as `Duplex=off`
+    pub tx_inhibit: bool,
+    /// CHIRP mode, such as `FM`, `NFM` or `DV`
+    pub mode: String,
+    /// Flowgraph selected when the channel is recalled
+    pub flowgraph: Option<String>,
+    /// M17 channel access number, 0 to 15
+    pub m17_can: Option<u8>,
+    /// CTCSS tone in tenths of Hz, e.g. 885 for 88.5 Hz
+    pub ctcss: Option<u16>,
+    /// Power level as CHIRP writes it, e.g. `High` or `5.0W`
+    pub power: String,
+    pub comment: String,
+    /// CHIRP columns LinHT does not use, such as `TStep`, kept as read so they survive a round trip
+    pub other: BTreeMap<String, String>,
+}
+
+/// CHIRP mode of the sample flowgraph `file_name`, `None` if it cannot be told from the name
+pub fn flowgraph_mode(file_name: &str) -> Option<&'static str> {
+    let name = file_name.to_ascii_lowercase();
+    if name.contains("m17") {
+        Some("DV")
+    } else if name.contains("tetra") {
+        Some("DIG")
+    } else if name.contains("fm") {
+        Some("FM")
+    } else {
+        None
+    }
+}
+
+impl Channel {
+    /// A simplex FM channel on `hz`
+    pub fn new(location: u32, name: impl Into<String>, hz: u32) -> Self {
+        Channel {
+            location,
+            name: name.into(),
+            rx_frequency: hz,
+            tx_frequency: hz,
+            tx_inhibit: false,
+            mode: "FM".to_string(),
+            flowgraph: None,
+            m17_can: None,
+            ctcss: None,
+            power: "High".to_string(),
+            comment: String::new(),
+            other: BTreeMap::new(),
+        }
+    }
+
+    /// The name, or `Memory <location>` for the unnamed channels CHIRP exports often have
+    pub fn label(&self) -> String {
+        if self.name.trim().is_empty() {
+            format!("Memory {}", self.location)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// The radio tuned to this channel
+    pub fn radio_state(&self) -> RadioState {
+        RadioState {
+            rx_frequency: self.rx_frequency,
+            tx_frequency: self.tx_frequency,
+            tx_inhibit: self.tx_inhibit,
+            channel: Some(self.label()),
+            memory: Some(self.location),
+        }
+    }
+
+    /// Environment variables for a flowgraph started on this channel, besides those of `RadioState`
+    ///
+    /// `LINHT_MODE` and `LINHT_POWER` are always set, `LINHT_M17_CAN` and
+    /// `LINHT_CTCSS` (Hz) only if the channel has them.
+    pub fn environment(&self) -> Vec<(&'static str, String)> {
+        let mut variables = vec![("LINHT_MODE", self.mode.clone()), ("LINHT_POWER", self.power.clone())];
+        if let Some(can) = self.m17_can {
+            variables.push(("LINHT_M17_CAN", can.to_string()));
+        }
+        if let Some(tone) = self.ctcss {
+            variables.push(("LINHT_CTCSS", format_tone(tone)));
+        }
+        variables
+    }
+
+    /// Reads a CSV row, given the position of each column in `CSV_COLUMNS`
+    fn from_row(fields: &[String], columns: &[Option<usize>]) -> Result<Self, String> {
+        let field = |name: &str| -> &str {
+            CSV_COLUMNS
+                .iter()
+                .position(|column| *column == name)
+                .and_then(|index| columns[index])
+                .and_then(|index| fields.get(index))
+                .map_or("", |value| value.trim())
+        };
+
+        let rx_frequency = parse_mhz(field("Frequency")).ok_or("invalid frequency")?;
+        let offset = field("Offset");
+        let tx_frequency = match field("Duplex") {
+            "" | "off" => rx_frequency,
+            "+" => rx_frequency.saturating_add(parse_mhz(offset).ok_or("invalid offset")?),
+            "-" => rx_frequency.saturating_sub(parse_mhz(offset).ok_or("invalid offset")?),
+            "split" => parse_mhz(offset).ok_or("invalid split frequency")?,
+            duplex => return Err(format!("unknown duplex {}", duplex)),
+        };
+        let ctcss = match field("Tone") {
+            "Tone" => Some(parse_tone(field("rToneFreq")).ok_or("invalid tone")?),
+            "TSQL" => Some(parse_tone(field("cToneFreq")).ok_or("invalid tone")?),
+            _ => None,
+        };
+        let m17_can = match field("M17CAN") {
+            "" => None,
+            can => Some(can.parse().ok().filter(|can| *can < 16).ok_or("invalid M17 CAN")?),
+        };
+
+        let other = CSV_COLUMNS
+            .iter()
+            .filter(|column| !MODELED_COLUMNS.contains(column))
+            .map(|column| (column.to_string(), field(column).to_string()))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+
+        Ok(Channel {
+            location: field("Location").parse().unwrap_or(0),
+            name: field("Name").to_string(),
+            rx_frequency,
+            tx_frequency,
+            tx_inhibit: field("Duplex") == "off",
+            mode: field("Mode").to_string(),
+            flowgraph: Some(field("Flowgraph").to_string()).filter(|name| !name.is_empty()),
+            m17_can,
+            ctcss,
+            power: field("Power").to_string(),
+            comment: field("Comment").to_string(),
+            other,
+        })
+    }
+
+    /// The channel as a CSV row in the order of `CSV_COLUMNS`
+    fn to_row(&self) -> Vec<String> {
+        // Cross-band channels need the transmit frequency itself
+        let same_band = band_of(self.rx_frequency).is_some() && band_of(self.rx_frequency) == band_of(self.tx_frequency);
+        let (duplex, offset) = match self.tx_frequency.cmp(&self.rx_frequency) {
+            _ if self.tx_inhibit => ("off", 0),
+            Ordering::Equal => ("", 0),
+            _ if !same_band => ("split", self.tx_frequency),
+            Ordering::Greater => ("+", self.tx_frequency - self.rx_frequency),
+            Ordering::Less => ("-", self.rx_frequency - self.tx_frequency),
+        };
+        let other = |column: &str| self.other.get(column).cloned();
+        // Tone squelch stays tone squelch, anything else becomes a plain tone
+        let tone_mode = match self.ctcss {
+            Some(_) => other("Tone").filter(|tone| tone == "TSQL").unwrap_or_else(|| "Tone".to_string()),
+            None => other("Tone").unwrap_or_default(),
+        };
+        let tone_column = if tone_mode == "TSQL" { "cToneFreq" } else { "rToneFreq" };
+
+        CSV_COLUMNS
+            .iter()
+            .map(|&column| match column {
+                "Location" => self.location.to_string(),
+                "Name" => self.name.clone(),
+                "Frequency" => format_mhz(self.rx_frequency),
+                "Duplex" => duplex.to_string(),
+                "Offset" => format_mhz(offset),
+                "Tone" => tone_mode.clone(),
+                "rToneFreq" | "cToneFreq" => match self.ctcss {
+                    Some(tone) if column == tone_column => format_tone(tone),
+                    _ => other(column).unwrap_or_else(|| "88.5".to_string()),
+                },
+                "Mode" => self.mode.clone(),
+                "Power" => self.power.clone(),
+                "Comment" => self.comment.clone(),
+                "Flowgraph" => self.flowgraph.clone().unwrap_or_default(),
+                "M17CAN" => self.m17_can.map_or(String::new(), |can| can.to_string()),
+                column => other(column).unwrap_or_else(|| chirp_default(column).to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Channels in the order of their location
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MemoryBank {
+    pub channels: Vec<Channel>,
+}
+
+impl MemoryBank {
+    /// `LINHT_MEMORIES`, else `~/.config/linht-memories.csv`, else `linht-memories.csv`
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var("LINHT_MEMORIES") {
+            return PathBuf::from(path);
+        }
+
+        match std::env::var("HOME") {
+            Ok(home) => Path::new(&home).join(".config").join(DEFAULT_MEMORY_FILE),
+            Err(_) => PathBuf::from(DEFAULT_MEMORY_FILE),
+        }
+    }
+
+    /// Reads channels from `path`, starting out empty if the file does not exist yet
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses a CHIRP CSV file; rows that cannot be read are skipped with a warning
+    ///
+    /// Fails only if the header lacks the `Name` or `Frequency` column.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut records = split_csv_records(contents).into_iter();
+        let header = records.next().map(|(_, fields)| fields).unwrap_or_default();
+        let columns: Vec<Option<usize>> = CSV_COLUMNS
+            .iter()
+            .map(|column| header.iter().position(|name| name.trim() == *column))
+            .collect();
+        for required in ["Name", "Frequency"] {
+            if !header.iter().any(|name| name.trim() == required) {
+                return Err(format!("no {} column", required));
+            }
+        }
+
+        let mut channels = Vec::new();
+        for (number, fields) in records {
+            match Channel::from_row(&fields, &columns) {
+                Ok(channel) => channels.push(channel),
+                Err(reason) => println!("[Memory] Ignoring line {}: {}", number, reason),
+            }
+        }
+        channels.sort_by_key(|channel| channel.location);
+
+        // Channels are told apart by location, so missing and repeated ones get a free one
+        let mut next = channels.last().map_or(1, |last| last.location + 1);
+        let mut used = Vec::with_capacity(channels.len());
+        for channel in &mut channels {
+            if channel.location == 0 || used.contains(&channel.location) {
+                channel.location = next;
+                next += 1;
+            }
+            used.push(channel.location);
+        }
+        channels.sort_by_key(|channel| channel.location);
+
+        Ok(MemoryBank { channels })
+    }
+
+    /// The channels as CSV that CHIRP imports
+    pub fn to_csv(&self) -> String {
+        let mut csv = CSV_COLUMNS.join(",");
+        csv.push('\n');
+        for channel in &self.channels {
+            let fields: Vec<String> = channel.to_row().iter().map(|field| quote_csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Writes the channels to `path`, creating its directory if needed
+    ///
+    /// Like the settings, the file is replaced in one step.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_csv())?;
+        fs::rename(&temporary, path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Index of the channel called `name`
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.channels.iter().position(|channel| channel.name == name)
+    }
+
+    /// Index of the channel at `location`
+    pub fn find(&self, location: u32) -> Option<usize> {
+        self.channels.iter().position(|channel| channel.location == location)
+    }
+
+    /// Adds a channel after the last location
+    pub fn push(&mut self, mut channel: Channel) {
+        channel.location = self.channels.last().map_or(1, |last| last.location + 1);
+        self.channels.push(channel);
+    }
+}
+
+/// What CHIRP writes into a column a channel has no value for
+fn chirp_default(column: &str) -> &'static str {
+    match column {
+        "DtcsCode" | "RxDtcsCode" => "023",
+        "DtcsPolarity" => "NN",
+        "CrossMode" => "Tone->Tone",
+        "TStep" => "5.00",
+        _ => "",
+    }
+}
+
+/// Parses MHz with up to six decimals, as CHIRP writes frequencies and offsets
+fn parse_mhz(value: &str) -> Option<u32> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 6 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let mhz: u32 = whole.parse().ok()?;
+    let hz: u32 = format!("{:0<6}", fraction).parse().ok()?;
+    mhz.checked_mul(1_000_000)?.checked_add(hz)
+}
+
+fn format_mhz(hz: u32) -> String {
+    format!("{}.{:06}", hz / 1_000_000, hz % 1_000_000)
+}
+
+/// Parses a tone in Hz with one decimal into tenths of Hz
+fn parse_tone(value: &str) -> Option<u16> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, "0"));
+    let tenths: u16 = fraction.get(..1)?.parse().ok()?;
+    whole.parse::<u16>().ok()?.checked_mul(10)?.checked_add(tenths)
+}
+
+fn format_tone(tenths: u16) -> String {
+    format!("{}.{}", tenths / 10, tenths % 10)
+}
+
+/// Splits CSV into the fields of each record and the line it starts on, skipping blank lines
+///
+/// Double quoted fields may hold commas, quotes and line breaks, so records are
+/// split here rather than line by line.
+fn split_csv_records(contents: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = contents.chars().peekable();
+    let blank = |fields: &[String]| fields.len() == 1 && fields[0].trim().is_empty();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                if !blank(&fields) {
+                    records.push((start, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                start = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    fields.push(field);
+    if !blank(&fields) {
+        records.push((start, fields));
+    }
+    records
+}
+
+fn quote_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "Location,Name,Frequency,Duplex,Offset,Tone,rToneFreq,cToneFreq,DtcsCode,DtcsPolarity,\
+                          RxDtcsCode,CrossMode,Mode,TStep,Skip,Power,Comment,URCALL,RPT1CALL,RPT2CALL,DVCODE";
+    const REPEATER: &str = "1,OE3XPA,438.825000,-,7.600000,TSQL,88.5,123.0,023,NN,023,Tone->Tone,FM,12.50,,High,\"Repeater, Vienna\",,,,";
+    const SIMPLEX: &str = "2,S20,145.500000,,0.000000,,88.5,88.5,025,NN,023,Tone->Tone,FM,12.50,S,5.0W,,,,,";
+
+    fn chirp_export(rows: &[&str]) -> String {
+        let mut csv = format!("{}\r\n", HEADER);
+        for row in rows {
+            csv.push_str(row);
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    #[test]
+    fn reads_chirp_exports() {
+        let bank = MemoryBank::parse(&chirp_export(&[SIMPLEX, REPEATER])).unwrap();
+        assert_eq!(bank.len(), 2);
+
+        let repeater = &bank.channels[0];
+        assert_eq!((repeater.location, repeater.name.as_str()), (1, "OE3XPA"));
+        assert_eq!((repeater.rx_frequency, repeater.tx_frequency), (438_825_000, 431_225_000));
+        assert_eq!(repeater.ctcss, Some(1230));
+        assert_eq!(repeater.comment, "Repeater, Vienna");
+        assert_eq!(repeater.other.get("TStep").map(String::as_str), Some("12.50"));
+
+        let simplex = &bank.channels[1];
+        assert_eq!(simplex.tx_frequency, simplex.rx_frequency);
+        assert_eq!((simplex.ctcss, simplex.power.as_str()), (None, "5.0W"));
+        assert_eq!(simplex.other.get("Skip").map(String::as_str), Some("S"));
+    }
+
+    #[test]
+    fn writes_chirp_columns_as_read() {
+        let csv = MemoryBank::parse(&chirp_export(&[REPEATER, SIMPLEX])).unwrap().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], CSV_COLUMNS.join(","));
+        assert_eq!(lines[1], format!("{},,", REPEATER));
+        assert_eq!(lines[2], format!("{},,", SIMPLEX));
+        assert_eq!(MemoryBank::parse(&csv).unwrap(), MemoryBank::parse(&chirp_export(&[REPEATER, SIMPLEX])).unwrap());
+    }
+
+    #[test]
+    fn channels_round_trip() {
+        let mut split = Channel::new(0, "Cross", 145_800_000);
+        split.tx_frequency = 437_800_000;
+        split.flowgraph = Some("som_m17.py".to_string());
+        split.m17_can = Some(7);
+        split.mode = "DV".to_string();
+        let mut receive_only = Channel::new(0, "SAT", 145_825_000);
+        receive_only.tx_inhibit = true;
+        let mut toned = Channel::new(0, "Tone", 438_500_000);
+        toned.tx_frequency = 439_100_000;
+        toned.ctcss = Some(885);
+
+        let mut bank = MemoryBank::default();
+        for channel in [split, receive_only, toned] {
+            bank.push(channel);
+        }
+        let csv = bank.to_csv();
+        assert!(csv.contains(",Cross,145.800000,split,437.800000,"));
+        assert!(csv.contains(",SAT,145.825000,off,0.000000,"));
+        assert!(csv.contains(",Tone,438.500000,+,0.600000,Tone,88.5,"));
+
+        // CHIRP's defaults fill the columns the channels left empty
+        let mut read = MemoryBank::parse(&csv).unwrap();
+        assert_eq!(read.to_csv(), csv);
+        for channel in &mut read.channels {
+            assert_eq!(channel.other.get("TStep").map(String::as_str), Some("5.00"));
+            channel.other.clear();
+        }
+        assert_eq!(read, bank);
+    }
+
+    #[test]
+    fn receive_only_channels_do_not_transmit() {
+        let row = "3,SAT,145.825000,off,0.000000,,88.5,88.5,023,NN,023,Tone->Tone,FM,5.00,,High,,,,,";
+        let channel = MemoryBank::parse(&chirp_export(&[row])).unwrap().channels.remove(0);
+        assert!(channel.tx_inhibit);
+
+        let radio = channel.radio_state();
+        assert!(radio.tx_inhibit);
+        assert!(radio.environment().iter().all(|(name, _)| *name != "LINHT_TX_FREQ"));
+    }
+
+    #[test]
+    fn modes_follow_the_flowgraph() {
+        assert_eq!(flowgraph_mode("som_m17_ptt.py"), Some("DV"));
+        assert_eq!(flowgraph_mode("som_tetra.py"), Some("DIG"));
+        assert_eq!(flowgraph_mode("som_fm.py"), Some("FM"));
+        assert_eq!(flowgraph_mode("beacon.py"), None);
+    }
+
+    #[test]
+    fn quoted_fields_may_span_lines() {
+        let row = "1,Club,438.500000,,0.000000,,88.5,88.5,023,NN,023,Tone->Tone,FM,5.00,,High,\"Nets on Monday,\r\n\"\"Tuesday\"\" too\",,,,";
+        let bank = MemoryBank::parse(&chirp_export(&[row, SIMPLEX])).unwrap();
+        assert_eq!(bank.len(), 2);
+        assert_eq!(bank.channels[0].comment, "Nets on Monday,\r\n\"Tuesday\" too");
+
+        assert_eq!(MemoryBank::parse(&bank.to_csv()).unwrap(), bank);
+    }
+
+    #[test]
+    fn locations_are_made_unique() {
+        let rows = [
+            "5,First,145.500000,,,,,,,,,,FM,,,,,,,,",
+            "5,Second,145.525000,,,,,,,,,,FM,,,,,,,,",
+            ",,145.550000,,,,,,,,,,FM,,,,,,,,",
+        ];
+        let bank = MemoryBank::parse(&chirp_export(&rows)).unwrap();
+        let locations: Vec<u32> = bank.channels.iter().map(|channel| channel.location).collect();
+        assert_eq!(locations, [5, 6, 7]);
+
+        assert_eq!(bank.channels[2].name, "Second");
+        assert_eq!(bank.find(7), Some(2));
+        assert_eq!(bank.channels[1].label(), "Memory 6");
+        assert_eq!(bank.channels[1].radio_state().memory, Some(6));
+    }
+
+    #[test]
+    fn skips_rows_it_cannot_read() {
+        let rows = [
+            "1,Bad,438.5x,,,,,,,,,,FM,,,,,,,,",
+            "2,Odd,145.500000,sideways,,,,,,,,,FM,,,,,,,,",
+            SIMPLEX,
+        ];
+        let bank = MemoryBank::parse(&chirp_export(&rows)).unwrap();
+        assert_eq!(bank.channels.iter().map(|channel| channel.name.as_str()).collect::<Vec<_>>(), ["S20"]);
+
+        assert!(MemoryBank::parse("Location,Frequency\n1,145.500000\n").is_err());
+        assert!(MemoryBank::parse("").is_err());
+    }
+
+    #[test]
+    fn saves_and_loads_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join(DEFAULT_MEMORY_FILE);
+        assert!(MemoryBank::load(&path).unwrap().is_empty());
+
+        let bank = MemoryBank::parse(&chirp_export(&[REPEATER, SIMPLEX])).unwrap();
+        bank.save(&path).unwrap();
+        assert_eq!(MemoryBank::load(&path).unwrap(), bank);
+        assert!(!path.with_extension("tmp").exists());
+    }
+}
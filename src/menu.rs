@@ -0,0 +1,677 @@
+//! Menus and value editors navigated with the up/down/enter/escape keys
+//!
+//! A `MenuScreen` lists entries that either open another screen, such as a
+//! submenu, or show a value that is changed in one of the editors. Editors
+//! hand the confirmed value to a closure, which stores it in the context.
+//!
+//! Up and down arrive as `NextFlowgraph` and `PreviousFlowgraph`, enter as
+//! `Run` and escape as `Stop`. `FrequencyEntry` also takes keypad digits.
+
+use crate::display::Display;
+use crate::framebuffer::{Color, FontId, Framebuffer, Point, Rect, TextStyle};
+use crate::input::InputEvent;
+use crate::navigation::{Screen, Transition};
+use crate::radio::{band_of, BANDS};
+use crate::theme::Theme;
+use crate::widgets::{Container, Insets, Label, List, Size, Ui, Widget, WidgetId};
+
+type Open<C> = Box<dyn Fn(&C) -> Box<dyn Screen<C>>>;
+type ValueText<C> = Box<dyn Fn(&C) -> String>;
+/// Stores a confirmed value in the context
+type Apply<C, T> = Box<dyn Fn(&mut C, T)>;
+/// Stores a confirmed value in the context, or refuses it with a reason
+type TryApply<C, T> = Box<dyn Fn(&mut C, T) -> Result<(), String>>;
+
+/// Widgets of a screen, kept until the theme changes
+struct Built {
+    ui: Ui,
+    /// The widget updated on every render
+    widget: WidgetId,
+    theme: Theme,
+}
+
+/// Returns the widgets of a screen, building them if missing or made for another theme
+fn widgets<'a>(
+    cache: &'a mut Option<Built>,
+    display: &Display,
+    build: impl FnOnce(&Theme) -> (Ui, WidgetId),
+) -> (&'a mut Ui, WidgetId) {
+    if !matches!(cache, Some(built) if built.theme == *display.theme()) {
+        *cache = None;
+    }
+
+    let built = cache.get_or_insert_with(|| {
+        let theme = display.theme().clone();
+        let (mut ui, widget) = build(&theme);
+        ui.set_background(theme.background);
+        Built { ui, widget, theme }
+    });
+    (&mut built.ui, built.widget)
+}
+
+/// A list filling the content area, used by menus and choices
+fn list_ui(display: &Display, theme: &Theme, items: Vec<String>) -> (Ui, WidgetId) {
+    let mut ui = Ui::new(Container::vertical().padding(Insets::symmetric(0, display.text_size(2.0) as u16)));
+    let root = ui.root();
+    let style = TextStyle::new(display.text_size(11.0), theme.foreground, FontId::Regular);
+    let list = ui.add(root, List::new(items, style, theme.selection, theme.selection_text));
+    ui.set_flex(list, 1);
+    (ui, list)
+}
+
+/// A row of a `MenuScreen`
+pub struct MenuEntry<C> {
+    label: String,
+    value: Option<ValueText<C>>,
+    open: Open<C>,
+}
+
+impl<C> MenuEntry<C> {
+    /// An entry opening a submenu or any other screen
+    pub fn screen(label: impl Into<String>, open: impl Fn(&C) -> Box<dyn Screen<C>> + 'static) -> Self {
+        MenuEntry {
+            label: label.into(),
+            value: None,
+            open: Box::new(open),
+        }
+    }
+
+    /// An entry showing the current value of a setting, changed on the screen `edit` opens
+    pub fn setting(
+        label: impl Into<String>,
+        value: impl Fn(&C) -> String + 'static,
+        edit: impl Fn(&C) -> Box<dyn Screen<C>> + 'static,
+    ) -> Self {
+        MenuEntry {
+            label: label.into(),
+            value: Some(Box::new(value)),
+            open: Box::new(edit),
+        }
+    }
+}
+
+/// A titled list of entries
+pub struct MenuScreen<C> {
+    title: String,
+    entries: Vec<MenuEntry<C>>,
+    selected: usize,
+    /// Built on the first render, when the screen size is known
+    ui: Option<Built>,
+}
+
+impl<C> MenuScreen<C> {
+    pub fn new(title: impl Into<String>, entries: Vec<MenuEntry<C>>) -> Self {
+        MenuScreen {
+            title: title.into(),
+            entries,
+            selected: 0,
+            ui: None,
+        }
+    }
+}
+
+impl<C: 'static> Screen<C> for MenuScreen<C> {
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> Transition<C> {
+        let count = self.entries.len();
+        match event {
+            InputEvent::NextFlowgraph if count > 0 => self.selected = (self.selected + count - 1) % count,
+            InputEvent::PreviousFlowgraph if count > 0 => self.selected = (self.selected + 1) % count,
+            InputEvent::Run => {
+                return match self.entries.get(self.selected) {
+                    Some(entry) => Transition::Push((entry.open)(context)),
+                    None => Transition::Stay,
+                };
+            }
+            InputEvent::Stop => return Transition::Pop,
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn render(&mut self, display: &mut Display, context: &C) {
+        let (ui, list) = widgets(&mut self.ui, display, |theme| list_ui(display, theme, Vec::new()));
+
+        let items = self.entries.iter().map(|entry| entry.label.clone()).collect();
+        let values = self
+            .entries
+            .iter()
+            .map(|entry| entry.value.as_ref().map_or_else(|| ">".to_string(), |value| value(context)))
+            .collect();
+        ui.update::<List>(list, |list| {
+            list.set_items(items);
+            list.set_values(values);
+            list.select(self.selected);
+        });
+
+        display.show_status(&self.title);
+        display.show_ui(ui);
+    }
+}
+
+/// Builds the layout shared by the editors: the edited value above a key hint
+///
+/// Returns the tree and the container to put the value widget into.
+fn editor_ui(display: &Display, theme: &Theme, hint: &str) -> (Ui, WidgetId) {
+    let mut ui = Ui::new(Container::vertical().padding(Insets::uniform(display.text_size(6.0) as u16)));
+    let root = ui.root();
+    let value = ui.add(root, Container::vertical());
+    ui.set_flex(value, 1);
+
+    let style = TextStyle::new(display.text_size(9.0), theme.muted, FontId::Regular).wrap(false);
+    ui.add(root, Label::new(hint, style));
+    (ui, value)
+}
+
+/// Changes a number in fixed steps between a minimum and a maximum
+pub struct NumberEditor<C> {
+    title: String,
+    value: i64,
+    min: i64,
+    max: i64,
+    step: i64,
+    format: Box<dyn Fn(i64) -> String>,
+    apply: Apply<C, i64>,
+    ui: Option<Built>,
+}
+
+impl<C> NumberEditor<C> {
+    pub fn new(
+        title: impl Into<String>,
+        value: i64,
+        min: i64,
+        max: i64,
+        step: i64,
+        apply: impl Fn(&mut C, i64) + 'static,
+    ) -> Self {
+        NumberEditor {
+            title: title.into(),
+            value: value.clamp(min, max),
+            min,
+            max,
+            step: step.max(1),
+            format: Box::new(|value| value.to_string()),
+            apply: Box::new(apply),
+            ui: None,
+        }
+    }
+
+    /// Sets how the value is shown, e.g. with a unit
+    pub fn format(mut self, format: impl Fn(i64) -> String + 'static) -> Self {
+        self.format = Box::new(format);
+        self
+    }
+}
+
+impl<C: 'static> Screen<C> for NumberEditor<C> {
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> Transition<C> {
+        match event {
+            InputEvent::NextFlowgraph => self.value = (self.value + self.step).min(self.max),
+            InputEvent::PreviousFlowgraph => self.value = (self.value - self.step).max(self.min),
+            InputEvent::Run => {
+                (self.apply)(context, self.value);
+                return Transition::Pop;
+            }
+            InputEvent::Stop => return Transition::Pop,
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn render(&mut self, display: &mut Display, _context: &C) {
+        let (ui, value) = widgets(&mut self.ui, display, |theme| {
+            let (mut ui, area) = editor_ui(display, theme, "+/- change, enter: save");
+            let style = TextStyle::new(display.text_size(16.0), theme.accent, FontId::Mono).wrap(false);
+            let value = ui.add(area, Label::new("", style));
+            ui.set_flex(value, 1);
+            (ui, value)
+        });
+
+        let text = (self.format)(self.value);
+        ui.update::<Label>(value, |label| label.set_text(text));
+
+        display.show_status(&self.title);
+        display.show_ui(ui);
+    }
+}
+
+/// Picks one of several options from a list
+pub struct ChoiceEditor<C> {
+    title: String,
+    options: Vec<String>,
+    selected: usize,
+    apply: Apply<C, usize>,
+    ui: Option<Built>,
+}
+
+impl<C> ChoiceEditor<C> {
+    /// `apply` receives the index of the chosen option
+    pub fn new(
+        title: impl Into<String>,
+        options: Vec<String>,
+        selected: usize,
+        apply: impl Fn(&mut C, usize) + 'static,
+    ) -> Self {
+        ChoiceEditor {
+            title: title.into(),
+            selected: selected.min(options.len().saturating_sub(1)),
+            options,
+            apply: Box::new(apply),
+            ui: None,
+        }
+    }
+}
+
+impl<C: 'static> Screen<C> for ChoiceEditor<C> {
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> Transition<C> {
+        let count = self.options.len();
+        match event {
+            InputEvent::NextFlowgraph if count > 0 => self.selected = (self.selected + count - 1) % count,
+            InputEvent::PreviousFlowgraph if count > 0 => self.selected = (self.selected + 1) % count,
+            InputEvent::Run => {
+                if count > 0 {
+                    (self.apply)(context, self.selected);
+                }
+                return Transition::Pop;
+            }
+            InputEvent::Stop => return Transition::Pop,
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn render(&mut self, display: &mut Display, _context: &C) {
+        let options = &self.options;
+        let (ui, list) = widgets(&mut self.ui, display, |theme| list_ui(display, theme, options.clone()));
+
+        ui.update::<List>(list, |list| list.select(self.selected));
+
+        display.show_status(&self.title);
+        display.show_ui(ui);
+    }
+}
+
+/// Characters offered by `TextEditor::callsign`; the leading space ends the text
+pub const CALLSIGN_CHARACTERS: &str = " ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789/-";
+
+/// Edits text one character at a time with up and down
+///
+/// Enter moves to the next character and saves when pressed on a space or the
+/// last position; back moves to the previous character.
+pub struct TextEditor<C> {
+    title: String,
+    text: Vec<char>,
+    cursor: usize,
+    max_len: usize,
+    characters: Vec<char>,
+    apply: Apply<C, String>,
+    ui: Option<Built>,
+}
+
+impl<C> TextEditor<C> {
+    /// `characters` lists the choices for each position, starting with a space
+    pub fn new(
+        title: impl Into<String>,
+        text: &str,
+        max_len: usize,
+        characters: &str,
+        apply: impl Fn(&mut C, String) + 'static,
+    ) -> Self {
+        let max_len = max_len.max(1);
+        TextEditor {
+            title: title.into(),
+            text: text.chars().take(max_len).collect(),
+            cursor: 0,
+            max_len,
+            characters: characters.chars().collect(),
+            apply: Box::new(apply),
+            ui: None,
+        }
+    }
+
+    /// Editor for upper case callsigns with `/` and `-` suffixes
+    pub fn callsign(
+        title: impl Into<String>,
+        text: &str,
+        max_len: usize,
+        apply: impl Fn(&mut C, String) + 'static,
+    ) -> Self {
+        Self::new(title, &text.to_ascii_uppercase(), max_len, CALLSIGN_CHARACTERS, apply)
+    }
+
+    fn current(&self) -> char {
+        self.text.get(self.cursor).copied().unwrap_or(' ')
+    }
+
+    /// Steps the character under the cursor through `characters`
+    fn cycle(&mut self, forward: bool) {
+        let count = self.characters.len();
+        if count == 0 {
+            return;
+        }
+
+        let index = self.characters.iter().position(|&c| c == self.current()).unwrap_or(0);
+        let next = if forward { (index + 1) % count } else { (index + count - 1) % count };
+        if self.cursor >= self.text.len() {
+            self.text.resize(self.cursor + 1, ' ');
+        }
+        self.text[self.cursor] = self.characters[next];
+    }
+
+    fn result(&self) -> String {
+        let end = self.text.iter().position(|&c| c == ' ').unwrap_or(self.text.len());
+        self.text[..end].iter().collect()
+    }
+}
+
+impl<C: 'static> Screen<C> for TextEditor<C> {
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> Transition<C> {
+        match event {
+            InputEvent::NextFlowgraph => self.cycle(true),
+            InputEvent::PreviousFlowgraph => self.cycle(false),
+            InputEvent::Run => {
+                if self.current() == ' ' || self.cursor + 1 >= self.max_len {
+                    (self.apply)(context, self.result());
+                    return Transition::Pop;
+                }
+                self.cursor += 1;
+            }
+            InputEvent::Back if self.cursor > 0 => self.cursor -= 1,
+            InputEvent::Stop => return Transition::Pop,
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn render(&mut self, display: &mut Display, _context: &C) {
+        let (ui, cells) = widgets(&mut self.ui, display, |theme| {
+            let (mut ui, area) = editor_ui(display, theme, "+/- char, enter: next, back: prev");
+            let style = TextStyle::new(display.text_size(16.0), theme.accent, FontId::Mono);
+            let cells = ui.add(area, CharacterCells {
+                text: Vec::new(),
+                cursor: 0,
+                style,
+                cursor_color: theme.selection,
+                cursor_text: theme.selection_text,
+                underline: theme.muted,
+            });
+            ui.set_flex(cells, 1);
+            (ui, cells)
+        });
+
+        let mut text = self.text.clone();
+        text.resize(text.len().max(self.cursor + 1), ' ');
+        ui.update::<CharacterCells>(cells, |cells| {
+            cells.text = text;
+            cells.cursor = self.cursor;
+        });
+
+        display.show_status(&self.title);
+        display.show_ui(ui);
+    }
+}
+
+/// Number of digits in a `FrequencyEntry`: three for MHz, five decimals for 10 Hz resolution
+const FREQUENCY_DIGITS: usize = 8;
+const FREQUENCY_DECIMALS: usize = 5;
+
+/// Enters a frequency digit by digit, accepting only frequencies inside `BANDS`
+///
+/// Starts from the current frequency. Digit keys overwrite the digit under the
+/// cursor and move on, up and down change it and back moves to the previous one.
+/// Enter saves the frequency as shown, unless `apply` refuses it with a reason
+/// that is then shown below the digits.
+pub struct FrequencyEntry<C> {
+    title: String,
+    digits: [u8; FREQUENCY_DIGITS],
+    cursor: usize,
+    /// Why the last frequency was refused
+    error: Option<String>,
+    apply: TryApply<C, u32>,
+    ui: Option<Built>,
+    error_label: Option<WidgetId>,
+}
+
+impl<C> FrequencyEntry<C> {
+    pub fn new(title: impl Into<String>, hz: u32, apply: impl Fn(&mut C, u32) -> Result<(), String> + 'static) -> Self {
+        let mut digits = [0; FREQUENCY_DIGITS];
+        let mut rest = hz / 10;
+        for digit in digits.iter_mut().rev() {
+            *digit = (rest % 10) as u8;
+            rest /= 10;
+        }
+
+        FrequencyEntry {
+            title: title.into(),
+            digits,
+            cursor: 0,
+            error: None,
+            apply: Box::new(apply),
+            ui: None,
+            error_label: None,
+        }
+    }
+
+    /// Enters `digit` right away, for screens opened by a digit key
+    pub fn typed(mut self, digit: u8) -> Self {
+        self.enter(digit);
+        self
+    }
+
+    fn enter(&mut self, digit: u8) {
+        self.digits[self.cursor] = digit % 10;
+        self.cursor = (self.cursor + 1).min(FREQUENCY_DIGITS - 1);
+    }
+
+    fn frequency(&self) -> u32 {
+        self.digits.iter().fold(0, |hz, &digit| hz * 10 + digit as u32) * 10
+    }
+
+    /// The digits with a decimal point, as shown in the cells
+    fn text(&self) -> Vec<char> {
+        let mut text: Vec<char> = self.digits.iter().map(|&digit| (b'0' + digit) as char).collect();
+        text.insert(FREQUENCY_DIGITS - FREQUENCY_DECIMALS, '.');
+        text
+    }
+}
+
+impl<C: 'static> Screen<C> for FrequencyEntry<C> {
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> Transition<C> {
+        match event {
+            InputEvent::Digit(digit) => self.enter(*digit),
+            InputEvent::NextFlowgraph => self.digits[self.cursor] = (self.digits[self.cursor] + 1) % 10,
+            InputEvent::PreviousFlowgraph => self.digits[self.cursor] = (self.digits[self.cursor] + 9) % 10,
+            InputEvent::Run => {
+                let hz = self.frequency();
+                if band_of(hz).is_none() {
+                    let bands: Vec<String> = BANDS
+                        .iter()
+                        .map(|band| format!("{}-{}", band.low / 1_000_000, band.high / 1_000_000))
+                        .collect();
+                    self.error = Some(format!("Out of band: {}", bands.join(", ")));
+                    return Transition::Stay;
+                }
+                match (self.apply)(context, hz) {
+                    Ok(()) => return Transition::Pop,
+                    Err(reason) => {
+                        self.error = Some(reason);
+                        return Transition::Stay;
+                    }
+                }
+            }
+            InputEvent::Back if self.cursor > 0 => self.cursor -= 1,
+            InputEvent::Stop => return Transition::Pop,
+            _ => return Transition::Unhandled,
+        }
+        self.error = None;
+        Transition::Stay
+    }
+
+    fn render(&mut self, display: &mut Display, _context: &C) {
+        let text = self.text();
+        // Skip the decimal point, which is not a digit of its own
+        let cursor = self.cursor + usize::from(self.cursor >= FREQUENCY_DIGITS - FREQUENCY_DECIMALS);
+        let message = self.error.clone().unwrap_or_default();
+
+        let error_label = &mut self.error_label;
+        let (ui, cells) = widgets(&mut self.ui, display, |theme| {
+            let (mut ui, area) = editor_ui(display, theme, "0-9 or +/- digit, enter: save");
+            let style = TextStyle::new(display.text_size(16.0), theme.accent, FontId::Mono);
+            let cells = ui.add(area, CharacterCells {
+                text: Vec::new(),
+                cursor: 0,
+                style,
+                cursor_color: theme.selection,
+                cursor_text: theme.selection_text,
+                underline: theme.muted,
+            });
+            ui.set_flex(cells, 1);
+
+            let style = TextStyle::new(display.text_size(9.0), theme.warning, FontId::Regular).wrap(false);
+            *error_label = Some(ui.add(area, Label::new("", style)));
+            (ui, cells)
+        });
+
+        ui.update::<CharacterCells>(cells, |cells| {
+            cells.text = text;
+            cells.cursor = cursor;
+        });
+        if let Some(label) = *error_label {
+            ui.update::<Label>(label, |label| label.set_text(message));
+        }
+
+        display.show_status(&self.title);
+        display.show_ui(ui);
+    }
+}
+
+/// Cell width used when the font cannot be measured
+const MIN_CELL_WIDTH: u16 = 4;
+
+/// Characters in equally wide cells with the one under the cursor highlighted
+#[derive(Debug, Clone, PartialEq)]
+struct CharacterCells {
+    text: Vec<char>,
+    cursor: usize,
+    style: TextStyle,
+    cursor_color: Color,
+    cursor_text: Color,
+    /// Line under the other cells
+    underline: Color,
+}
+
+impl CharacterCells {
+    /// Cell width and the ascent and descent of the font
+    ///
+    /// A font that is not loaded gets `MIN_CELL_WIDTH` wide cells of the text size.
+    fn metrics(&self, fb: &Framebuffer) -> (u16, u16, u16) {
+        fb.measure_text("M", self.style.size, self.style.font).map_or(
+            (MIN_CELL_WIDTH, self.style.size.ceil() as u16, 0),
+            |metrics| ((metrics.width + 2).max(MIN_CELL_WIDTH), metrics.ascent, metrics.descent),
+        )
+    }
+}
+
+impl Widget for CharacterCells {
+    fn measure(&self, fb: &Framebuffer, available: Size) -> Size {
+        let (cell, ascent, descent) = self.metrics(fb);
+        Size::new((cell * self.text.len() as u16).min(available.width), ascent + descent + 2)
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let (cell, ascent, descent) = self.metrics(fb);
+        let height = ascent + descent + 2;
+        let top = bounds.y + bounds.height.saturating_sub(height) / 2;
+
+        // Scroll long text so the cursor stays visible
+        let visible = (bounds.width / cell.max(1)).max(1) as usize;
+        let first = (self.cursor + 1).saturating_sub(visible);
+
+        for (slot, (index, &c)) in self.text.iter().enumerate().skip(first).take(visible).enumerate() {
+            let x = bounds.x + slot as u16 * cell;
+            if index == self.cursor {
+                fb.fill_rect(Rect::new(x, top, cell, height), self.cursor_color);
+            } else {
+                fb.draw_hline(Point::new(x + 1, top + height - 1), cell.saturating_sub(2), self.underline);
+            }
+
+            let width = fb.measure_text(&c.to_string(), self.style.size, self.style.font).map_or(0, |m| m.width);
+            let color = if index == self.cursor { self.cursor_text } else { self.style.color };
+            let position = Point::new(x + cell.saturating_sub(width) / 2, top + 1 + ascent);
+            let _ = fb.write_text(&c.to_string(), position, self.style.size, color, self.style.font);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::framebuffer::PixelFormat;
+
+    fn cells(size: f32) -> CharacterCells {
+        CharacterCells {
+            text: "145.500".chars().collect(),
+            cursor: 2,
+            style: TextStyle::new(size, Color::white(), FontId::Mono),
+            cursor_color: Color::new(0, 0, 255, 255),
+            cursor_text: Color::white(),
+            underline: Color::new(0, 255, 0, 255),
+        }
+    }
+
+    /// Pixels of the given color, read back from the PPM dump
+    fn count(fb: &Framebuffer, color: Color) -> usize {
+        let mut ppm = Vec::new();
+        fb.write_ppm(&mut ppm).unwrap();
+        ppm[b"P6\n160 128\n255\n".len()..]
+            .chunks_exact(3)
+            .filter(|pixel| *pixel == [color.r, color.g, color.b])
+            .count()
+    }
+
+    #[test]
+    fn cells_are_drawn_without_a_font() {
+        let mut fb = Framebuffer::with_backend(Box::new(MemoryBackend::new(160, 128, PixelFormat::RGB565))).unwrap();
+        assert!(fb.load_font(FontId::Mono, Some("/nonexistent/DejaVuSansMono.ttf")).is_err());
+
+        let cells = cells(16.0);
+        let size = cells.measure(&fb, Size::new(160, 128));
+        assert_eq!(size, Size::new(7 * MIN_CELL_WIDTH, 18));
+
+        cells.draw(&mut fb, Rect::new(0, 0, 160, 40));
+        assert_eq!(count(&fb, cells.cursor_color), (MIN_CELL_WIDTH * 18) as usize);
+        assert_eq!(count(&fb, cells.underline), 6 * (MIN_CELL_WIDTH - 2) as usize);
+    }
+
+    #[test]
+    fn cells_fit_the_widest_character() {
+        let mut fb = Framebuffer::with_backend(Box::new(MemoryBackend::new(160, 128, PixelFormat::RGB565))).unwrap();
+        fb.load_default_fonts(crate::framebuffer::DEFAULT_FONT_DIR).unwrap();
+
+        let cells = cells(16.0);
+        let (cell, ascent, descent) = cells.metrics(&fb);
+        assert_eq!(cell, fb.measure_text("M", 16.0, FontId::Mono).unwrap().width + 2);
+        assert_eq!(cells.measure(&fb, Size::new(160, 128)), Size::new(7 * cell, ascent + descent + 2));
+        assert_eq!(cells.measure(&fb, Size::new(20, 128)).width, 20);
+    }
+}
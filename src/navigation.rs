@@ -0,0 +1,219 @@
+//! Screens stacked on top of each other with push/pop navigation
+//!
+//! Only the topmost screen is drawn and receives input. Screens share an
+//! application context `C` holding whatever state they act on.
+
+use crate::display::Display;
+use crate::input::InputEvent;
+
+/// A full page of the UI, such as the idle screen, a menu or a detail view
+pub trait Screen<C> {
+    /// Name used in log messages
+    fn name(&self) -> &str;
+
+    /// Reacts to a key press while this screen is on top
+    ///
+    /// Returning `Transition::Unhandled` for `InputEvent::Back` leaves the screen.
+    fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> Transition<C>;
+
+    /// Draws the screen; called after input and periodically to refresh it
+    fn render(&mut self, display: &mut Display, context: &C);
+}
+
+/// What the screen stack does after a screen handled input
+pub enum Transition<C> {
+    /// Stay on this screen
+    Stay,
+    /// Open another screen on top of this one
+    Push(Box<dyn Screen<C>>),
+    /// Close this screen, returning to the one below
+    Pop,
+    /// Close this screen and open another in its place
+    Replace(Box<dyn Screen<C>>),
+    /// The screen ignored the event
+    Unhandled,
+}
+
+/// The open screens, the last one being visible
+///
+/// The root screen passed to `new` is never popped.
+pub struct ScreenStack<C> {
+    screens: Vec<Box<dyn Screen<C>>>,
+    /// The visible screen changed since the last render
+    changed: bool,
+}
+
+impl<C> ScreenStack<C> {
+    pub fn new(root: Box<dyn Screen<C>>) -> Self {
+        ScreenStack {
+            screens: vec![root],
+            changed: true,
+        }
+    }
+
+    /// Number of open screens, including the root
+    pub fn depth(&self) -> usize {
+        self.screens.len()
+    }
+
+    pub fn top(&self) -> &dyn Screen<C> {
+        self.screens[self.screens.len() - 1].as_ref()
+    }
+
+    pub fn push(&mut self, screen: Box<dyn Screen<C>>) {
+        println!("[Screens] Opening {}", screen.name());
+        self.screens.push(screen);
+        self.changed = true;
+    }
+
+    /// Closes the top screen, returning false if only the root is left
+    pub fn pop(&mut self) -> bool {
+        if self.screens.len() == 1 {
+            return false;
+        }
+
+        if let Some(screen) = self.screens.pop() {
+            println!("[Screens] Closing {}", screen.name());
+        }
+        self.changed = true;
+        true
+    }
+
+    /// Replaces the top screen, or the root if it is the only one
+    pub fn replace(&mut self, screen: Box<dyn Screen<C>>) {
+        self.screens.pop();
+        self.push(screen);
+    }
+
+    /// Closes every screen above the root
+    pub fn pop_to_root(&mut self) {
+        while self.pop() {}
+    }
+
+    /// Passes an event to the top screen and carries out the resulting transition
+    ///
+    /// Returns false if the event was ignored, including a `Back` on the root.
+    pub fn handle_input(&mut self, event: &InputEvent, context: &mut C) -> bool {
+        let last = self.screens.len() - 1;
+        match self.screens[last].handle_input(event, context) {
+            Transition::Stay => true,
+            Transition::Push(screen) => {
+                self.push(screen);
+                true
+            }
+            Transition::Pop => self.pop(),
+            Transition::Replace(screen) => {
+                self.replace(screen);
+                true
+            }
+            Transition::Unhandled if *event == InputEvent::Back => self.pop(),
+            Transition::Unhandled => false,
+        }
+    }
+
+    /// Draws the top screen, in full if it was just opened or uncovered
+    pub fn render(&mut self, display: &mut Display, context: &C) {
+        if self.changed {
+            self.changed = false;
+            display.invalidate();
+        }
+
+        let last = self.screens.len() - 1;
+        self.screens[last].render(display, context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opens a screen on `Run`, closes itself on `Stop`, swaps itself on `Mode`
+    /// and counts the events it keeps
+    struct TestScreen(String);
+
+    impl Screen<Vec<String>> for TestScreen {
+        fn name(&self) -> &str {
+            &self.0
+        }
+
+        fn handle_input(&mut self, event: &InputEvent, handled: &mut Vec<String>) -> Transition<Vec<String>> {
+            match event {
+                InputEvent::Run => Transition::Push(screen(&format!("{}/child", self.0))),
+                InputEvent::Stop => Transition::Pop,
+                InputEvent::Mode => Transition::Replace(screen(&format!("{}'", self.0))),
+                InputEvent::Back | InputEvent::Quit => Transition::Unhandled,
+                _ => {
+                    handled.push(self.0.clone());
+                    Transition::Stay
+                }
+            }
+        }
+
+        fn render(&mut self, _display: &mut Display, _handled: &Vec<String>) {}
+    }
+
+    fn screen(name: &str) -> Box<dyn Screen<Vec<String>>> {
+        Box::new(TestScreen(name.to_string()))
+    }
+
+    #[test]
+    fn push_and_pop_follow_the_top_screen() {
+        let mut stack = ScreenStack::new(screen("home"));
+        let mut handled = Vec::new();
+
+        assert!(stack.handle_input(&InputEvent::Run, &mut handled));
+        assert!(stack.handle_input(&InputEvent::Run, &mut handled));
+        assert_eq!((stack.depth(), stack.top().name()), (3, "home/child/child"));
+
+        assert!(stack.handle_input(&InputEvent::Menu, &mut handled));
+        assert_eq!(handled, ["home/child/child"]);
+
+        assert!(stack.handle_input(&InputEvent::Stop, &mut handled));
+        assert!(stack.handle_input(&InputEvent::Back, &mut handled));
+        assert_eq!((stack.depth(), stack.top().name()), (1, "home"));
+    }
+
+    #[test]
+    fn root_is_never_popped() {
+        let mut stack = ScreenStack::new(screen("home"));
+        let mut handled = Vec::new();
+
+        assert!(!stack.handle_input(&InputEvent::Back, &mut handled));
+        assert!(!stack.handle_input(&InputEvent::Stop, &mut handled));
+        assert!(!stack.handle_input(&InputEvent::Quit, &mut handled));
+        assert_eq!((stack.depth(), stack.top().name()), (1, "home"));
+    }
+
+    #[test]
+    fn replace_swaps_the_top_screen() {
+        let mut stack = ScreenStack::new(screen("home"));
+        let mut handled = Vec::new();
+
+        stack.handle_input(&InputEvent::Mode, &mut handled);
+        assert_eq!((stack.depth(), stack.top().name()), (1, "home'"));
+
+        stack.handle_input(&InputEvent::Run, &mut handled);
+        stack.handle_input(&InputEvent::Mode, &mut handled);
+        assert_eq!((stack.depth(), stack.top().name()), (2, "home'/child'"));
+
+        stack.pop_to_root();
+        assert_eq!((stack.depth(), stack.top().name()), (1, "home'"));
+    }
+
+    #[test]
+    fn transitions_mark_the_screen_for_a_full_redraw() {
+        let mut stack = ScreenStack::new(screen("home"));
+        let mut handled = Vec::new();
+        stack.changed = false;
+
+        stack.handle_input(&InputEvent::Menu, &mut handled);
+        assert!(!stack.changed);
+
+        stack.handle_input(&InputEvent::Run, &mut handled);
+        assert!(stack.changed);
+
+        stack.changed = false;
+        stack.handle_input(&InputEvent::Back, &mut handled);
+        assert!(stack.changed);
+    }
+}
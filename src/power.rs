@@ -0,0 +1,306 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::display::Display;
+
+/// Directory the kernel lists backlight devices in
+pub const BACKLIGHT_CLASS_DIR: &str = "/sys/class/backlight";
+
+/// Idle time before the backlight is dimmed
+pub const DEFAULT_DIM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Idle time before the display is blanked
+pub const DEFAULT_BLANK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Fraction of the normal brightness used while dimmed
+pub const DEFAULT_DIM_LEVEL: f32 = 0.2;
+
+/// Backlight controlled through sysfs, e.g. `/sys/class/backlight/backlight`
+pub struct Backlight {
+    path: PathBuf,
+    max_brightness: u32,
+}
+
+impl Backlight {
+    /// Opens the backlight device directory at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let max_brightness = read_value(&path.join("max_brightness"))?;
+        if max_brightness == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "max_brightness is 0"));
+        }
+
+        Ok(Self { path, max_brightness })
+    }
+
+    /// Opens the first usable device in `/sys/class/backlight`, if there is one
+    pub fn discover() -> Option<Self> {
+        let mut devices: Vec<PathBuf> = fs::read_dir(BACKLIGHT_CLASS_DIR)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        devices.sort();
+
+        devices.into_iter().find_map(|path| Self::open(path).ok())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    /// Current raw brightness, between 0 and `max_brightness`
+    pub fn brightness(&self) -> io::Result<u32> {
+        read_value(&self.path.join("brightness"))
+    }
+
+    /// Sets the raw brightness, clamped to `max_brightness`
+    pub fn set_brightness(&self, value: u32) -> io::Result<()> {
+        fs::write(self.path.join("brightness"), value.min(self.max_brightness).to_string())
+    }
+
+    /// Sets the brightness as a fraction of the maximum, from 0.0 (off) to 1.0
+    pub fn set_level(&self, level: f32) -> io::Result<()> {
+        let value = (level.clamp(0.0, 1.0) * self.max_brightness as f32).round() as u32;
+        self.set_brightness(value)
+    }
+}
+
+fn read_value(path: &Path) -> io::Result<u32> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// How far the display has been powered down, ordered from on to off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PowerState {
+    Active,
+    Dimmed,
+    Blanked,
+}
+
+/// Dims and then blanks the display after a period without activity
+///
+/// Call `update` regularly from the main loop and `wake` on every key press
+/// or incoming frame.
+pub struct PowerManager {
+    backlight: Option<Backlight>,
+    /// Raw brightness restored when waking up
+    active_brightness: u32,
+    dim_level: f32,
+    dim_after: Option<Duration>,
+    blank_after: Option<Duration>,
+    last_activity: Instant,
+    state: PowerState,
+}
+
+impl PowerManager {
+    /// Creates a manager using the default timeouts; without a backlight only blanking is done
+    pub fn new(backlight: Option<Backlight>) -> Self {
+        let active_brightness = backlight
+            .as_ref()
+            .map_or(0, |light| light.brightness().unwrap_or(light.max_brightness()));
+
+        PowerManager {
+            backlight,
+            active_brightness,
+            dim_level: DEFAULT_DIM_LEVEL,
+            dim_after: Some(DEFAULT_DIM_TIMEOUT),
+            blank_after: Some(DEFAULT_BLANK_TIMEOUT),
+            last_activity: Instant::now(),
+            state: PowerState::Active,
+        }
+    }
+
+    /// Sets the idle times before dimming and blanking; `None` disables that step
+    pub fn set_timeouts(&mut self, dim_after: Option<Duration>, blank_after: Option<Duration>) {
+        self.dim_after = dim_after;
+        self.blank_after = blank_after;
+    }
+
+    /// Sets the dimmed brightness as a fraction of the normal brightness
+    pub fn set_dim_level(&mut self, level: f32) {
+        self.dim_level = level.clamp(0.0, 1.0);
+    }
+
+    pub fn state(&self) -> PowerState {
+        self.state
+    }
+
+    pub fn is_blanked(&self) -> bool {
+        self.state == PowerState::Blanked
+    }
+
+    /// Records activity and restores full brightness
+    ///
+    /// Returns true if the display was blanked, so the caller can redraw it
+    /// and drop the key press that woke it.
+    pub fn wake(&mut self, display: &mut Display) -> bool {
+        self.last_activity = Instant::now();
+
+        let was_blanked = self.is_blanked();
+        if self.state != PowerState::Active {
+            self.apply(PowerState::Active, display);
+        }
+        was_blanked
+    }
+
+    /// Dims or blanks the display once the idle timeouts have passed
+    pub fn update(&mut self, display: &mut Display) {
+        let idle = self.last_activity.elapsed();
+        let target = if self.blank_after.is_some_and(|timeout| idle >= timeout) {
+            PowerState::Blanked
+        } else if self.dim_after.is_some_and(|timeout| idle >= timeout) {
+            PowerState::Dimmed
+        } else {
+            PowerState::Active
+        };
+
+        if target > self.state {
+            self.apply(target, display);
+        }
+    }
+
+    fn apply(&mut self, state: PowerState, display: &mut Display) {
+        println!("[Power] {:?} -> {:?}", self.state, state);
+
+        let brightness = match state {
+            PowerState::Active => self.active_brightness,
+            PowerState::Dimmed => ((self.active_brightness as f32 * self.dim_level).round() as u32).max(1),
+            PowerState::Blanked => 0,
+        };
+        if let Some(backlight) = &self.backlight {
+            if let Err(e) = backlight.set_brightness(brightness) {
+                println!("[Power] Failed to set backlight {}: {}", backlight.path().display(), e);
+            }
+        }
+
+        let blank = state == PowerState::Blanked;
+        if blank != self.is_blanked() {
+            if let Err(e) = display.set_blank(blank) {
+                println!("[Power] {}", e);
+            }
+        }
+
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Backend, MemoryBackend};
+    use crate::framebuffer::{DeviceInfo, Framebuffer, FramebufferError, PixelFormat};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    /// Memory display that remembers whether it was blanked
+    struct Panel {
+        memory: MemoryBackend,
+        blanked: Arc<AtomicBool>,
+    }
+
+    impl Backend for Panel {
+        fn device_info(&self) -> &DeviceInfo {
+            self.memory.device_info()
+        }
+
+        fn buffer_mut(&mut self) -> &mut [u8] {
+            self.memory.buffer_mut()
+        }
+
+        fn set_blank(&mut self, blank: bool) -> Result<(), FramebufferError> {
+            self.blanked.store(blank, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn display() -> (Display, Arc<AtomicBool>) {
+        let blanked = Arc::new(AtomicBool::new(false));
+        let panel = Panel { memory: MemoryBackend::new(160, 128, PixelFormat::RGB565), blanked: blanked.clone() };
+        (Display::new(Framebuffer::with_backend(Box::new(panel)).unwrap()), blanked)
+    }
+
+    fn backlight(dir: &tempfile::TempDir, max: &str, brightness: &str) -> io::Result<Backlight> {
+        fs::write(dir.path().join("max_brightness"), max).unwrap();
+        fs::write(dir.path().join("brightness"), brightness).unwrap();
+        Backlight::open(dir.path())
+    }
+
+    fn brightness(dir: &tempfile::TempDir) -> String {
+        fs::read_to_string(dir.path().join("brightness")).unwrap()
+    }
+
+    #[test]
+    fn backlight_levels_are_scaled_and_clamped() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(backlight(&dir, "0\n", "0\n").is_err());
+
+        let light = backlight(&dir, "255\n", "100\n").unwrap();
+        assert_eq!(light.brightness().unwrap(), 100);
+        light.set_level(0.5).unwrap();
+        assert_eq!(brightness(&dir), "128");
+        light.set_level(3.0).unwrap();
+        assert_eq!(brightness(&dir), "255");
+        light.set_brightness(1000).unwrap();
+        assert_eq!(brightness(&dir), "255");
+    }
+
+    #[test]
+    fn dims_then_blanks_and_wakes_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut display, blanked) = display();
+        let mut power = PowerManager::new(Some(backlight(&dir, "255\n", "200\n").unwrap()));
+        power.set_timeouts(Some(Duration::from_secs(30)), Some(Duration::from_secs(60)));
+
+        power.update(&mut display);
+        assert_eq!(power.state(), PowerState::Active);
+
+        power.last_activity -= Duration::from_secs(30);
+        power.update(&mut display);
+        assert_eq!(power.state(), PowerState::Dimmed);
+        assert_eq!(brightness(&dir), "40");
+        assert!(!blanked.load(Ordering::SeqCst));
+
+        power.last_activity -= Duration::from_secs(30);
+        power.update(&mut display);
+        assert!(power.is_blanked());
+        assert_eq!(brightness(&dir), "0");
+        assert!(blanked.load(Ordering::SeqCst));
+
+        assert!(power.wake(&mut display));
+        assert_eq!(power.state(), PowerState::Active);
+        assert_eq!(brightness(&dir), "200");
+        assert!(!blanked.load(Ordering::SeqCst));
+        assert!(!power.wake(&mut display));
+    }
+
+    #[test]
+    fn disabled_steps_are_skipped() {
+        let (mut display, blanked) = display();
+        let mut power = PowerManager::new(None);
+        power.set_timeouts(None, Some(Duration::from_secs(60)));
+
+        power.last_activity -= Duration::from_secs(45);
+        power.update(&mut display);
+        assert_eq!(power.state(), PowerState::Active);
+
+        power.last_activity -= Duration::from_secs(15);
+        power.update(&mut display);
+        assert!(power.is_blanked() && blanked.load(Ordering::SeqCst));
+
+        power.set_timeouts(None, None);
+        power.wake(&mut display);
+        power.last_activity -= Duration::from_secs(3600);
+        power.update(&mut display);
+        assert_eq!(power.state(), PowerState::Active);
+    }
+}
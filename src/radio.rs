@@ -0,0 +1,273 @@
+//! What the radio is tuned to, shown on the idle screen and handed to flowgraphs
+//!
+//! The state is saved with the settings (`rx_frequency`, `tx_frequency`, `tx_inhibit`,
+//! `channel` and `memory`) and passed to a started flowgraph in these environment variables:
+//!
+//! - `LINHT_RX_FREQ` - receive frequency in Hz
+//! - `LINHT_TX_FREQ` - transmit frequency in Hz, unset on receive-only channels
+//! - `LINHT_CHANNEL` - channel name, empty when tuned freely
+
+/// An amateur band the radio may be tuned within
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Band {
+    pub name: &'static str,
+    /// Lowest frequency in Hz
+    pub low: u32,
+    /// Highest frequency in Hz
+    pub high: u32,
+}
+
+impl Band {
+    pub fn contains(&self, hz: u32) -> bool {
+        (self.low..=self.high).contains(&hz)
+    }
+}
+
+/// Bands accepted by frequency entry and tuning
+pub const BANDS: [Band; 2] = [
+    Band { name: "2 m", low: 144_000_000, high: 148_000_000 },
+    Band { name: "70 cm", low: 420_000_000, high: 450_000_000 },
+];
+
+/// Returns the band `hz` lies in, if any
+pub fn band_of(hz: u32) -> Option<&'static Band> {
+    BANDS.iter().find(|band| band.contains(hz))
+}
+
+/// Tuning steps offered on the device, in Hz
+pub const TUNING_STEPS: [u32; 5] = [6_250, 12_500, 25_000, 100_000, 1_000_000];
+
+/// Tuning step used until another one is selected
+pub const DEFAULT_TUNING_STEP: u32 = 12_500;
+
+/// Frequencies and name of the channel the radio is tuned to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RadioState {
+    /// Receive frequency in Hz
+    pub rx_frequency: u32,
+    /// Transmit frequency in Hz
+    pub tx_frequency: u32,
+    /// Receive only, like CHIRP's `Duplex=off`; `tx_frequency` is not handed to flowgraphs
+    pub tx_inhibit: bool,
+    /// Name of the memory channel, `None` while tuned freely (VFO)
+    pub channel: Option<String>,
+    /// Location of the recalled memory, which unlike its name is unique
+    pub memory: Option<u32>,
+}
+
+impl Default for RadioState {
+    fn default() -> Self {
+        RadioState {
+            rx_frequency: 438_300_000,
+            tx_frequency: 430_700_000,
+            tx_inhibit: false,
+            channel: None,
+            memory: None,
+        }
+    }
+}
+
+impl RadioState {
+    /// Simplex on `hz`, not tied to a channel
+    pub fn simplex(hz: u32) -> Self {
+        RadioState {
+            rx_frequency: hz,
+            tx_frequency: hz,
+            tx_inhibit: false,
+            channel: None,
+            memory: None,
+        }
+    }
+
+    /// Transmit minus receive frequency in Hz, negative for a repeater shifting down
+    pub fn offset(&self) -> i64 {
+        self.tx_frequency as i64 - self.rx_frequency as i64
+    }
+
+    /// Retunes the receiver, moving the transmit frequency along to keep the offset
+    ///
+    /// Leaves the channel, since the frequencies no longer match it, but stays
+    /// receive only. Returns false, changing nothing, if either frequency would
+    /// leave the bands.
+    pub fn tune(&mut self, rx_frequency: u32) -> bool {
+        let tx_frequency = offset_from(rx_frequency, self.offset());
+        if band_of(rx_frequency).is_none() || (!self.tx_inhibit && band_of(tx_frequency).is_none()) {
+            return false;
+        }
+
+        self.rx_frequency = rx_frequency;
+        self.tx_frequency = tx_frequency;
+        self.leave_channel();
+        true
+    }
+
+    /// Tunes one `step` up or down, onto the next multiple of `step`
+    ///
+    /// Returns false, leaving the frequency alone, if that would leave the bands.
+    pub fn step(&mut self, step: u32, up: bool) -> bool {
+        let step = step.max(1) as u64;
+        let hz = self.rx_frequency as u64;
+        let target = if up { (hz / step + 1) * step } else { hz.div_ceil(step).saturating_sub(1) * step };
+
+        u32::try_from(target).is_ok_and(|target| self.tune(target))
+    }
+
+    /// Forgets the memory channel after the frequencies were changed by hand
+    pub fn leave_channel(&mut self) {
+        self.channel = None;
+        self.memory = None;
+    }
+
+    /// Whether the receive frequency, and the transmit frequency unless inhibited, lie within `BANDS`
+    pub fn in_band(&self) -> bool {
+        band_of(self.rx_frequency).is_some() && (self.tx_inhibit || band_of(self.tx_frequency).is_some())
+    }
+
+    /// Channel name, or `VFO` while tuned freely
+    pub fn channel_name(&self) -> &str {
+        self.channel.as_deref().unwrap_or("VFO")
+    }
+
+    /// Environment variables telling a flowgraph what to tune to
+    pub fn environment(&self) -> Vec<(&'static str, String)> {
+        let mut variables = vec![
+            ("LINHT_RX_FREQ", self.rx_frequency.to_string()),
+            ("LINHT_CHANNEL", self.channel.clone().unwrap_or_default()),
+        ];
+        if !self.tx_inhibit {
+            variables.push(("LINHT_TX_FREQ", self.tx_frequency.to_string()));
+        }
+        variables
+    }
+}
+
+/// `hz` moved by `offset` Hz, kept within `u32`
+fn offset_from(hz: u32, offset: i64) -> u32 {
+    (hz as i64 + offset).clamp(0, u32::MAX as i64) as u32
+}
+
+/// Formats a frequency in Hz as MHz, e.g. `438.300`
+///
+/// At least three decimals are shown, more where the frequency is not a whole
+/// kHz, so `438.30625` for a 6.25 kHz channel.
+pub fn format_frequency(hz: u32) -> String {
+    format_mhz(hz as u64)
+}
+
+/// MHz with at least three decimals, see `format_frequency`
+fn format_mhz(hz: u64) -> String {
+    let decimals = format!("{:06}", hz % 1_000_000);
+    format!("{}.{:0<3}", hz / 1_000_000, decimals.trim_end_matches('0'))
+}
+
+/// Formats a transmit offset as signed MHz, e.g. `-7.600 MHz`, or `Simplex`
+///
+/// Offsets that are not a whole kHz get more decimals, like frequencies.
+pub fn format_offset(offset: i64) -> String {
+    if offset == 0 {
+        return "Simplex".to_string();
+    }
+
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!("{}{} MHz", sign, format_mhz(offset.unsigned_abs()))
+}
+
+/// Formats a tuning step, e.g. `12.5 kHz` or `1 MHz`
+pub fn format_step(hz: u32) -> String {
+    if hz >= 1_000_000 && hz.is_multiple_of(1_000_000) {
+        format!("{} MHz", hz / 1_000_000)
+    } else {
+        format!("{} kHz", hz as f32 / 1_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_frequencies_with_all_decimals() {
+        assert_eq!(format_frequency(438_300_000), "438.300");
+        assert_eq!(format_frequency(438_306_250), "438.30625");
+        assert_eq!(format_frequency(145_512_500), "145.5125");
+        assert_eq!(format_frequency(144_000_001), "144.000001");
+        assert_eq!(format_frequency(0), "0.000");
+    }
+
+    #[test]
+    fn formats_offsets_and_steps() {
+        assert_eq!(format_offset(-7_600_000), "-7.600 MHz");
+        assert_eq!(format_offset(600_000), "+0.600 MHz");
+        assert_eq!(format_offset(0), "Simplex");
+        assert_eq!(format_offset(-6_250), "-0.00625 MHz");
+        assert_eq!(format_offset(1_612_500), "+1.6125 MHz");
+        assert_eq!(format_step(6_250), "6.25 kHz");
+        assert_eq!(format_step(12_500), "12.5 kHz");
+        assert_eq!(format_step(1_000_000), "1 MHz");
+    }
+
+    #[test]
+    fn steps_onto_the_next_multiple() {
+        let mut radio = RadioState::simplex(145_506_250);
+        assert!(radio.step(12_500, true));
+        assert_eq!(radio.rx_frequency, 145_512_500);
+        assert!(radio.step(25_000, false));
+        assert_eq!(radio.rx_frequency, 145_500_000);
+        assert!(radio.step(25_000, false));
+        assert_eq!(radio.rx_frequency, 145_475_000);
+    }
+
+    #[test]
+    fn steps_stop_at_the_band_edges() {
+        let mut radio = RadioState::simplex(148_000_000);
+        assert!(!radio.step(12_500, true));
+        assert_eq!(radio.rx_frequency, 148_000_000);
+
+        let mut radio = RadioState::simplex(144_000_000);
+        assert!(!radio.step(12_500, false));
+        assert!(radio.step(12_500, true));
+        assert_eq!(radio.rx_frequency, 144_012_500);
+
+        let mut radio = RadioState::simplex(449_500_000);
+        assert!(radio.step(1_000_000, true));
+        assert!(!radio.step(1_000_000, true));
+        assert_eq!(radio.rx_frequency, 450_000_000);
+    }
+
+    #[test]
+    fn tuning_keeps_the_offset_within_the_bands() {
+        let mut radio = RadioState {
+            channel: Some("OE3XPA".to_string()),
+            memory: Some(1),
+            ..RadioState::default()
+        };
+        assert!(radio.tune(438_825_000));
+        assert_eq!((radio.tx_frequency, radio.channel.as_deref(), radio.memory), (431_225_000, None, None));
+
+        // The transmit frequency would end up at 419.9 MHz
+        assert!(!radio.tune(427_500_000));
+        assert_eq!(radio.rx_frequency, 438_825_000);
+        assert!(radio.tune(428_000_000));
+        assert!(!radio.step(1_000_000, false));
+        assert_eq!(radio.rx_frequency, 428_000_000);
+
+        radio.tx_inhibit = true;
+        assert!(radio.tune(427_500_000));
+        assert!(!radio.tune(150_000_000));
+    }
+
+    #[test]
+    fn receive_only_hands_no_transmit_frequency_to_flowgraphs() {
+        let mut radio = RadioState::simplex(145_500_000);
+        radio.channel = Some("S20".to_string());
+        assert_eq!(
+            radio.environment(),
+            [("LINHT_RX_FREQ", "145500000".to_string()), ("LINHT_CHANNEL", "S20".to_string()), ("LINHT_TX_FREQ", "145500000".to_string())]
+        );
+
+        radio.tx_inhibit = true;
+        radio.tx_frequency = 0;
+        assert!(radio.in_band());
+        assert!(radio.environment().iter().all(|(name, _)| *name != "LINHT_TX_FREQ"));
+    }
+}
@@ -0,0 +1,349 @@
+// Screens of the linht-gui application, shown through a `ScreenStack<AppState>`
+
+use linht_gui::{ChoiceEditor, Display, FlowgraphState, FrequencyEntry, InputEvent, MenuEntry, MenuScreen, NumberEditor, Screen, TextEditor, Theme, Transition};
+use linht_gui::radio::{format_frequency, format_offset, format_step, TUNING_STEPS};
+use linht_gui::settings::MAX_CALLSIGN_LEN;
+
+use crate::AppState;
+
+/// What up and down do on the home screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum HomeMode {
+    /// Select the flowgraph
+    #[default]
+    Flowgraph,
+    /// Tune the receive frequency by the tuning step
+    Vfo,
+    /// Recall the channel memories one after the other
+    Memory,
+}
+
+impl HomeMode {
+    /// The mode `*` switches to; memory recall is skipped while there are no memories
+    fn next(self, state: &AppState) -> Self {
+        match self {
+            HomeMode::Flowgraph => HomeMode::Vfo,
+            HomeMode::Vfo if !state.memories.is_empty() => HomeMode::Memory,
+            HomeMode::Vfo | HomeMode::Memory => HomeMode::Flowgraph,
+        }
+    }
+
+    /// Shown next to the channel on the idle screen
+    fn label(self, state: &AppState) -> String {
+        match self {
+            HomeMode::Flowgraph => String::new(),
+            HomeMode::Vfo => format!("\u{b1}{}", format_step(state.settings.tuning_step)),
+            HomeMode::Memory => {
+                let current = state.current_channel().map_or("-".to_string(), |index| (index + 1).to_string());
+                format!("MR {}/{}", current, state.memories.len())
+            }
+        }
+    }
+}
+
+/// Flowgraph selection and tuning, and the spectrum or received M17 messages while a flowgraph runs
+#[derive(Default)]
+pub struct HomeScreen {
+    mode: HomeMode,
+    /// `*` switched from the spectrum back to the idle screen or messages
+    hide_spectrum: bool,
+    /// Shown in a popup on the next render
+    error: Option<String>,
+}
+
+impl HomeScreen {
+    /// Tunes one step up or down; the running flowgraph keeps its frequency, so only while stopped
+    fn tune(&mut self, state: &mut AppState, up: bool) {
+        if state.settings.radio.step(state.settings.tuning_step, up) {
+            state.save_settings_later();
+        } else {
+            println!("[Main] Band edge reached at {} MHz", format_frequency(state.settings.radio.rx_frequency));
+        }
+    }
+    
+    /// Whether the spectrum is shown; up and down then move the reference level and `#` changes the span
+    fn showing_spectrum(&self, state: &AppState) -> bool {
+        state.is_running() && state.has_spectrum() && !self.hide_spectrum
+    }
+    
+    fn step_reference_level(&mut self, state: &mut AppState, up: bool) {
+        if state.settings.spectrum.step_reference_level(up) {
+            state.save_settings_later();
+        }
+    }
+}
+
+impl Screen<AppState> for HomeScreen {
+    fn name(&self) -> &str {
+        "home"
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, state: &mut AppState) -> Transition<AppState> {
+        match event {
+            InputEvent::NextFlowgraph if self.showing_spectrum(state) => self.step_reference_level(state, true),
+            InputEvent::PreviousFlowgraph if self.showing_spectrum(state) => self.step_reference_level(state, false),
+            InputEvent::Step if self.showing_spectrum(state) => {
+                state.settings.spectrum.next_span();
+                state.save_settings_later();
+            }
+            InputEvent::Mode if state.is_running() && state.has_spectrum() => self.hide_spectrum = !self.hide_spectrum,
+            InputEvent::NextFlowgraph if !state.is_running() => match self.mode {
+                HomeMode::Flowgraph => state.next_flowgraph(),
+                HomeMode::Vfo => self.tune(state, true),
+                HomeMode::Memory => state.step_channel(true),
+            },
+            InputEvent::PreviousFlowgraph if !state.is_running() => match self.mode {
+                HomeMode::Flowgraph => state.previous_flowgraph(),
+                HomeMode::Vfo => self.tune(state, false),
+                HomeMode::Memory => state.step_channel(false),
+            },
+            InputEvent::Mode => {
+                // Leaving VFO mode saves the frequency tuned to
+                state.save_pending_settings(true);
+                self.mode = self.mode.next(state);
+            }
+            InputEvent::Step if self.mode == HomeMode::Vfo => {
+                state.settings.tuning_step = next_tuning_step(state.settings.tuning_step);
+                state.save_settings_later();
+            }
+            InputEvent::Digit(digit) if !state.is_running() => {
+                let entry = rx_frequency_entry(state).typed(*digit);
+                return Transition::Push(Box::new(entry));
+            }
+            InputEvent::Run if !state.is_running() => {
+                if let Err(e) = state.start_flowgraph() {
+                    println!("[Main] Failed to start flowgraph: {}", e);
+                    self.error = Some(e.to_string());
+                }
+            }
+            InputEvent::Stop if state.is_running() => state.stop_flowgraph(),
+            InputEvent::Menu => return Transition::Push(Box::new(settings_menu())),
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn render(&mut self, display: &mut Display, state: &AppState) {
+        // The state icon next to the text tells whether it runs
+        match state.flowgraph_state() {
+            FlowgraphState::Running => display.show_status(&state.current_flowgraph_name()),
+            FlowgraphState::Failed => display.show_status("Failed"),
+            FlowgraphState::Stopped => display.show_status("Ready"),
+        }
+
+        match &state.last_message {
+            _ if self.showing_spectrum(state) => display.show_spectrum(&state.settings.radio, &state.settings.spectrum),
+            Some(message) if state.is_running() => display.show_m17_message(message),
+            _ => {
+                // Memories can be deleted in the menu meanwhile
+                if self.mode == HomeMode::Memory && state.memories.is_empty() {
+                    self.mode = HomeMode::Flowgraph;
+                }
+                display.show_idle(&state.current_flowgraph_name(), &state.settings.radio, &self.mode.label(state));
+            }
+        }
+
+        if let Some(error) = self.error.take() {
+            display.show_popup("Start failed", &error);
+        }
+    }
+}
+
+/// Longest memory name entered on the device
+const MAX_CHANNEL_NAME_LEN: usize = 10;
+
+/// Idle timeouts offered in the display settings, in seconds
+const TIMEOUT_CHOICES: [u32; 6] = [0, 10, 30, 60, 120, 300];
+
+fn settings_menu() -> MenuScreen<AppState> {
+    MenuScreen::new("Settings", vec![
+        MenuEntry::screen("Station", |_| Box::new(station_menu())),
+        MenuEntry::screen("Radio", |_| Box::new(radio_menu())),
+        MenuEntry::screen("Memories", |_| Box::new(memories_menu())),
+        MenuEntry::screen("Display", |_| Box::new(display_menu())),
+        MenuEntry::setting(
+            "Flowgraph",
+            |state: &AppState| state.settings.default_flowgraph.clone().unwrap_or_else(|| "-".to_string()),
+            |state| {
+                let names: Vec<String> = state.flowgraph_names().collect();
+                let selected = names
+                    .iter()
+                    .position(|name| Some(name) == state.settings.default_flowgraph.as_ref())
+                    .unwrap_or(0);
+                Box::new(ChoiceEditor::new("Default flowgraph", names, selected, |state: &mut AppState, index| {
+                    let name = state.flowgraph_names().nth(index);
+                    state.settings.default_flowgraph = name;
+                    state.save_settings();
+                }))
+            },
+        ),
+    ])
+}
+
+fn station_menu() -> MenuScreen<AppState> {
+    MenuScreen::new("Station", vec![
+        MenuEntry::setting(
+            "Callsign",
+            |state: &AppState| state.settings.callsign.clone(),
+            |state| {
+                Box::new(TextEditor::callsign("Callsign", &state.settings.callsign, MAX_CALLSIGN_LEN, |state: &mut AppState, callsign| {
+                    state.settings.callsign = callsign;
+                    state.save_settings();
+                }))
+            },
+        ),
+    ])
+}
+
+fn radio_menu() -> MenuScreen<AppState> {
+    MenuScreen::new("Radio", vec![
+        MenuEntry::setting(
+            "RX frequency",
+            |state: &AppState| format_frequency(state.settings.radio.rx_frequency),
+            |state| Box::new(rx_frequency_entry(state)),
+        ),
+        MenuEntry::setting(
+            "TX frequency",
+            |state: &AppState| {
+                if state.settings.radio.tx_inhibit {
+                    "Off".to_string()
+                } else {
+                    format_frequency(state.settings.radio.tx_frequency)
+                }
+            },
+            |state| {
+                Box::new(FrequencyEntry::new("TX frequency", state.settings.radio.tx_frequency, |state: &mut AppState, hz| {
+                    state.settings.radio.tx_frequency = hz;
+                    state.settings.radio.tx_inhibit = false;
+                    state.settings.radio.leave_channel();
+                    state.save_settings();
+                    Ok(())
+                }))
+            },
+        ),
+        MenuEntry::setting(
+            "Tuning step",
+            |state: &AppState| format_step(state.settings.tuning_step),
+            |state| {
+                let options = TUNING_STEPS.iter().map(|&hz| format_step(hz)).collect();
+                let selected = TUNING_STEPS.iter().position(|&hz| hz == state.settings.tuning_step).unwrap_or(0);
+                Box::new(ChoiceEditor::new("Tuning step", options, selected, |state: &mut AppState, index| {
+                    state.settings.tuning_step = TUNING_STEPS[index];
+                    state.save_settings();
+                }))
+            },
+        ),
+        MenuEntry::setting(
+            "Volume",
+            |state: &AppState| format!("{}%", state.settings.volume),
+            |state| {
+                let editor = NumberEditor::new("Volume", state.settings.volume as i64, 0, 100, 5, |state: &mut AppState, volume| {
+                    state.settings.volume = volume as u8;
+                    state.save_settings();
+                });
+                Box::new(editor.format(|volume| format!("{}%", volume)))
+            },
+        ),
+    ])
+}
+
+fn memories_menu() -> MenuScreen<AppState> {
+    MenuScreen::new("Memories", vec![
+        MenuEntry::setting(
+            "Recall",
+            |state: &AppState| state.settings.radio.channel.clone().unwrap_or_else(|| "-".to_string()),
+            |state| {
+                let selected = state.current_channel().unwrap_or(0);
+                Box::new(ChoiceEditor::new("Recall memory", memory_names(state), selected, |state: &mut AppState, index| {
+                    if state.recall_channel(index) {
+                        state.save_settings();
+                    }
+                }))
+            },
+        ),
+        MenuEntry::screen("Store current", |state: &AppState| {
+            let name = state.settings.radio.channel.clone().unwrap_or_else(|| format!("CH{}", state.memories.len() + 1));
+            Box::new(TextEditor::callsign("Memory name", &name, MAX_CHANNEL_NAME_LEN, |state: &mut AppState, name| {
+                if !name.is_empty() {
+                    state.store_channel(name);
+                }
+            }))
+        }),
+        MenuEntry::screen("Delete", |state: &AppState| {
+            let selected = state.current_channel().unwrap_or(0);
+            Box::new(ChoiceEditor::new("Delete memory", memory_names(state), selected, |state: &mut AppState, index| {
+                state.delete_channel(index);
+            }))
+        }),
+    ])
+}
+
+fn memory_names(state: &AppState) -> Vec<String> {
+    state
+        .memories
+        .channels
+        .iter()
+        .map(|channel| format!("{} {}", channel.label(), format_frequency(channel.rx_frequency)))
+        .collect()
+}
+
+fn display_menu() -> MenuScreen<AppState> {
+    MenuScreen::new("Display", vec![
+        MenuEntry::setting(
+            "Dim after",
+            |state: &AppState| format_timeout(state.settings.dim_timeout),
+            |state| Box::new(timeout_editor("Dim after", state.settings.dim_timeout, |state, seconds| state.settings.dim_timeout = seconds)),
+        ),
+        MenuEntry::setting(
+            "Blank after",
+            |state: &AppState| format_timeout(state.settings.blank_timeout),
+            |state| Box::new(timeout_editor("Blank after", state.settings.blank_timeout, |state, seconds| state.settings.blank_timeout = seconds)),
+        ),
+        MenuEntry::setting(
+            "Theme",
+            |state: &AppState| state.settings.theme.clone(),
+            |state| {
+                let names = Theme::available(Theme::default_dir());
+                let selected = names.iter().position(|name| *name == state.settings.theme).unwrap_or(0);
+                Box::new(ChoiceEditor::new("Theme", names.clone(), selected, move |state: &mut AppState, index| {
+                    state.settings.theme = names[index].clone();
+                    state.save_settings();
+                }))
+            },
+        ),
+    ])
+}
+
+/// Enters the receive frequency, moving the transmit frequency along
+fn rx_frequency_entry(state: &AppState) -> FrequencyEntry<AppState> {
+    FrequencyEntry::new("RX frequency", state.settings.radio.rx_frequency, |state: &mut AppState, hz| {
+        if !state.settings.radio.tune(hz) {
+            return Err(format!("TX out of band at {}", format_offset(state.settings.radio.offset())));
+        }
+        state.save_settings();
+        Ok(())
+    })
+}
+
+/// The tuning step after `hz`, wrapping around to the smallest
+fn next_tuning_step(hz: u32) -> u32 {
+    let index = TUNING_STEPS.iter().position(|&step| step == hz).map_or(0, |index| index + 1);
+    TUNING_STEPS[index % TUNING_STEPS.len()]
+}
+
+fn timeout_editor(title: &str, seconds: u32, set: fn(&mut AppState, u32)) -> ChoiceEditor<AppState> {
+    let options = TIMEOUT_CHOICES.iter().map(|&seconds| format_timeout(seconds)).collect();
+    let selected = TIMEOUT_CHOICES.iter().position(|&choice| choice == seconds).unwrap_or(0);
+    ChoiceEditor::new(title, options, selected, move |state: &mut AppState, index| {
+        set(state, TIMEOUT_CHOICES[index]);
+        state.save_settings();
+    })
+}
+
+fn format_timeout(seconds: u32) -> String {
+    match seconds {
+        0 => "Off".to_string(),
+        seconds if seconds % 60 == 0 => format!("{} min", seconds / 60),
+        seconds => format!("{} s", seconds),
+    }
+}
@@ -0,0 +1,323 @@
+//! User settings persisted in a plain `key = value` file
+//!
+//! ```text
+//! # LinHT settings
+//! callsign = OE3ANC
+//! rx_frequency = 438300000
+//! ```
+//!
+//! Unknown keys and invalid values are skipped with a warning, so a file
+//! written by a newer version still loads.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::power::{DEFAULT_BLANK_TIMEOUT, DEFAULT_DIM_TIMEOUT};
+use crate::radio::{RadioState, DEFAULT_TUNING_STEP, TUNING_STEPS};
+use crate::spectrum::{SpectrumView, REFERENCE_LEVEL_LIMITS, SPAN_DIVISORS};
+
+/// Settings file used when neither `LINHT_CONFIG` nor `HOME` is set
+pub const DEFAULT_SETTINGS_FILE: &str = "linht-gui.conf";
+
+/// Longest callsign accepted, enough for prefixes and suffixes like `OE3/DL1ABC/P`
+pub const MAX_CALLSIGN_LEN: usize = 12;
+
+/// Values the user can change on the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub callsign: String,
+    /// Frequencies and channel restored at startup
+    pub radio: RadioState,
+    /// Up/down tuning step in Hz, one of `TUNING_STEPS`
+    pub tuning_step: u32,
+    /// Audio volume in percent
+    pub volume: u8,
+    /// Seconds without input before the backlight dims, 0 to never dim
+    pub dim_timeout: u32,
+    /// Seconds without input before the display blanks, 0 to never blank
+    pub blank_timeout: u32,
+    /// File name of the flowgraph selected at startup
+    pub default_flowgraph: Option<String>,
+    /// Name of a built-in theme or of a theme file
+    pub theme: String,
+    /// Reference level and span of the spectrum display
+    pub spectrum: SpectrumView,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            callsign: "N0CALL".to_string(),
+            radio: RadioState::default(),
+            tuning_step: DEFAULT_TUNING_STEP,
+            volume: 70,
+            dim_timeout: DEFAULT_DIM_TIMEOUT.as_secs() as u32,
+            blank_timeout: DEFAULT_BLANK_TIMEOUT.as_secs() as u32,
+            default_flowgraph: None,
+            theme: "dark".to_string(),
+            spectrum: SpectrumView::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// `LINHT_CONFIG`, else `~/.config/linht-gui.conf`, else `linht-gui.conf`
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var("LINHT_CONFIG") {
+            return PathBuf::from(path);
+        }
+
+        match std::env::var("HOME") {
+            Ok(home) => Path::new(&home).join(".config").join(DEFAULT_SETTINGS_FILE),
+            Err(_) => PathBuf::from(DEFAULT_SETTINGS_FILE),
+        }
+    }
+
+    /// Reads settings from `path`, using defaults if the file does not exist yet
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Self::parse(&contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses the contents of a settings file on top of the defaults
+    pub fn parse(contents: &str) -> Self {
+        let mut settings = Self::default();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                println!("[Settings] Ignoring line {}: {}", number + 1, line);
+                continue;
+            };
+            if let Err(reason) = settings.set(key.trim(), value.trim()) {
+                println!("[Settings] Ignoring {} on line {}: {}", key.trim(), number + 1, reason);
+            }
+        }
+
+        // Checked once all lines are read, since `tx_inhibit` decides whether the TX frequency counts
+        if !settings.radio.in_band() {
+            println!(
+                "[Settings] Ignoring frequencies outside the bands: RX {} Hz, TX {} Hz",
+                settings.radio.rx_frequency, settings.radio.tx_frequency
+            );
+            settings.radio = RadioState::default();
+        }
+
+        settings
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("invalid number {}", value))
+        }
+
+        match key {
+            "callsign" => {
+                if value.len() > MAX_CALLSIGN_LEN {
+                    return Err(format!("longer than {} characters", MAX_CALLSIGN_LEN));
+                }
+                self.callsign = value.to_ascii_uppercase();
+            }
+            "rx_frequency" => self.radio.rx_frequency = number(value)?,
+            "tx_frequency" => self.radio.tx_frequency = number(value)?,
+            "tx_inhibit" => self.radio.tx_inhibit = value.parse().map_err(|_| format!("invalid flag {}", value))?,
+            "channel" => self.radio.channel = if value.is_empty() { None } else { Some(value.to_string()) },
+            "memory" => self.radio.memory = if value.is_empty() { None } else { Some(number(value)?) },
+            "tuning_step" => {
+                let step = number(value)?;
+                if !TUNING_STEPS.contains(&step) {
+                    return Err(format!("unsupported tuning step {}", step));
+                }
+                self.tuning_step = step;
+            }
+            "volume" => self.volume = number::<u32>(value)?.min(100) as u8,
+            "dim_timeout" => self.dim_timeout = number(value)?,
+            "blank_timeout" => self.blank_timeout = number(value)?,
+            "default_flowgraph" => {
+                self.default_flowgraph = if value.is_empty() { None } else { Some(value.to_string()) };
+            }
+            "theme" if value.is_empty() => return Err("empty theme name".to_string()),
+            "theme" => self.theme = value.to_string(),
+            "spectrum_reference_level" => {
+                let (low, high) = REFERENCE_LEVEL_LIMITS;
+                self.spectrum.reference_level = number::<i32>(value)?.clamp(low, high);
+            }
+            "spectrum_span" => {
+                let span = number(value)?;
+                if !SPAN_DIVISORS.contains(&span) {
+                    return Err(format!("unsupported span divisor {}", span));
+                }
+                self.spectrum.span = span;
+            }
+            _ => return Err("unknown setting".to_string()),
+        }
+        Ok(())
+    }
+
+    /// The settings in the format read by `parse`
+    pub fn to_file_contents(&self) -> String {
+        format!(
+            "# LinHT settings, rewritten when changed on the device\n\
+             callsign = {}\n\
+             rx_frequency = {}\n\
+             tx_frequency = {}\n\
+             tx_inhibit = {}\n\
+             channel = {}\n\
+             memory = {}\n\
+             tuning_step = {}\n\
+             volume = {}\n\
+             dim_timeout = {}\n\
+             blank_timeout = {}\n\
+             default_flowgraph = {}\n\
+             theme = {}\n\
+             spectrum_reference_level = {}\n\
+             spectrum_span = {}\n",
+            self.callsign,
+            self.radio.rx_frequency,
+            self.radio.tx_frequency,
+            self.radio.tx_inhibit,
+            self.radio.channel.as_deref().unwrap_or(""),
+            self.radio.memory.map_or(String::new(), |location| location.to_string()),
+            self.tuning_step,
+            self.volume,
+            self.dim_timeout,
+            self.blank_timeout,
+            self.default_flowgraph.as_deref().unwrap_or(""),
+            self.theme,
+            self.spectrum.reference_level,
+            self.spectrum.span,
+        )
+    }
+
+    /// Writes the settings to `path`, creating its directory if needed
+    ///
+    /// The file is replaced in one step, so losing power while saving keeps the old one.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_file_contents())?;
+        fs::rename(&temporary, path)
+    }
+
+    pub fn dim_after(&self) -> Option<Duration> {
+        timeout(self.dim_timeout)
+    }
+
+    pub fn blank_after(&self) -> Option<Duration> {
+        timeout(self.blank_timeout)
+    }
+}
+
+fn timeout(seconds: u32) -> Option<Duration> {
+    match seconds {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds as u64)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changed() -> Settings {
+        let mut settings = Settings {
+            callsign: "OE3ANC".to_string(),
+            tuning_step: TUNING_STEPS[0],
+            volume: 35,
+            dim_timeout: 0,
+            blank_timeout: 300,
+            default_flowgraph: Some("som_m17.py".to_string()),
+            theme: "sunlight".to_string(),
+            spectrum: SpectrumView { reference_level: -40, span: 4 },
+            ..Settings::default()
+        };
+        settings.radio = RadioState::simplex(145_500_000);
+        settings.radio.tx_inhibit = true;
+        settings.radio.channel = Some("S20".to_string());
+        settings.radio.memory = Some(2);
+        settings
+    }
+
+    #[test]
+    fn file_contents_round_trip() {
+        assert_eq!(Settings::parse(&Settings::default().to_file_contents()), Settings::default());
+        assert_eq!(Settings::parse(&changed().to_file_contents()), changed());
+    }
+
+    #[test]
+    fn invalid_lines_keep_the_defaults() {
+        let settings = Settings::parse(
+            "# comment\n\
+             no equals sign\n\
+             future_setting = 1\n\
+             volume = loud\n\
+             tuning_step = 7000\n\
+             spectrum_span = 3\n\
+             theme =\n\
+             callsign = oe3xyz/portable\n\
+             rx_frequency = 145500000\n",
+        );
+
+        let expected = Settings {
+            radio: RadioState { rx_frequency: 145_500_000, ..RadioState::default() },
+            ..Settings::default()
+        };
+        assert_eq!(settings, expected);
+    }
+
+    #[test]
+    fn values_are_normalized() {
+        let settings = Settings::parse("callsign = oe3anc\nvolume = 250\nspectrum_reference_level = 500\nchannel =\n");
+        assert_eq!(settings.callsign, "OE3ANC");
+        assert_eq!(settings.volume, 100);
+        assert_eq!(Settings::parse("volume = 300\n").volume, 100);
+        assert_eq!(Settings::parse("volume = -5\n").volume, Settings::default().volume);
+        assert_eq!(settings.spectrum.reference_level, REFERENCE_LEVEL_LIMITS.1);
+        assert_eq!(settings.radio.channel, None);
+    }
+
+    #[test]
+    fn frequencies_outside_the_bands_fall_back_to_the_defaults() {
+        let settings = Settings::parse("rx_frequency = 27065000\ntx_frequency = 27065000\nchannel = CB9\nvolume = 20\n");
+        assert_eq!(settings.radio, RadioState::default());
+        assert_eq!(settings.volume, 20);
+
+        let settings = Settings::parse("rx_frequency = 145500000\ntx_frequency = 4000000000\n");
+        assert_eq!(settings.radio, RadioState::default());
+
+        // A receive-only channel may keep a transmit frequency the radio never uses
+        let settings = Settings::parse("rx_frequency = 145500000\ntx_frequency = 0\ntx_inhibit = true\n");
+        assert_eq!((settings.radio.rx_frequency, settings.radio.tx_inhibit), (145_500_000, true));
+    }
+
+    #[test]
+    fn saves_and_loads_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join(DEFAULT_SETTINGS_FILE);
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+
+        changed().save(&path).unwrap();
+        assert_eq!(Settings::load(&path).unwrap(), changed());
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn zero_timeouts_disable_dimming_and_blanking() {
+        let settings = changed();
+        assert_eq!(settings.dim_after(), None);
+        assert_eq!(settings.blank_after(), Some(Duration::from_secs(300)));
+    }
+}
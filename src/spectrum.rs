@@ -0,0 +1,220 @@
+//! Spectrum of the band around the receive frequency, sent by the running flowgraph
+//!
+//! A flowgraph sends every FFT as one UDP datagram to `127.0.0.1:17006`, or to
+//! the address in `LINHT_SPECTRUM_ADDR`: the magnitude of each bin in dB as a
+//! little-endian `f32`, lowest frequency first. In GNU Radio that is an FFT
+//! with shift, `complex_to_mag_squared` and `nlog10_ff(10, ...)` into a
+//! `network.udp_sink` for `float` vectors with no header and a payload of one
+//! vector.
+//!
+//! UDP rather than the ZeroMQ sockets `som_m17_ptt.py` uses keeps a ZeroMQ
+//! library off the device; a dropped datagram only skips a waterfall line.
+
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+
+/// Address listened on unless `LINHT_SPECTRUM_ADDR` names another one
+pub const DEFAULT_SPECTRUM_ADDRESS: &str = "127.0.0.1:17006";
+
+/// Most bins a datagram can carry
+pub const MAX_SPECTRUM_BINS: usize = 65_507 / 4;
+
+/// Levels shown between the top and the bottom of the spectrum, in dB
+pub const SPECTRUM_RANGE_DB: i32 = 80;
+
+/// Reference level change per key press, in dB
+pub const REFERENCE_LEVEL_STEP_DB: i32 = 10;
+
+/// Lowest and highest reference level, in dB
+pub const REFERENCE_LEVEL_LIMITS: (i32, i32) = (-120, 40);
+
+/// Span as a fraction of the bandwidth the flowgraph sends: 1/1, 1/2, 1/4 and 1/8
+pub const SPAN_DIVISORS: [u32; 4] = [1, 2, 4, 8];
+
+/// Part of the spectrum shown and its scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpectrumView {
+    /// Level at the top of the spectrum in dB
+    pub reference_level: i32,
+    /// Shows the middle 1/`span` of the bins, one of `SPAN_DIVISORS`
+    pub span: u32,
+}
+
+impl Default for SpectrumView {
+    fn default() -> Self {
+        SpectrumView {
+            reference_level: -20,
+            span: 1,
+        }
+    }
+}
+
+impl SpectrumView {
+    /// Moves the reference level a step up or down, returning false at the limit
+    pub fn step_reference_level(&mut self, up: bool) -> bool {
+        let (low, high) = REFERENCE_LEVEL_LIMITS;
+        let step = if up { REFERENCE_LEVEL_STEP_DB } else { -REFERENCE_LEVEL_STEP_DB };
+        let level = (self.reference_level + step).clamp(low, high);
+        let changed = level != self.reference_level;
+        self.reference_level = level;
+        changed
+    }
+
+    /// Narrows the span to the next divisor, going back to the full bandwidth after the narrowest
+    pub fn next_span(&mut self) {
+        let index = SPAN_DIVISORS.iter().position(|&span| span == self.span).map_or(0, |index| index + 1);
+        self.span = SPAN_DIVISORS[index % SPAN_DIVISORS.len()];
+    }
+
+    /// The bins within the span, centered on the receive frequency
+    pub fn visible<'a>(&self, bins: &'a [f32]) -> &'a [f32] {
+        let count = (bins.len() / self.span.max(1) as usize).max(1).min(bins.len());
+        let start = (bins.len() - count) / 2;
+        &bins[start..start + count]
+    }
+
+    /// Where `db` lies between the bottom (0.0) and the top (1.0) of the spectrum
+    pub fn level(&self, db: f32) -> f32 {
+        let bottom = (self.reference_level - SPECTRUM_RANGE_DB) as f32;
+        ((db - bottom) / SPECTRUM_RANGE_DB as f32).clamp(0.0, 1.0)
+    }
+
+    /// `Ref -20 dB`
+    pub fn reference_label(&self) -> String {
+        format!("Ref {} dB", self.reference_level)
+    }
+
+    /// `Span 1/4`, or `Full span`
+    pub fn span_label(&self) -> String {
+        match self.span {
+            0 | 1 => "Full span".to_string(),
+            span => format!("Span 1/{}", span),
+        }
+    }
+}
+
+/// Reads the bins of one datagram, or `None` if it is not a whole number of `f32`s
+///
+/// Bins that are not finite, like the `-inf` of an empty bin, become very low levels.
+pub fn parse_spectrum(datagram: &[u8]) -> Option<Vec<f32>> {
+    if datagram.is_empty() || !datagram.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let bins = datagram
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .map(|db| if db.is_finite() { db } else { -1000.0 })
+        .collect();
+    Some(bins)
+}
+
+/// Socket flowgraphs send their spectrum to
+pub struct SpectrumReceiver {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl SpectrumReceiver {
+    /// Listens on `address` without blocking
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, buffer: vec![0; MAX_SPECTRUM_BINS * 4] })
+    }
+
+    /// `LINHT_SPECTRUM_ADDR`, else `DEFAULT_SPECTRUM_ADDRESS`
+    pub fn default_address() -> String {
+        std::env::var("LINHT_SPECTRUM_ADDR").unwrap_or_else(|_| DEFAULT_SPECTRUM_ADDRESS.to_string())
+    }
+
+    /// Returns the newest spectrum received since the last call, dropping older ones
+    pub fn receive(&mut self) -> Option<Vec<f32>> {
+        let mut newest = None;
+        loop {
+            match self.socket.recv(&mut self.buffer) {
+                Ok(length) => {
+                    if let Some(bins) = parse_spectrum(&self.buffer[..length]) {
+                        newest = Some(bins);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    println!("[Spectrum] Receive failed: {}", e);
+                    break;
+                }
+            }
+        }
+        newest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datagram(bins: &[f32]) -> Vec<u8> {
+        bins.iter().flat_map(|db| db.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn parses_little_endian_bins() {
+        let bins = parse_spectrum(&datagram(&[-90.5, f32::NEG_INFINITY, -12.0, f32::NAN])).unwrap();
+        assert_eq!(bins, [-90.5, -1000.0, -12.0, -1000.0]);
+
+        assert_eq!(parse_spectrum(&[]), None);
+        assert_eq!(parse_spectrum(&[0; 6]), None);
+    }
+
+    #[test]
+    fn narrower_spans_keep_the_middle_bins() {
+        let bins: Vec<f32> = (0..16).map(|bin| bin as f32).collect();
+        let mut view = SpectrumView::default();
+        assert_eq!(view.visible(&bins), &bins[..]);
+        assert_eq!(view.span_label(), "Full span");
+
+        view.next_span();
+        assert_eq!(view.visible(&bins), &bins[4..12]);
+        view.next_span();
+        assert_eq!(view.visible(&bins), &bins[6..10]);
+        view.next_span();
+        assert_eq!(view.visible(&bins), &bins[7..9]);
+        assert_eq!(view.span_label(), "Span 1/8");
+        assert_eq!(view.visible(&bins[..4]), &[1.0]);
+        assert!(view.visible(&[]).is_empty());
+
+        view.next_span();
+        assert_eq!(view.span, 1);
+    }
+
+    #[test]
+    fn reference_level_stops_at_the_limits() {
+        let mut view = SpectrumView::default();
+        assert_eq!(view.level(-20.0), 1.0);
+        assert_eq!(view.level(-60.0), 0.5);
+        assert_eq!(view.level(-150.0), 0.0);
+
+        assert!(view.step_reference_level(false));
+        assert_eq!(view.reference_label(), "Ref -30 dB");
+
+        while view.step_reference_level(true) {}
+        assert_eq!(view.reference_level, REFERENCE_LEVEL_LIMITS.1);
+        while view.step_reference_level(false) {}
+        assert_eq!(view.reference_level, REFERENCE_LEVEL_LIMITS.0);
+    }
+
+    #[test]
+    fn receives_the_newest_spectrum() {
+        let mut receiver = SpectrumReceiver::bind("127.0.0.1:0").unwrap();
+        assert_eq!(receiver.receive(), None);
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = receiver.socket.local_addr().unwrap();
+        sender.send_to(&datagram(&[-80.0, -70.0]), address).unwrap();
+        sender.send_to(&[1, 2, 3], address).unwrap();
+        sender.send_to(&datagram(&[-60.0, -50.0]), address).unwrap();
+
+        assert_eq!(receiver.receive(), Some(vec![-60.0, -50.0]));
+        assert_eq!(receiver.receive(), None);
+    }
+}
@@ -0,0 +1,244 @@
+//! What the status bar shows next to its text: clock, battery, signal strength,
+//! transmit state and whether the flowgraph runs
+//!
+//! Flowgraphs report signal strength and PTT by printing lines like these:
+//!
+//! ```text
+//! RSSI: -93.5
+//! PTT: 1
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Directory the kernel lists batteries and chargers in
+pub const POWER_SUPPLY_CLASS_DIR: &str = "/sys/class/power_supply";
+
+/// How long a battery reading is reused before sysfs is read again
+pub const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Signal strength shown as an empty meter (S0)
+pub const RSSI_FLOOR_DBM: f32 = -127.0;
+
+/// Signal strength shown as a full meter (S9+20 dB)
+pub const RSSI_CEILING_DBM: f32 = -53.0;
+
+/// Wall clock time in the local time zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockTime {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl ClockTime {
+    /// The current local time, or `None` if the system cannot tell
+    pub fn now() -> Option<Self> {
+        // SAFETY: `time` and `localtime_r` only write to the locals passed in
+        unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut local: libc::tm = std::mem::zeroed();
+            if libc::localtime_r(&now, &mut local).is_null() {
+                return None;
+            }
+            Some(ClockTime {
+                hour: local.tm_hour as u8,
+                minute: local.tm_min as u8,
+            })
+        }
+    }
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Charge of the battery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryLevel {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+/// Battery read through sysfs, e.g. `/sys/class/power_supply/BAT0`
+pub struct Battery {
+    path: PathBuf,
+    /// When the battery was last read and the reading, or why it failed
+    last: Option<(Instant, Result<BatteryLevel, io::ErrorKind>)>,
+}
+
+impl Battery {
+    /// Opens the power supply directory at `path`, which must report a capacity
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        fs::read_to_string(path.join("capacity"))?;
+        Ok(Self { path, last: None })
+    }
+
+    /// Opens the first battery in `/sys/class/power_supply`, if there is one
+    pub fn discover() -> Option<Self> {
+        let mut devices: Vec<PathBuf> = fs::read_dir(POWER_SUPPLY_CLASS_DIR)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == "Battery"))
+            .collect();
+        devices.sort();
+
+        devices.into_iter().find_map(|path| Self::open(path).ok())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the charge, at most once per `BATTERY_POLL_INTERVAL`
+    ///
+    /// A failed read is not retried before the interval is over either.
+    pub fn level(&mut self) -> io::Result<BatteryLevel> {
+        if let Some((read_at, last)) = self.last {
+            if read_at.elapsed() < BATTERY_POLL_INTERVAL {
+                return last.map_err(io::Error::from);
+            }
+        }
+
+        let level = self.read();
+        self.last = Some((Instant::now(), level.as_ref().copied().map_err(io::Error::kind)));
+        level
+    }
+
+    fn read(&self) -> io::Result<BatteryLevel> {
+        let percent: u8 = fs::read_to_string(self.path.join("capacity"))?
+            .trim()
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let charging = fs::read_to_string(self.path.join("status")).is_ok_and(|status| status.trim() == "Charging");
+
+        Ok(BatteryLevel { percent: percent.min(100), charging })
+    }
+}
+
+/// Whether the radio listens or transmits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Activity {
+    /// No flowgraph running
+    #[default]
+    Idle,
+    Receiving,
+    /// PTT pressed
+    Transmitting,
+}
+
+/// Lifecycle of the selected flowgraph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlowgraphState {
+    #[default]
+    Stopped,
+    Running,
+    /// Failed to start or exited on its own
+    Failed,
+}
+
+/// Everything the status bar shows besides its text
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StatusIndicators {
+    pub clock: Option<ClockTime>,
+    pub battery: Option<BatteryLevel>,
+    /// Signal strength from 0.0 to 1.0, `None` hides the meter
+    pub signal: Option<f32>,
+    pub activity: Activity,
+    pub flowgraph: FlowgraphState,
+}
+
+/// Maps a signal strength in dBm onto the 0.0 to 1.0 range of the signal meter
+pub fn signal_level(rssi_dbm: f32) -> f32 {
+    ((rssi_dbm - RSSI_FLOOR_DBM) / (RSSI_CEILING_DBM - RSSI_FLOOR_DBM)).clamp(0.0, 1.0)
+}
+
+/// A line printed by a flowgraph about the state of the radio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RadioReport {
+    /// Received signal strength in dBm
+    Rssi(f32),
+    Ptt(bool),
+}
+
+/// Parses `RSSI: <dBm>` and `PTT: <0|1>` lines
+pub fn parse_radio_report(line: &str) -> Option<RadioReport> {
+    let (key, value) = line.split_once(':')?;
+    let value = value.trim();
+    match key.trim() {
+        "RSSI" => value.parse().ok().filter(|dbm: &f32| dbm.is_finite()).map(RadioReport::Rssi),
+        "PTT" => match value {
+            "1" | "on" => Some(RadioReport::Ptt(true)),
+            "0" | "off" => Some(RadioReport::Ptt(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_radio_reports() {
+        assert_eq!(parse_radio_report("RSSI: -93.5"), Some(RadioReport::Rssi(-93.5)));
+        assert_eq!(parse_radio_report("  RSSI :-120"), Some(RadioReport::Rssi(-120.0)));
+        assert_eq!(parse_radio_report("PTT: 1"), Some(RadioReport::Ptt(true)));
+        assert_eq!(parse_radio_report("PTT: off"), Some(RadioReport::Ptt(false)));
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        for line in ["RSSI: -inf", "RSSI: NaN", "RSSI: strong", "PTT: 2", "PTT", "LSF: SRC: OE3ANC", ""] {
+            assert_eq!(parse_radio_report(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn signal_level_spans_s0_to_s9_plus_20() {
+        assert_eq!(signal_level(RSSI_FLOOR_DBM - 10.0), 0.0);
+        assert_eq!(signal_level(RSSI_CEILING_DBM + 10.0), 1.0);
+        assert!((signal_level(-90.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reads_battery_from_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Battery::open(dir.path()).is_err());
+
+        fs::write(dir.path().join("capacity"), "104\n").unwrap();
+        fs::write(dir.path().join("status"), "Charging\n").unwrap();
+        let mut battery = Battery::open(dir.path()).unwrap();
+        assert_eq!(battery.level().unwrap(), BatteryLevel { percent: 100, charging: true });
+
+        // Within the poll interval the first reading is reused
+        fs::write(dir.path().join("capacity"), "50\n").unwrap();
+        assert_eq!(battery.level().unwrap().percent, 100);
+    }
+
+    #[test]
+    fn failed_battery_reads_are_not_retried_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("capacity"), "unknown\n").unwrap();
+        let mut battery = Battery::open(dir.path()).unwrap();
+        assert_eq!(battery.level().unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        fs::write(dir.path().join("capacity"), "80\n").unwrap();
+        assert_eq!(battery.level().unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        battery.last = battery.last.map(|(_, last)| (Instant::now() - BATTERY_POLL_INTERVAL, last));
+        assert_eq!(battery.level().unwrap().percent, 80);
+    }
+
+    #[test]
+    fn clock_shows_two_digits() {
+        assert_eq!(ClockTime { hour: 7, minute: 5 }.to_string(), "07:05");
+    }
+}
@@ -0,0 +1,310 @@
+//! Color themes: named roles instead of colors spread through the screens
+//!
+//! Besides the built-in themes, a theme can be loaded from a file in the same
+//! `key = value` format as the settings. It starts from a built-in theme and
+//! overrides some roles with `#RRGGBB` or `#RRGGBBAA` colors:
+//!
+//! ```text
+//! base = dark
+//! accent = #FFB000
+//! rx = #FFB000
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::framebuffer::Color;
+
+/// Directory searched for `<name>.theme` files, relative to the binary or the working directory
+pub const DEFAULT_THEME_DIR: &str = "themes";
+
+/// Extension of theme files
+pub const THEME_EXTENSION: &str = "theme";
+
+/// Names of the themes compiled into the binary
+pub const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "sunlight"];
+
+/// Colors of the UI by the role they play
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Behind everything else
+    pub background: Color,
+    /// Regular text
+    pub foreground: Color,
+    /// Captions and hints that should not draw attention
+    pub muted: Color,
+    /// Titles and branding
+    pub accent: Color,
+    /// Receive frequency
+    pub rx: Color,
+    /// Transmit frequency
+    pub tx: Color,
+    /// Popup titles and warnings
+    pub warning: Color,
+    /// Status bar background
+    pub status_bar: Color,
+    /// Status bar text
+    pub status_text: Color,
+    /// Separator lines and frames
+    pub border: Color,
+    /// Background of popups
+    pub surface: Color,
+    /// Background of the selected list row
+    pub selection: Color,
+    /// Text of the selected list row
+    pub selection_text: Color,
+}
+
+impl Theme {
+    /// Light text on black, the default
+    pub fn dark() -> Self {
+        Theme {
+            name: "dark".to_string(),
+            background: Color::black(),
+            foreground: Color::white(),
+            muted: Color::new(149, 165, 166, 255),
+            accent: Color::green(),
+            rx: Color::green(),
+            tx: Color::new(241, 196, 15, 255),
+            warning: Color::new(241, 196, 15, 255),
+            status_bar: Color::new(30, 30, 30, 255),
+            status_text: Color::white(),
+            border: Color::new(123, 123, 123, 255),
+            surface: Color::new(30, 30, 30, 235),
+            selection: Color::new(46, 125, 50, 255),
+            selection_text: Color::white(),
+        }
+    }
+
+    /// Dark text on a light gray background
+    pub fn light() -> Self {
+        Theme {
+            name: "light".to_string(),
+            background: Color::new(236, 239, 241, 255),
+            foreground: Color::new(33, 33, 33, 255),
+            muted: Color::new(96, 110, 118, 255),
+            accent: Color::new(27, 125, 50, 255),
+            rx: Color::new(27, 125, 50, 255),
+            tx: Color::new(191, 94, 0, 255),
+            warning: Color::new(191, 94, 0, 255),
+            status_bar: Color::new(55, 71, 79, 255),
+            status_text: Color::white(),
+            border: Color::new(144, 164, 174, 255),
+            surface: Color::new(255, 255, 255, 240),
+            selection: Color::new(27, 125, 50, 255),
+            selection_text: Color::white(),
+        }
+    }
+
+    /// Black on white with saturated colors only, readable in direct sunlight
+    pub fn sunlight() -> Self {
+        Theme {
+            name: "sunlight".to_string(),
+            background: Color::white(),
+            foreground: Color::black(),
+            muted: Color::black(),
+            accent: Color::black(),
+            rx: Color::new(0, 100, 0, 255),
+            tx: Color::new(180, 0, 0, 255),
+            warning: Color::new(180, 0, 0, 255),
+            status_bar: Color::black(),
+            status_text: Color::white(),
+            border: Color::black(),
+            surface: Color::white(),
+            selection: Color::black(),
+            selection_text: Color::white(),
+        }
+    }
+
+    /// Returns the built-in theme called `name`
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "sunlight" | "high-contrast" => Some(Self::sunlight()),
+            _ => None,
+        }
+    }
+
+    /// Returns the built-in theme `name`, else loads `<dir>/<name>.theme`
+    pub fn named<P: AsRef<Path>>(name: &str, dir: P) -> io::Result<Self> {
+        match Self::builtin(name) {
+            Some(theme) => Ok(theme),
+            None => Self::load(dir.as_ref().join(name).with_extension(THEME_EXTENSION)),
+        }
+    }
+
+    /// Names of the built-in themes followed by those of the theme files in `dir`
+    pub fn available<P: AsRef<Path>>(dir: P) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|extension| extension == THEME_EXTENSION))
+                    .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+                    .filter(|name| Self::builtin(name).is_none())
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+
+        BUILTIN_THEMES.iter().map(|name| name.to_string()).chain(files).collect()
+    }
+
+    /// Reads a theme file, named after the file without its extension
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        let mut theme = Self::parse(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(stem) = path.file_stem() {
+            theme.name = stem.to_string_lossy().to_string();
+        }
+        Ok(theme)
+    }
+
+    /// Parses a theme file; unknown roles and invalid colors are skipped with a warning
+    ///
+    /// Fails only if `base` names a theme that is not built in.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let entries: Vec<(usize, &str, &str)> = contents
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|(number, line)| match line.split_once('=') {
+                Some((key, value)) => Some((number, key.trim(), value.trim())),
+                None => {
+                    println!("[Theme] Ignoring line {}: {}", number, line);
+                    None
+                }
+            })
+            .collect();
+
+        let base = entries
+            .iter()
+            .find(|(_, key, _)| *key == "base")
+            .map_or("dark", |(_, _, value)| value);
+        let mut theme = Self::builtin(base).ok_or_else(|| format!("unknown base theme {}", base))?;
+
+        for (number, key, value) in entries {
+            if key == "base" {
+                continue;
+            }
+            let Some(color) = parse_color(value) else {
+                println!("[Theme] Ignoring {} on line {}: invalid color {}", key, number, value);
+                continue;
+            };
+            match theme.role_mut(key) {
+                Some(role) => *role = color,
+                None => println!("[Theme] Ignoring {} on line {}: unknown role", key, number),
+            }
+        }
+
+        Ok(theme)
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Color> {
+        Some(match role {
+            "background" => &mut self.background,
+            "foreground" => &mut self.foreground,
+            "muted" => &mut self.muted,
+            "accent" => &mut self.accent,
+            "rx" => &mut self.rx,
+            "tx" => &mut self.tx,
+            "warning" => &mut self.warning,
+            "status_bar" => &mut self.status_bar,
+            "status_text" => &mut self.status_text,
+            "border" => &mut self.border,
+            "surface" => &mut self.surface,
+            "selection" => &mut self.selection,
+            "selection_text" => &mut self.selection_text,
+            _ => return None,
+        })
+    }
+
+    /// `LINHT_THEME_DIR`, else `themes/` next to the binary, else `themes/`
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var("LINHT_THEME_DIR") {
+            return PathBuf::from(dir);
+        }
+
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_THEME_DIR)))
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_THEME_DIR))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Parses `#RRGGBB` or `#RRGGBBAA`
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::new(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_roles_of_the_base_theme() {
+        let theme = Theme::parse("# Test\nbase = light\naccent = #102030\nsurface = #405060C0\n").unwrap();
+
+        let expected = Theme {
+            accent: Color::new(0x10, 0x20, 0x30, 255),
+            surface: Color::new(0x40, 0x50, 0x60, 0xC0),
+            ..Theme::light()
+        };
+        assert_eq!(theme, expected);
+    }
+
+    #[test]
+    fn skips_invalid_lines() {
+        let theme = Theme::parse("tx = red\nrx = #12345\nsparkle = #FFFFFF\nno color here\nwarning = #ÄÄ0000\n").unwrap();
+        assert_eq!(theme, Theme::dark());
+    }
+
+    #[test]
+    fn refuses_an_unknown_base() {
+        assert!(Theme::parse("base = neon\n").is_err());
+        assert_eq!(Theme::parse("base = high-contrast\n").unwrap(), Theme::sunlight());
+    }
+
+    #[test]
+    fn loads_theme_files_by_name() {
+        let theme = Theme::named("amber", DEFAULT_THEME_DIR).unwrap();
+        assert_eq!(theme.name, "amber");
+        assert_eq!(theme.accent, Color::new(0xFF, 0xB0, 0x00, 255));
+        assert_eq!(theme.background, Theme::dark().background);
+
+        assert_eq!(Theme::named("light", "/nonexistent").unwrap(), Theme::light());
+        assert!(Theme::named("missing", DEFAULT_THEME_DIR).is_err());
+    }
+
+    #[test]
+    fn lists_builtin_themes_before_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["zebra.theme", "dark.theme", "notes.txt", "aqua.theme"] {
+            fs::write(dir.path().join(name), "accent = #00FFFF\n").unwrap();
+        }
+
+        let mut expected: Vec<String> = BUILTIN_THEMES.iter().map(|name| name.to_string()).collect();
+        expected.extend(["aqua".to_string(), "zebra".to_string()]);
+        assert_eq!(Theme::available(dir.path()), expected);
+    }
+}
@@ -0,0 +1,453 @@
+//! Retained widgets laid out in containers and redrawn only when they change
+//!
+//! A `Ui` owns a tree of widgets. Screens are built once by adding widgets to
+//! containers; afterwards `Ui::update` changes individual widgets and
+//! `Ui::render` lays the tree out and redraws just the widgets that changed
+//! or moved.
+
+use std::any::Any;
+
+use crate::framebuffer::{Color, Framebuffer, Rect};
+
+mod container;
+mod icon;
+mod indicators;
+mod list;
+mod text;
+
+pub use container::{Container, Direction, Insets};
+pub use icon::Icon;
+pub use indicators::{Divider, Meter, ProgressBar};
+pub use list::List;
+pub use text::{Label, ValueReadout};
+
+/// Width and height in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Size {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Size {
+    pub const fn new(width: u16, height: u16) -> Self {
+        Self { width, height }
+    }
+}
+
+/// Something that can be measured and drawn inside a `Ui`
+pub trait Widget: AsAny {
+    /// Size the widget would like to have when at most `available` is free
+    fn measure(&self, fb: &Framebuffer, available: Size) -> Size;
+
+    /// Draws the widget into `bounds`
+    ///
+    /// The area has been cleared to the background and drawing is clipped to it.
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect);
+}
+
+/// Downcasting support for `Widget` trait objects, implemented for every type
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Handle of a widget inside a `Ui`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidgetId(usize);
+
+/// How a child shares the main axis of its container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Sizing {
+    /// As large as the widget measures
+    #[default]
+    Natural,
+    /// Exactly this many pixels
+    Fixed(u16),
+    /// A share of the space left over by the other children
+    Flex(u16),
+}
+
+struct Node {
+    widget: Box<dyn Widget>,
+    children: Vec<WidgetId>,
+    sizing: Sizing,
+    bounds: Rect,
+    /// Needs to be drawn again on the next render
+    dirty: bool,
+}
+
+/// A tree of widgets rooted in a container
+pub struct Ui {
+    nodes: Vec<Node>,
+    /// Color behind containers without a background of their own
+    background: Color,
+    /// Area of the last layout
+    area: Option<Rect>,
+    needs_layout: bool,
+}
+
+impl Ui {
+    /// Creates a tree whose root is `root`
+    pub fn new(root: Container) -> Self {
+        Self {
+            nodes: vec![Node {
+                widget: Box::new(root),
+                children: Vec::new(),
+                sizing: Sizing::Natural,
+                bounds: Rect::new(0, 0, 0, 0),
+                dirty: true,
+            }],
+            background: Color::black(),
+            area: None,
+            needs_layout: true,
+        }
+    }
+
+    pub fn root(&self) -> WidgetId {
+        WidgetId(0)
+    }
+
+    /// Sets the color shown where no container paints a background
+    pub fn set_background(&mut self, color: Color) {
+        if self.background != color {
+            self.background = color;
+            self.invalidate();
+        }
+    }
+
+    /// Appends `widget` to the children of `parent`, which must be a `Container`
+    pub fn add<W: Widget>(&mut self, parent: WidgetId, widget: W) -> WidgetId {
+        assert!(self.get::<Container>(parent).is_some(), "widgets can only be added to containers");
+
+        let id = WidgetId(self.nodes.len());
+        self.nodes.push(Node {
+            widget: Box::new(widget),
+            children: Vec::new(),
+            sizing: Sizing::Natural,
+            bounds: Rect::new(0, 0, 0, 0),
+            dirty: true,
+        });
+        self.nodes[parent.0].children.push(id);
+        self.nodes[parent.0].dirty = true;
+        self.needs_layout = true;
+        id
+    }
+
+    /// Gives the widget a fixed length along its container's direction
+    pub fn set_fixed(&mut self, id: WidgetId, length: u16) {
+        self.set_sizing(id, Sizing::Fixed(length));
+    }
+
+    /// Lets the widget take `weight` shares of the space its siblings leave free
+    pub fn set_flex(&mut self, id: WidgetId, weight: u16) {
+        self.set_sizing(id, Sizing::Flex(weight));
+    }
+
+    fn set_sizing(&mut self, id: WidgetId, sizing: Sizing) {
+        if self.nodes[id.0].sizing != sizing {
+            self.nodes[id.0].sizing = sizing;
+            self.needs_layout = true;
+        }
+    }
+
+    /// Returns the widget if it is of type `W`
+    pub fn get<W: Widget>(&self, id: WidgetId) -> Option<&W> {
+        let node = self.nodes.get(id.0)?;
+        (*node.widget).as_any().downcast_ref()
+    }
+
+    /// Changes a widget of type `W`, scheduling a redraw only if it ends up different
+    ///
+    /// Returns false if `id` is not a `W`.
+    pub fn update<W: Widget + Clone + PartialEq>(&mut self, id: WidgetId, change: impl FnOnce(&mut W)) -> bool {
+        let Some(widget) = self.nodes.get_mut(id.0).and_then(|node| (*node.widget).as_any_mut().downcast_mut::<W>()) else {
+            return false;
+        };
+
+        let before = widget.clone();
+        change(widget);
+        if *widget != before {
+            self.nodes[id.0].dirty = true;
+            self.needs_layout = true;
+        }
+        true
+    }
+
+    /// Area the widget was given by the last layout
+    pub fn bounds(&self, id: WidgetId) -> Rect {
+        self.nodes[id.0].bounds
+    }
+
+    /// Redraws everything on the next render, e.g. after something else drew over the area
+    pub fn invalidate(&mut self) {
+        self.nodes[0].dirty = true;
+        self.needs_layout = true;
+    }
+
+    /// Lays the tree out inside `area` and draws whatever changed since the last render
+    ///
+    /// Returns true if anything was drawn. The framebuffer is not flushed.
+    pub fn render(&mut self, fb: &mut Framebuffer, area: Rect) -> bool {
+        if self.area != Some(area) {
+            self.area = Some(area);
+            self.invalidate();
+        }
+
+        if self.needs_layout {
+            self.needs_layout = false;
+            self.nodes[0].bounds = area;
+            self.layout_children(fb, WidgetId(0));
+        }
+
+        self.draw_changed(fb, WidgetId(0), self.background)
+    }
+
+    /// Measures a node, adding up the children of containers
+    fn measure(&self, fb: &Framebuffer, id: WidgetId, available: Size) -> Size {
+        let node = &self.nodes[id.0];
+        let Some(container) = (*node.widget).as_any().downcast_ref::<Container>() else {
+            return node.widget.measure(fb, available);
+        };
+
+        let padding = container.padding;
+        let inner = Size::new(
+            available.width.saturating_sub(padding.horizontal()),
+            available.height.saturating_sub(padding.vertical()),
+        );
+
+        let mut main = 0u32;
+        let mut cross = 0u16;
+        for &child in &node.children {
+            let size = self.measure(fb, child, inner);
+            let (child_main, child_cross) = container.direction.split(size);
+            main += match self.nodes[child.0].sizing {
+                Sizing::Fixed(length) => length as u32,
+                _ => child_main as u32,
+            };
+            cross = cross.max(child_cross);
+        }
+        main += container.spacing as u32 * node.children.len().saturating_sub(1) as u32;
+
+        let main = main.min(u16::MAX as u32) as u16;
+        let size = container.direction.join(main, cross);
+        Size::new(
+            size.width.saturating_add(padding.horizontal()),
+            size.height.saturating_add(padding.vertical()),
+        )
+    }
+
+    /// Assigns bounds to the children of a container and marks moved ones for redrawing
+    fn layout_children(&mut self, fb: &Framebuffer, id: WidgetId) {
+        let Some(container) = self.get::<Container>(id).cloned() else {
+            return;
+        };
+
+        let inner = container.padding.shrink(self.nodes[id.0].bounds);
+        let children = self.nodes[id.0].children.clone();
+        let direction = container.direction;
+        let (inner_main, inner_cross) = direction.split(Size::new(inner.width, inner.height));
+
+        // Natural and fixed children first, flexible ones share what is left
+        let mut lengths = Vec::with_capacity(children.len());
+        let mut used = container.spacing as u32 * children.len().saturating_sub(1) as u32;
+        let mut flex_total = 0u32;
+        for &child in &children {
+            let length = match self.nodes[child.0].sizing {
+                Sizing::Fixed(length) => length,
+                Sizing::Flex(weight) => {
+                    flex_total += weight as u32;
+                    0
+                }
+                Sizing::Natural => {
+                    let available = Size::new(inner.width, inner.height);
+                    direction.split(self.measure(fb, child, available)).0
+                }
+            };
+            used += length as u32;
+            lengths.push(length);
+        }
+
+        let free = (inner_main as u32).saturating_sub(used);
+        for (length, &child) in lengths.iter_mut().zip(&children) {
+            if let Sizing::Flex(weight) = self.nodes[child.0].sizing {
+                if let Some(share) = (free * weight as u32).checked_div(flex_total) {
+                    *length = share as u16;
+                }
+            }
+        }
+
+        let mut offset = 0u32;
+        let mut moved = false;
+        for (&length, &child) in lengths.iter().zip(&children) {
+            let start = offset.min(inner_main as u32) as u16;
+            let length = length.min(inner_main - start);
+            let (x, y) = direction.join_point(inner, start);
+            let size = direction.join(length, inner_cross);
+            let bounds = Rect::new(x, y, size.width, size.height);
+
+            let node = &mut self.nodes[child.0];
+            if node.bounds != bounds {
+                node.bounds = bounds;
+                moved = true;
+            }
+            offset += length as u32 + container.spacing as u32;
+
+            self.layout_children(fb, child);
+        }
+
+        // Redrawing the container also clears the areas children moved away from
+        if moved {
+            self.nodes[id.0].dirty = true;
+        }
+    }
+
+    /// Draws dirty nodes and their subtrees, returning true if anything was drawn
+    fn draw_changed(&mut self, fb: &mut Framebuffer, id: WidgetId, background: Color) -> bool {
+        if self.nodes[id.0].dirty {
+            self.draw_subtree(fb, id, background);
+            return true;
+        }
+
+        let background = self.background_of(id).unwrap_or(background);
+        let mut drawn = false;
+        for child in self.nodes[id.0].children.clone() {
+            drawn |= self.draw_changed(fb, child, background);
+        }
+        drawn
+    }
+
+    fn draw_subtree(&mut self, fb: &mut Framebuffer, id: WidgetId, background: Color) {
+        let node = &mut self.nodes[id.0];
+        node.dirty = false;
+        let bounds = node.bounds;
+
+        fb.push_clip(bounds);
+        fb.fill_rect(bounds, background);
+        node.widget.draw(fb, bounds);
+        fb.pop_clip();
+
+        let background = self.background_of(id).unwrap_or(background);
+        for child in self.nodes[id.0].children.clone() {
+            self.draw_subtree(fb, child, background);
+        }
+    }
+
+    fn background_of(&self, id: WidgetId) -> Option<Color> {
+        self.get::<Container>(id).and_then(|container| container.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Areas drawn by `Block`s, in drawing order
+    type DrawLog = Rc<RefCell<Vec<Rect>>>;
+
+    /// A solid block of a given natural size that logs where it is drawn
+    #[derive(Clone)]
+    struct Block {
+        size: Size,
+        color: Color,
+        log: DrawLog,
+    }
+
+    impl PartialEq for Block {
+        fn eq(&self, other: &Self) -> bool {
+            self.size == other.size && self.color == other.color
+        }
+    }
+
+    impl Widget for Block {
+        fn measure(&self, _fb: &Framebuffer, _available: Size) -> Size {
+            self.size
+        }
+
+        fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+            self.log.borrow_mut().push(bounds);
+            fb.fill_rect(bounds, self.color);
+        }
+    }
+
+    fn block(log: &DrawLog, width: u16, height: u16) -> Block {
+        Block { size: Size::new(width, height), color: Color::white(), log: log.clone() }
+    }
+
+    #[test]
+    fn columns_place_natural_fixed_and_flexible_children() {
+        let mut fb = Framebuffer::headless(160, 128).unwrap();
+        let log = DrawLog::default();
+        let mut ui = Ui::new(Container::vertical().padding(Insets::symmetric(4, 2)).spacing(3));
+        let root = ui.root();
+        let natural = ui.add(root, block(&log, 50, 20));
+        let fixed = ui.add(root, block(&log, 50, 20));
+        ui.set_fixed(fixed, 10);
+        let small = ui.add(root, block(&log, 0, 0));
+        ui.set_flex(small, 1);
+        let large = ui.add(root, block(&log, 0, 0));
+        ui.set_flex(large, 2);
+
+        assert!(ui.render(&mut fb, Rect::new(0, 0, 160, 128)));
+        // 124 rows inside the padding, 30 taken, 9 of spacing: 85 to share 1:2
+        assert_eq!(ui.bounds(natural), Rect::new(4, 2, 152, 20));
+        assert_eq!(ui.bounds(fixed), Rect::new(4, 25, 152, 10));
+        assert_eq!(ui.bounds(small), Rect::new(4, 38, 152, 28));
+        assert_eq!(ui.bounds(large), Rect::new(4, 69, 152, 56));
+    }
+
+    #[test]
+    fn rows_measure_the_sum_of_their_children() {
+        let fb = Framebuffer::headless(160, 128).unwrap();
+        let log = DrawLog::default();
+        let mut ui = Ui::new(Container::vertical());
+        let row = ui.add(ui.root(), Container::horizontal().padding(Insets::uniform(1)).spacing(2));
+        ui.add(row, block(&log, 10, 5));
+        ui.add(row, block(&log, 20, 8));
+
+        assert_eq!(ui.measure(&fb, row, Size::new(160, 128)), Size::new(34, 10));
+        assert_eq!(ui.measure(&fb, ui.root(), Size::new(160, 128)), Size::new(34, 10));
+    }
+
+    #[test]
+    fn only_changed_widgets_are_redrawn() {
+        let mut fb = Framebuffer::headless(160, 128).unwrap();
+        let log = DrawLog::default();
+        let mut ui = Ui::new(Container::vertical());
+        let root = ui.root();
+        let first = ui.add(root, block(&log, 50, 20));
+        let second = ui.add(root, block(&log, 50, 20));
+        let area = Rect::new(0, 0, 160, 128);
+
+        ui.render(&mut fb, area);
+        assert_eq!(log.borrow().len(), 2);
+        log.borrow_mut().clear();
+        assert!(!ui.render(&mut fb, area));
+
+        // The same value again changes nothing
+        assert!(ui.update::<Block>(second, |block| block.color = Color::white()));
+        assert!(!ui.render(&mut fb, area));
+
+        ui.update::<Block>(second, |block| block.color = Color::black());
+        assert!(ui.render(&mut fb, area));
+        assert_eq!(*log.borrow(), [Rect::new(0, 20, 160, 20)]);
+        log.borrow_mut().clear();
+
+        // Growing the first block moves the second, so both are drawn again
+        ui.update::<Block>(first, |block| block.size.height = 30);
+        ui.render(&mut fb, area);
+        assert_eq!(*log.borrow(), [Rect::new(0, 0, 160, 30), Rect::new(0, 30, 160, 20)]);
+        assert!(!ui.update::<Container>(first, |_| {}));
+    }
+}
@@ -0,0 +1,128 @@
+// Containers stacking their children vertically or horizontally
+
+use super::{Size, Widget};
+use crate::framebuffer::{Color, Framebuffer, Rect};
+
+/// Axis along which a container places its children
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+impl Direction {
+    /// Splits a size into its (main axis, cross axis) lengths
+    pub(super) fn split(self, size: Size) -> (u16, u16) {
+        match self {
+            Direction::Vertical => (size.height, size.width),
+            Direction::Horizontal => (size.width, size.height),
+        }
+    }
+
+    /// Builds a size from main and cross axis lengths
+    pub(super) fn join(self, main: u16, cross: u16) -> Size {
+        match self {
+            Direction::Vertical => Size::new(cross, main),
+            Direction::Horizontal => Size::new(main, cross),
+        }
+    }
+
+    /// Top-left corner of a child starting `offset` pixels into `inner`
+    pub(super) fn join_point(self, inner: Rect, offset: u16) -> (u16, u16) {
+        match self {
+            Direction::Vertical => (inner.x, inner.y + offset),
+            Direction::Horizontal => (inner.x + offset, inner.y),
+        }
+    }
+}
+
+/// Space kept free inside the edges of a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Insets {
+    pub top: u16,
+    pub right: u16,
+    pub bottom: u16,
+    pub left: u16,
+}
+
+impl Insets {
+    pub const fn uniform(inset: u16) -> Self {
+        Self { top: inset, right: inset, bottom: inset, left: inset }
+    }
+
+    pub const fn symmetric(horizontal: u16, vertical: u16) -> Self {
+        Self { top: vertical, right: horizontal, bottom: vertical, left: horizontal }
+    }
+
+    pub const fn horizontal(&self) -> u16 {
+        self.left.saturating_add(self.right)
+    }
+
+    pub const fn vertical(&self) -> u16 {
+        self.top.saturating_add(self.bottom)
+    }
+
+    /// The part of `rect` left inside the insets
+    pub fn shrink(&self, rect: Rect) -> Rect {
+        let left = self.left.min(rect.width);
+        let top = self.top.min(rect.height);
+        Rect::new(
+            rect.x + left,
+            rect.y + top,
+            rect.width.saturating_sub(self.horizontal()),
+            rect.height.saturating_sub(self.vertical()),
+        )
+    }
+}
+
+/// Lays out child widgets in a row or column
+///
+/// Children stretch across the cross axis. Along the main axis they get their
+/// measured size unless `Ui::set_fixed` or `Ui::set_flex` says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Container {
+    pub direction: Direction,
+    pub padding: Insets,
+    /// Gap between neighbouring children
+    pub spacing: u16,
+    pub background: Option<Color>,
+}
+
+impl Container {
+    pub fn vertical() -> Self {
+        Self { direction: Direction::Vertical, ..Self::default() }
+    }
+
+    pub fn horizontal() -> Self {
+        Self { direction: Direction::Horizontal, ..Self::default() }
+    }
+
+    pub fn padding(mut self, padding: Insets) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: u16) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+}
+
+impl Widget for Container {
+    /// Only the padding; `Ui` adds the children, which it owns
+    fn measure(&self, _fb: &Framebuffer, _available: Size) -> Size {
+        Size::new(self.padding.horizontal(), self.padding.vertical())
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        if let Some(color) = self.background {
+            fb.fill_rect(bounds, color);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// Bitmap icons, optionally a single cell of a sprite sheet
+
+use std::sync::Arc;
+
+use super::{Size, Widget};
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::image::Bitmap;
+
+/// A bitmap centered in its bounds
+///
+/// The bitmap is shared so several icons can point at one sprite sheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Icon {
+    pub bitmap: Arc<Bitmap>,
+    /// Part of the bitmap to draw; the whole bitmap when `None`
+    pub source: Option<Rect>,
+    /// Whole-number enlargement, which keeps pixel art crisp
+    pub scale: u16,
+}
+
+impl Icon {
+    pub fn new(bitmap: Arc<Bitmap>) -> Self {
+        Self { bitmap, source: None, scale: 1 }
+    }
+
+    /// Icon showing cell `index` of a sprite sheet, see `Bitmap::tile`
+    pub fn tile(bitmap: Arc<Bitmap>, index: u16, tile_width: u16, tile_height: u16) -> Self {
+        let source = bitmap.tile(index, tile_width, tile_height);
+        Self { bitmap, source: Some(source), scale: 1 }
+    }
+
+    pub fn scale(mut self, scale: u16) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    fn source_rect(&self) -> Rect {
+        self.source.unwrap_or_else(|| self.bitmap.bounds())
+    }
+}
+
+impl Widget for Icon {
+    fn measure(&self, _fb: &Framebuffer, _available: Size) -> Size {
+        let source = self.source_rect();
+        Size::new(source.width.saturating_mul(self.scale), source.height.saturating_mul(self.scale))
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let source = self.source_rect();
+        let width = source.width.saturating_mul(self.scale);
+        let height = source.height.saturating_mul(self.scale);
+        let dest = Rect::new(
+            bounds.x + bounds.width.saturating_sub(width) / 2,
+            bounds.y + bounds.height.saturating_sub(height) / 2,
+            width,
+            height,
+        );
+        fb.draw_bitmap_region(&self.bitmap, source, dest);
+    }
+}
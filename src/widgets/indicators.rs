@@ -0,0 +1,168 @@
+// Bar-shaped indicators: progress bars, segmented meters and divider lines
+
+use super::{Size, Widget};
+use crate::framebuffer::{Color, Framebuffer, Point, Rect};
+
+/// Horizontal bar filled from the left in proportion to `value`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressBar {
+    /// Fraction done, from 0.0 to 1.0
+    pub value: f32,
+    pub height: u16,
+    pub fill: Color,
+    pub track: Color,
+    pub border: Option<Color>,
+}
+
+impl ProgressBar {
+    pub fn new(fill: Color, track: Color) -> Self {
+        Self { value: 0.0, height: 6, fill, track, border: None }
+    }
+
+    pub fn height(mut self, height: u16) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn border(mut self, color: Color) -> Self {
+        self.border = Some(color);
+        self
+    }
+
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(0.0, 1.0);
+    }
+}
+
+impl Widget for ProgressBar {
+    fn measure(&self, _fb: &Framebuffer, available: Size) -> Size {
+        Size::new(available.width, self.height)
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let bar = Rect::new(bounds.x, bounds.y + bounds.height.saturating_sub(self.height) / 2, bounds.width, self.height.min(bounds.height));
+        let inner = match self.border {
+            Some(color) => {
+                fb.draw_rect(bar, color);
+                Rect::new(bar.x + 1, bar.y + 1, bar.width.saturating_sub(2), bar.height.saturating_sub(2))
+            }
+            None => bar,
+        };
+
+        let filled = (inner.width as f32 * self.value.clamp(0.0, 1.0)).round() as u16;
+        fb.fill_rect(Rect::new(inner.x, inner.y, filled, inner.height), self.fill);
+        fb.fill_rect(Rect::new(inner.x + filled, inner.y, inner.width - filled, inner.height), self.track);
+    }
+}
+
+/// Row of segments lighting up with `value`, e.g. a signal strength meter
+///
+/// Segments past `warning_at` and `peak_at` take the warning and peak colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Meter {
+    /// Level from 0.0 to 1.0
+    pub value: f32,
+    pub segments: u16,
+    pub height: u16,
+    pub normal: Color,
+    pub warning: Color,
+    pub peak: Color,
+    /// Color of unlit segments
+    pub off: Color,
+    pub warning_at: f32,
+    pub peak_at: f32,
+}
+
+impl Meter {
+    /// A green, yellow and red meter with the given number of segments
+    pub fn new(segments: u16) -> Self {
+        Self {
+            value: 0.0,
+            segments: segments.max(1),
+            height: 8,
+            normal: Color::green(),
+            warning: Color::new(241, 196, 15, 255),
+            peak: Color::new(231, 76, 60, 255),
+            off: Color::new(50, 50, 50, 255),
+            warning_at: 0.6,
+            peak_at: 0.85,
+        }
+    }
+
+    pub fn height(mut self, height: u16) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn colors(mut self, normal: Color, warning: Color, peak: Color, off: Color) -> Self {
+        self.normal = normal;
+        self.warning = warning;
+        self.peak = peak;
+        self.off = off;
+        self
+    }
+
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(0.0, 1.0);
+    }
+}
+
+impl Widget for Meter {
+    fn measure(&self, _fb: &Framebuffer, available: Size) -> Size {
+        Size::new(available.width, self.height)
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let segments = self.segments as u32;
+        let gap = 1;
+        let y = bounds.y + bounds.height.saturating_sub(self.height) / 2;
+        let height = self.height.min(bounds.height);
+        let lit = (self.value.clamp(0.0, 1.0) * segments as f32).round() as u32;
+
+        for i in 0..segments {
+            // Spread the rounding error over all segments so the row fills the bounds
+            let x0 = bounds.x as u32 + i * bounds.width as u32 / segments;
+            let x1 = bounds.x as u32 + (i + 1) * bounds.width as u32 / segments;
+            let width = (x1 - x0).saturating_sub(gap) as u16;
+
+            let position = (i as f32 + 0.5) / segments as f32;
+            let color = if i >= lit {
+                self.off
+            } else if position >= self.peak_at {
+                self.peak
+            } else if position >= self.warning_at {
+                self.warning
+            } else {
+                self.normal
+            };
+            fb.fill_rect(Rect::new(x0 as u16, y, width.max(1), height), color);
+        }
+    }
+}
+
+/// One pixel line across the container, separating groups of widgets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Divider {
+    pub color: Color,
+}
+
+impl Divider {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Widget for Divider {
+    fn measure(&self, _fb: &Framebuffer, _available: Size) -> Size {
+        Size::new(1, 1)
+    }
+
+    /// Horizontal in a column, vertical in a row, centered in the bounds
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        if bounds.width >= bounds.height {
+            fb.draw_hline(Point::new(bounds.x, bounds.y + bounds.height / 2), bounds.width, self.color);
+        } else {
+            fb.draw_vline(Point::new(bounds.x + bounds.width / 2, bounds.y), bounds.height, self.color);
+        }
+    }
+}
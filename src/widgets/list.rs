@@ -0,0 +1,130 @@
+// Scrolling list with a highlighted selection
+
+use super::{Size, Widget};
+use crate::framebuffer::{Color, Framebuffer, Rect, TextStyle};
+
+/// Column of text rows, scrolled so the selected row stays visible
+#[derive(Debug, Clone, PartialEq)]
+pub struct List {
+    pub items: Vec<String>,
+    /// Text shown right-aligned next to the item with the same index, e.g. a setting's value
+    pub values: Vec<String>,
+    pub selected: Option<usize>,
+    pub style: TextStyle,
+    /// Background of the selected row
+    pub highlight: Color,
+    /// Text color of the selected row
+    pub highlight_text: Color,
+    /// Extra space above and below each row's text
+    pub row_padding: u16,
+}
+
+impl List {
+    pub fn new(items: Vec<String>, style: TextStyle, highlight: Color, highlight_text: Color) -> Self {
+        Self {
+            selected: if items.is_empty() { None } else { Some(0) },
+            items,
+            values: Vec::new(),
+            style: style.wrap(false),
+            highlight,
+            highlight_text,
+            row_padding: 2,
+        }
+    }
+
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.selected = match self.selected {
+            _ if items.is_empty() => None,
+            Some(index) => Some(index.min(items.len() - 1)),
+            None => Some(0),
+        };
+        self.items = items;
+    }
+
+    pub fn set_values(&mut self, values: Vec<String>) {
+        self.values = values;
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.selected = Some(index);
+        }
+    }
+
+    /// Moves the selection down, wrapping around at the end
+    pub fn select_next(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = Some(self.selected.map_or(0, |index| (index + 1) % self.items.len()));
+        }
+    }
+
+    /// Moves the selection up, wrapping around at the start
+    pub fn select_previous(&mut self) {
+        if !self.items.is_empty() {
+            let last = self.items.len() - 1;
+            self.selected = Some(self.selected.map_or(last, |index| index.checked_sub(1).unwrap_or(last)));
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&str> {
+        self.selected.and_then(|index| self.items.get(index)).map(String::as_str)
+    }
+
+    fn row_height(&self, fb: &Framebuffer) -> u16 {
+        fb.measure_text("", self.style.size, self.style.font)
+            .map_or(self.style.size.ceil() as u16, |metrics| metrics.height())
+            + 2 * self.row_padding
+    }
+}
+
+impl Widget for List {
+    fn measure(&self, fb: &Framebuffer, available: Size) -> Size {
+        let rows = self.items.len().min(u16::MAX as usize) as u16;
+        Size::new(available.width, self.row_height(fb).saturating_mul(rows))
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let row_height = self.row_height(fb).max(1);
+        let visible = (bounds.height / row_height).max(1) as usize;
+        let first = self.selected.map_or(0, |index| (index + 1).saturating_sub(visible));
+        let scrollable = self.items.len() > visible;
+        let text_width = bounds.width.saturating_sub(if scrollable { 4 } else { 0 });
+
+        for (row, (index, item)) in self.items.iter().enumerate().skip(first).take(visible).enumerate() {
+            let row_rect = Rect::new(bounds.x, bounds.y + row as u16 * row_height, text_width, row_height);
+            let mut style = self.style;
+            if Some(index) == self.selected {
+                fb.fill_rect(row_rect, self.highlight);
+                style.color = self.highlight_text;
+            }
+
+            let mut text_rect = Rect::new(
+                row_rect.x + self.row_padding,
+                row_rect.y + self.row_padding,
+                row_rect.width.saturating_sub(2 * self.row_padding),
+                row_rect.height - 2 * self.row_padding,
+            );
+            if let Some(value) = self.values.get(index).filter(|value| !value.is_empty()) {
+                // The value keeps up to half the row, the item gets the rest
+                let width = fb
+                    .measure_text(value, style.size, style.font)
+                    .map_or(0, |metrics| metrics.width)
+                    .min(text_rect.width / 2);
+                let value_rect = Rect::new(text_rect.x + text_rect.width - width, text_rect.y, width, text_rect.height);
+                let _ = fb.write_text_box(value, value_rect, &style);
+                text_rect.width = text_rect.width.saturating_sub(width + 2 * self.row_padding);
+            }
+            let _ = fb.write_text_box(item, text_rect, &style);
+        }
+
+        if scrollable {
+            // Thumb on the right edge showing which part of the list is visible
+            let track = Rect::new(bounds.x + bounds.width - 2, bounds.y, 2, bounds.height);
+            let total = self.items.len() as u32;
+            let top = track.y + (first as u32 * track.height as u32 / total) as u16;
+            let length = ((visible as u32 * track.height as u32 / total) as u16).max(2);
+            fb.fill_rect(track, Color { a: 80, ..self.highlight });
+            fb.fill_rect(Rect::new(track.x, top, 2, length), self.highlight);
+        }
+    }
+}
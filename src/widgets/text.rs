@@ -0,0 +1,167 @@
+// Text widgets: plain labels and caption/value/unit readouts
+
+use super::{Size, Widget};
+use crate::framebuffer::{Framebuffer, Point, Rect, TextAlign, TextStyle};
+
+/// Text drawn with a `TextStyle`
+///
+/// Single-line labels (`wrap` off) are centered vertically in their bounds;
+/// wrapped text starts at the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+impl Label {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Self { text: text.into(), style }
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+    }
+
+    /// Height of `lines` lines of this label's style
+    fn text_height(&self, fb: &Framebuffer, lines: usize) -> u16 {
+        let (Ok(metrics), Ok(line_height)) = (
+            fb.measure_text("", self.style.size, self.style.font),
+            fb.line_height(&self.style),
+        ) else {
+            return 0;
+        };
+
+        if lines == 0 {
+            return 0;
+        }
+        (metrics.height() as f32 + (lines - 1) as f32 * line_height).ceil() as u16
+    }
+}
+
+impl Widget for Label {
+    fn measure(&self, fb: &Framebuffer, available: Size) -> Size {
+        if !self.style.wrap {
+            let width = fb
+                .measure_text(&self.text, self.style.size, self.style.font)
+                .map_or(0, |metrics| metrics.width);
+            return Size::new(width.min(available.width), self.text_height(fb, 1));
+        }
+
+        let unbounded = Rect::new(0, 0, available.width, u16::MAX);
+        let lines = fb.layout_text(&self.text, unbounded, &self.style).unwrap_or_default();
+        let width = lines
+            .iter()
+            .filter_map(|line| fb.measure_text(line, self.style.size, self.style.font).ok())
+            .map(|metrics| metrics.width)
+            .max()
+            .unwrap_or(0);
+
+        Size::new(width.min(available.width), self.text_height(fb, lines.len().max(1)))
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let mut area = bounds;
+        if !self.style.wrap {
+            let offset = bounds.height.saturating_sub(self.text_height(fb, 1)) / 2;
+            area = Rect::new(bounds.x, bounds.y + offset, bounds.width, bounds.height - offset);
+        }
+        let _ = fb.write_text_box(&self.text, area, &self.style);
+    }
+}
+
+/// A value with a caption before and a unit after it, e.g. `RX: 438.300 MHz`
+///
+/// All three parts share one baseline and are centered vertically.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueReadout {
+    pub caption: String,
+    pub value: String,
+    pub unit: String,
+    pub value_style: TextStyle,
+    pub caption_style: TextStyle,
+    pub unit_style: TextStyle,
+}
+
+impl ValueReadout {
+    /// A readout with all three parts in `style`
+    pub fn new(caption: impl Into<String>, value: impl Into<String>, unit: impl Into<String>, style: TextStyle) -> Self {
+        Self {
+            caption: caption.into(),
+            value: value.into(),
+            unit: unit.into(),
+            value_style: style,
+            caption_style: style,
+            unit_style: style,
+        }
+    }
+
+    pub fn caption_style(mut self, style: TextStyle) -> Self {
+        self.caption_style = style;
+        self
+    }
+
+    pub fn unit_style(mut self, style: TextStyle) -> Self {
+        self.unit_style = style;
+        self
+    }
+
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+    }
+
+    fn parts(&self) -> [(&str, &TextStyle); 3] {
+        [
+            (&self.caption, &self.caption_style),
+            (&self.value, &self.value_style),
+            (&self.unit, &self.unit_style),
+        ]
+    }
+
+    /// Widths of the parts, the gap between them, and the largest ascent and descent
+    fn metrics(&self, fb: &Framebuffer) -> ([u16; 3], u16, u16, u16) {
+        let mut widths = [0; 3];
+        let (mut ascent, mut descent) = (0, 0);
+        for (i, (text, style)) in self.parts().into_iter().enumerate() {
+            if let Ok(metrics) = fb.measure_text(text, style.size, style.font) {
+                widths[i] = if text.is_empty() { 0 } else { metrics.width };
+                ascent = ascent.max(metrics.ascent);
+                descent = descent.max(metrics.descent);
+            }
+        }
+
+        let gap = fb
+            .measure_text(" ", self.value_style.size, self.value_style.font)
+            .map_or(0, |metrics| metrics.width);
+        (widths, gap, ascent, descent)
+    }
+}
+
+impl Widget for ValueReadout {
+    fn measure(&self, fb: &Framebuffer, available: Size) -> Size {
+        let (widths, gap, ascent, descent) = self.metrics(fb);
+        let gaps = widths.iter().filter(|&&width| width > 0).count().saturating_sub(1) as u16;
+        let width = widths.iter().sum::<u16>() + gap * gaps;
+        Size::new(width.min(available.width), ascent + descent)
+    }
+
+    fn draw(&self, fb: &mut Framebuffer, bounds: Rect) {
+        let (widths, gap, ascent, descent) = self.metrics(fb);
+        let total = widths.iter().sum::<u16>()
+            + gap * widths.iter().filter(|&&width| width > 0).count().saturating_sub(1) as u16;
+
+        let mut x = match self.value_style.align {
+            TextAlign::Left => bounds.x,
+            TextAlign::Center => bounds.x + bounds.width.saturating_sub(total) / 2,
+            TextAlign::Right => bounds.x + bounds.width.saturating_sub(total),
+        };
+        let baseline = bounds.y + bounds.height.saturating_sub(ascent + descent) / 2 + ascent;
+
+        for ((text, style), width) in self.parts().into_iter().zip(widths) {
+            if width == 0 {
+                continue;
+            }
+            let _ = fb.write_text(text, Point::new(x, baseline), style.size, style.color, style.font);
+            x = x.saturating_add(width + gap);
+        }
+    }
+}
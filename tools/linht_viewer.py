@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+
+#
+# SPDX-License-Identifier: GPL-3.0
+#
+# Desktop viewer for the LinHT-GUI socket display backend
+#
+# Run linht-gui with LINHT_DISPLAY=socket, then start this script:
+#   python3 tools/linht_viewer.py [socket path] [--scale N]
+# Keys typed into the terminal running linht-gui still control it.
+
+import argparse
+import socket
+import struct
+import threading
+import time
+import tkinter as tk
+
+DEFAULT_SOCKET_PATH = "/tmp/linht-display.sock"
+
+
+def read_exact(sock, length):
+    data = bytearray()
+    while len(data) < length:
+        chunk = sock.recv(length - len(data))
+        if not chunk:
+            raise ConnectionError("display closed the connection")
+        data.extend(chunk)
+    return bytes(data)
+
+
+class Viewer:
+    def __init__(self, path, scale):
+        self.path = path
+        self.scale = scale
+        self.lock = threading.Lock()
+        self.size = None
+        self.pixels = None
+        self.changed = False
+
+        self.root = tk.Tk()
+        self.root.title("LinHT display")
+        self.label = tk.Label(self.root, text="Waiting for " + path, padx=20, pady=20)
+        self.label.pack()
+        self.image = None
+
+        threading.Thread(target=self.receive, daemon=True).start()
+        self.root.after(30, self.refresh)
+
+    def receive(self):
+        while True:
+            try:
+                sock = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+                sock.connect(self.path)
+            except OSError:
+                time.sleep(1)
+                continue
+
+            try:
+                while True:
+                    self.read_message(sock)
+            except (ConnectionError, OSError) as e:
+                print("[Viewer] " + str(e))
+            finally:
+                sock.close()
+
+    def read_message(self, sock):
+        magic, width, height, count = struct.unpack("<4sHHH", read_exact(sock, 10))
+        if magic != b"LHTF":
+            raise ConnectionError("unexpected data on the display socket")
+
+        with self.lock:
+            if self.size != (width, height):
+                self.size = (width, height)
+                self.pixels = bytearray(width * height * 3)
+
+        for _ in range(count):
+            x, y, w, h = struct.unpack("<HHHH", read_exact(sock, 8))
+            rgba = read_exact(sock, w * h * 4)
+            with self.lock:
+                for row in range(h):
+                    rgb = bytearray(rgba[row * w * 4:(row + 1) * w * 4])
+                    del rgb[3::4]
+                    start = ((y + row) * width + x) * 3
+                    self.pixels[start:start + w * 3] = rgb
+        with self.lock:
+            self.changed = True
+
+    def refresh(self):
+        with self.lock:
+            if self.changed:
+                width, height = self.size
+                ppm = b"P6 %d %d 255\n" % (width, height) + bytes(self.pixels)
+                self.changed = False
+            else:
+                ppm = None
+
+        if ppm is not None:
+            image = tk.PhotoImage(data=ppm, format="PPM")
+            if self.scale > 1:
+                image = image.zoom(self.scale)
+            self.image = image
+            self.label.configure(image=image, text="", padx=0, pady=0)
+
+        self.root.after(30, self.refresh)
+
+    def run(self):
+        self.root.mainloop()
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Shows the LinHT-GUI socket display")
+    parser.add_argument("path", nargs="?", default=DEFAULT_SOCKET_PATH, help="display socket")
+    parser.add_argument("--scale", type=int, default=3, help="zoom factor")
+    args = parser.parse_args()
+
+    Viewer(args.path, args.scale).run()
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,55 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+
+#
+# SPDX-License-Identifier: GPL-3.0
+#
+# Sends a made-up spectrum to LinHT-GUI the way a flowgraph does, to work on
+# the spectrum display without a receiver
+#
+# Run a flowgraph in linht-gui (som_fm.py will do), then start this script:
+#   python3 tools/spectrum_source.py [host:port] [--bins N] [--rate HZ]
+
+import argparse
+import math
+import random
+import socket
+import struct
+import time
+
+DEFAULT_ADDRESS = "127.0.0.1:17006"
+
+
+def spectrum(bins, t):
+    """Noise around -100 dB, a carrier drifting around the middle and a weaker one keying on and off"""
+    levels = [-100.0 + random.gauss(0.0, 3.0) for _ in range(bins)]
+
+    carriers = [(bins / 2 + bins / 8 * math.sin(t / 3.0), -35.0)]
+    if int(t) % 4 < 2:
+        carriers.append((bins * 0.2, -60.0))
+
+    for center, peak in carriers:
+        for index in range(max(0, int(center) - 8), min(bins, int(center) + 9)):
+            distance = abs(index - center)
+            levels[index] = max(levels[index], peak - 6.0 * distance * distance)
+    return levels
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Send a made-up spectrum to LinHT-GUI")
+    parser.add_argument("address", nargs="?", default=DEFAULT_ADDRESS)
+    parser.add_argument("--bins", type=int, default=512, help="FFT size")
+    parser.add_argument("--rate", type=float, default=10.0, help="spectra per second")
+    args = parser.parse_args()
+
+    host, port = args.address.rsplit(":", 1)
+    sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+    start = time.monotonic()
+    while True:
+        levels = spectrum(args.bins, time.monotonic() - start)
+        sock.sendto(struct.pack("<%df" % len(levels), *levels), (host, int(port)))
+        time.sleep(1.0 / args.rate)
+
+
+if __name__ == "__main__":
+    main()